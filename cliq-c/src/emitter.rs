@@ -0,0 +1,236 @@
+use std::fmt::Write;
+
+use cliq_ir::{Inst, InstKind, Terminator, ValueId};
+use cliq_opt::typeck::TypeError;
+use cliq_opt::value::{Type, Value};
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::statement::Statement;
+
+use crate::{Options, STATUS_DIVISION_BY_ZERO, STATUS_OK};
+
+//signed overflow is undefined in C, int arithmetic goes through unsigned to wrap like cliq does
+const PRELUDE: &str = "\
+static inline int32_t cliq_add(int32_t a, int32_t b) { return (int32_t)((uint32_t)a + (uint32_t)b); }
+static inline int32_t cliq_sub(int32_t a, int32_t b) { return (int32_t)((uint32_t)a - (uint32_t)b); }
+static inline int32_t cliq_mul(int32_t a, int32_t b) { return (int32_t)((uint32_t)a * (uint32_t)b); }
+static inline int32_t cliq_neg(int32_t a) { return (int32_t)(0u - (uint32_t)a); }
+
+static inline int32_t cliq_div(int *status, int32_t a, int32_t b) {
+  if (b == 0) { *status = CLIQ_DIVISION_BY_ZERO; return 0; }
+  if (b == -1) return cliq_neg(a);
+  return a / b;
+}
+
+static inline int32_t cliq_rem(int *status, int32_t a, int32_t b) {
+  if (b == 0) { *status = CLIQ_DIVISION_BY_ZERO; return 0; }
+  if (b == -1) return 0;
+  return a % b;
+}
+";
+
+fn c_type(ty: Type) -> &'static str {
+  match ty {
+    Type::Int => "int32_t",
+    Type::Float => "float",
+  }
+}
+
+fn literal(value: Value) -> String {
+  match value {
+    Value::Int(i32::MIN) => "(-2147483647 - 1)".to_string(),
+    Value::Int(val) if val < 0 => format!("({})", val),
+    Value::Int(val) => val.to_string(),
+    //debug formatting is the shortest text that reads back as the same f32
+    Value::Float(val) if val.is_sign_negative() => format!("({:?}f)", val),
+    Value::Float(val) => format!("{:?}f", val),
+  }
+}
+
+//writes the function cliq_ir lowers a program to, one C statement per instruction
+pub struct Emitter<'o> {
+  options: &'o Options,
+  //C code of every value, constants and conversions are written where they are used, the rest are locals
+  values: Vec<String>,
+  out: String,
+}
+
+impl<'o> Emitter<'o> {
+  pub fn new(options: &'o Options) -> Self {
+    Self {
+      options,
+      values: Vec::new(),
+      out: String::new(),
+    }
+  }
+
+  pub fn emit(mut self, ast: &[Statement]) -> Result<String, TypeError> {
+    let function = cliq_ir::lower(ast)?;
+    let [block] = &function.blocks[..] else {
+      unreachable!("lower builds a single block");
+    };
+    let Terminator::Return(result) = block.terminator else {
+      unreachable!("lower returns from its only block");
+    };
+
+    self
+      .out
+      .push_str("/* generated by cliq */\n#include <stdint.h>\n#include <math.h>\n");
+    if self.options.main {
+      self.out.push_str("#include <inttypes.h>\n#include <stdio.h>\n");
+    }
+    writeln!(
+      self.out,
+      "\n#define CLIQ_OK {}\n#define CLIQ_DIVISION_BY_ZERO {}\n",
+      STATUS_OK, STATUS_DIVISION_BY_ZERO
+    )
+    .unwrap();
+    self.out.push_str(PRELUDE);
+
+    let entry = &self.options.entry;
+    match function.ret {
+      Some(ty) => writeln!(self.out, "\nint {}({} *result) {{", entry, c_type(ty)).unwrap(),
+      None => writeln!(self.out, "\nint {}(void) {{", entry).unwrap(),
+    }
+    self.out.push_str("  int status = CLIQ_OK;\n");
+    let mut used = vec![false; function.value_count()];
+    for operand in block.insts.iter().flat_map(Inst::operands).chain(result) {
+      used[operand.index()] = true;
+    }
+    self.values = (0..function.value_count()).map(|id| format!("v{}", id)).collect();
+    for inst in &block.insts {
+      self.inst(inst, used[inst.result.index()]);
+    }
+    if let Some(result) = result {
+      writeln!(self.out, "  *result = {};", self.values[result.index()]).unwrap();
+    }
+    self.out.push_str("  return CLIQ_OK;\n}\n");
+
+    if self.options.main {
+      self.main(function.ret);
+    }
+    Ok(self.out)
+  }
+
+  fn inst(&mut self, inst: &Inst, used: bool) {
+    let value = |id: ValueId| self.values[id.index()].as_str();
+    let code = match (&inst.kind, inst.ty) {
+      (InstKind::Const(val), _) => {
+        self.values[inst.result.index()] = literal(*val);
+        return;
+      }
+      (InstKind::IntToFloat(operand), _) => {
+        self.values[inst.result.index()] = format!("(float){}", value(*operand));
+        return;
+      }
+      (InstKind::Binary(op, lhs, rhs), Type::Int) => {
+        let (lhs, rhs) = (value(*lhs), value(*rhs));
+        match op {
+          BinOp::Add => format!("cliq_add({}, {})", lhs, rhs),
+          BinOp::Sub => format!("cliq_sub({}, {})", lhs, rhs),
+          BinOp::Mul => format!("cliq_mul({}, {})", lhs, rhs),
+          BinOp::Div => format!("cliq_div(&status, {}, {})", lhs, rhs),
+          BinOp::Rem => format!("cliq_rem(&status, {}, {})", lhs, rhs),
+        }
+      }
+      (InstKind::Binary(BinOp::Rem, lhs, rhs), Type::Float) => format!("fmodf({}, {})", value(*lhs), value(*rhs)),
+      (InstKind::Binary(op, lhs, rhs), Type::Float) => format!("{} {} {}", value(*lhs), op.symbol(), value(*rhs)),
+      (InstKind::Unary(UnOp::Neg, operand), Type::Int) => format!("cliq_neg({})", value(*operand)),
+      (InstKind::Unary(UnOp::Neg, operand), Type::Float) => format!("-{}", value(*operand)),
+    };
+    if used {
+      writeln!(
+        self.out,
+        "  const {} {} = {};",
+        c_type(inst.ty),
+        self.values[inst.result.index()],
+        code
+      )
+      .unwrap();
+    } else {
+      //still evaluated, it may divide by zero
+      writeln!(self.out, "  (void)({});", code).unwrap();
+    }
+    if code.contains("&status") {
+      self.out.push_str("  if (status != CLIQ_OK) return status;\n");
+    }
+  }
+
+  fn main(&mut self, result: Option<Type>) {
+    self.out.push_str("\nint main(void) {\n");
+    let call = match result {
+      Some(ty) => {
+        writeln!(self.out, "  {} result;", c_type(ty)).unwrap();
+        format!("{}(&result)", self.options.entry)
+      }
+      None => format!("{}()", self.options.entry),
+    };
+    writeln!(self.out, "  if ({} != CLIQ_OK) {{", call).unwrap();
+    self
+      .out
+      .push_str("    fputs(\"error: division by zero\\n\", stderr);\n    return 1;\n  }\n");
+    match result {
+      Some(Type::Int) => self.out.push_str("  printf(\"%\" PRId32 \"\\n\", result);\n"),
+      //9 significant digits read back as the same float
+      Some(Type::Float) => self.out.push_str("  printf(\"%.9g\\n\", (double)result);\n"),
+      None => {}
+    }
+    self.out.push_str("  return 0;\n}\n");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use crate::emit;
+
+  use super::*;
+
+  fn body(input: &str) -> String {
+    let ast = Parser::new(Lexer::new().lex(input).unwrap()).parse();
+    let c = emit(&ast, &Options::default()).unwrap();
+    let start = c.find("int cliq_run").unwrap();
+    c[start..].to_string()
+  }
+
+  #[test]
+  fn test_typed_declarations() {
+    assert_eq!(
+      body("var a = 7\nvar b = a / 2\nb * 2.5 - -a\nvar a = 0.5"),
+      "int cliq_run(float *result) {
+  int status = CLIQ_OK;
+  const int32_t v2 = cliq_div(&status, 7, 2);
+  if (status != CLIQ_OK) return status;
+  const float v5 = (float)v2 * 2.5f;
+  const int32_t v6 = cliq_neg(7);
+  const float v8 = v5 - (float)v6;
+  *result = v8;
+  return CLIQ_OK;
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_intermediate_expressions() {
+    assert_eq!(
+      body("1 % 0\nvar x = 2.0 % 3\nx"),
+      "int cliq_run(float *result) {
+  int status = CLIQ_OK;
+  (void)(cliq_rem(&status, 1, 0));
+  if (status != CLIQ_OK) return status;
+  const float v6 = fmodf(2.0f, (float)3);
+  *result = v6;
+  return CLIQ_OK;
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_undefined_variable() {
+    let ast = Parser::new(Lexer::new().lex("a + 1").unwrap()).parse();
+    assert_eq!(emit(&ast, &Options::default()), Err(TypeError::UndefinedVariable("a".to_string())));
+  }
+}
@@ -0,0 +1,32 @@
+use cliq_opt::typeck::TypeError;
+use cliq_parser::statement::Statement;
+
+use self::emitter::Emitter;
+
+pub mod emitter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+  //name of the generated `int name(T *result)` function
+  pub entry: String,
+  //also emit a main that prints the result, for running the file on its own
+  pub main: bool,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      entry: "cliq_run".to_string(),
+      main: false,
+    }
+  }
+}
+
+//status codes returned by the generated entry function
+pub const STATUS_OK: i32 = 0;
+pub const STATUS_DIVISION_BY_ZERO: i32 = 1;
+
+//translates a program into a C99 file with no dependencies besides libc and libm
+pub fn emit(ast: &[Statement], options: &Options) -> Result<String, TypeError> {
+  Emitter::new(options).emit(ast)
+}
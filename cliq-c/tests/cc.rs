@@ -0,0 +1,156 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use cliq_c::{emit, Options};
+use cliq_interp::{Interpreter, RuntimeError};
+use cliq_lexer::lexer::Lexer;
+use cliq_opt::typeck::TypeChecker;
+use cliq_opt::value::{ArithError, Type, Value};
+use cliq_parser::{
+  expression::{
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  parser::Parser,
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+use proptest::prelude::*;
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+fn parse(input: &str) -> Vec<Statement> {
+  Parser::new(Lexer::new().lex(input).unwrap()).parse()
+}
+
+//compiles the program with the system C compiler and runs it
+fn run_native(ast: &[Statement]) -> Result<Option<Value>, RuntimeError> {
+  let options = Options {
+    main: true,
+    ..Options::default()
+  };
+  let source = emit(ast, &options).unwrap();
+  let dir = std::env::temp_dir().join(format!("cliq-c-{}-{}", std::process::id(), BUILDS.fetch_add(1, Ordering::SeqCst)));
+  std::fs::create_dir_all(&dir).unwrap();
+  let c_file = dir.join("program.c");
+  let exe: PathBuf = dir.join("program");
+  std::fs::write(&c_file, &source).unwrap();
+
+  let cc = Command::new("cc")
+    .arg("-std=c99")
+    .arg("-O2")
+    .arg("-o")
+    .arg(&exe)
+    .arg(&c_file)
+    .arg("-lm")
+    .output()
+    .unwrap();
+  assert!(
+    cc.status.success(),
+    "cc failed:\n{}\n{}",
+    String::from_utf8_lossy(&cc.stderr),
+    source
+  );
+  let run = Command::new(&exe).output().unwrap();
+  std::fs::remove_dir_all(&dir).unwrap();
+
+  if !run.status.success() {
+    assert_eq!(String::from_utf8_lossy(&run.stderr), "error: division by zero\n");
+    return Err(RuntimeError::Arith(ArithError::DivisionByZero));
+  }
+  let stdout = String::from_utf8(run.stdout).unwrap();
+  let text = stdout.trim();
+  Ok(match TypeChecker::new().check(ast).unwrap() {
+    Some(Type::Int) => Some(Value::Int(text.parse().unwrap())),
+    Some(Type::Float) => Some(Value::Float(text.parse().unwrap_or_else(|_| panic!("not a float: {}", text)))),
+    None => None,
+  })
+}
+
+//nan compares unequal to itself, the debug text does not
+fn assert_same(native: Result<Option<Value>, RuntimeError>, interpreted: Result<Option<Value>, RuntimeError>, ast: &[Statement]) {
+  let source = cliq_parser::statement::to_source(ast);
+  assert_eq!(format!("{:?}", native), format!("{:?}", interpreted), "program:\n{}", source);
+}
+
+#[test]
+fn matches_interpreter() {
+  let programs = [
+    "var a = 7\nvar b = a / 2\nb * 2.5 - -a",
+    "var ab = 1\nvar ac = 2.5\nvar hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / ab % -3",
+    "2147483647 + 1",
+    "-2147483647 - 1 - 1",
+    "var m = -2147483647 - 1\nm / -1",
+    "var m = -2147483647 - 1\nm % -1",
+    "-7 / 2\n-7 % 2",
+    "46341 * 46341",
+    "7.5 % 2\n-7.5 % 2",
+    "1.0 / 0",
+    "0.0 / 0",
+    "var a = 16777217\na * 1.0",
+    "var x = 1\nvar x = x * 0.1\nvar x = x * 3\nx",
+    "var z = 0\n1 + 1\n5 % z\n3",
+    "var a = 1",
+    "",
+  ];
+  for program in programs {
+    let ast = parse(program);
+    assert_same(run_native(&ast), Interpreter::new().run(&ast), &ast);
+  }
+}
+
+const NAMES: [&str; 3] = ["a", "b", "c"];
+
+fn expression() -> impl Strategy<Value = Expression> {
+  let leaf = prop_oneof![
+    (0..4).prop_map(ValueExpression::int_value),
+    (0..=i32::MAX).prop_map(ValueExpression::int_value),
+    prop::sample::select(vec![0.0f32, 0.1, 2.5, 1.0e30]).prop_map(ValueExpression::float_value),
+    prop::sample::select(NAMES.to_vec()).prop_map(|name| VariableExpression::expression(name.to_string())),
+  ];
+  leaf.prop_recursive(4, 24, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), inner.clone(), prop::sample::select(BinOp::ALL.to_vec())).prop_map(|(lhs, rhs, op)| Expression::binary(op, lhs, rhs)),
+      inner.prop_map(|operand| Expression::unary(UnOp::Neg, operand)),
+    ]
+  })
+}
+
+//every variable is declared up front so the program always type checks
+fn program() -> impl Strategy<Value = Vec<Statement>> {
+  let statement = prop_oneof![
+    expression().prop_map(Statement::Expression),
+    (prop::sample::select(NAMES.to_vec()), expression()).prop_map(|(name, value)| AssignStmt::statement(
+      VariableExpression::expression(name.to_string()),
+      value,
+      false
+    )),
+  ];
+  (prop::collection::vec(0..100i32, 3), prop::collection::vec(statement, 1..6)).prop_map(|(inits, body)| {
+    let mut ast: Vec<Statement> = NAMES
+      .iter()
+      .zip(inits)
+      .map(|(name, init)| {
+        AssignStmt::statement(
+          VariableExpression::expression(name.to_string()),
+          ValueExpression::int_value(init),
+          false,
+        )
+      })
+      .collect();
+    ast.extend(body);
+    ast
+  })
+}
+
+proptest! {
+  //every case runs the C compiler
+  #![proptest_config(ProptestConfig::with_cases(24))]
+
+  #[test]
+  fn random_programs_match_interpreter(ast in program()) {
+    assert_same(run_native(&ast), Interpreter::new().run(&ast), &ast);
+  }
+}
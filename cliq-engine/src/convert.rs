@@ -0,0 +1,131 @@
+use std::fmt;
+
+use cliq_opt::value::{Type, Value};
+
+//conversions between rust values and cliq values for Engine::set, Engine::get and Engine::eval
+
+pub trait IntoValue {
+  fn into_value(self) -> Value;
+}
+
+pub trait FromValue: Sized {
+  fn from_value(value: Value) -> Result<Self, ConversionError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionError {
+  pub expected: Type,
+  pub found: Type,
+}
+
+impl fmt::Display for ConversionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "expected {} but the value is {}", self.expected, self.found)
+  }
+}
+
+impl IntoValue for Value {
+  fn into_value(self) -> Value {
+    self
+  }
+}
+
+impl FromValue for Value {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    Ok(value)
+  }
+}
+
+//only types that fit into an i32 without loss, wider ints have to be converted by the host
+macro_rules! int_conversions {
+  ($($ty:ty),*) => {$(
+    impl IntoValue for $ty {
+      fn into_value(self) -> Value {
+        Value::Int(self.into())
+      }
+    }
+  )*};
+}
+
+int_conversions!(i8, i16, i32, u8, u16);
+
+impl FromValue for i32 {
+  //a float is never truncated silently
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Int(val) => Ok(val),
+      Value::Float(_) => Err(ConversionError {
+        expected: Type::Int,
+        found: Type::Float,
+      }),
+    }
+  }
+}
+
+impl FromValue for i64 {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    i32::from_value(value).map(i64::from)
+  }
+}
+
+impl IntoValue for f32 {
+  fn into_value(self) -> Value {
+    Value::Float(self)
+  }
+}
+
+//cliq floats are f32, the value is rounded to the nearest one
+impl IntoValue for f64 {
+  fn into_value(self) -> Value {
+    Value::Float(self as f32)
+  }
+}
+
+//ints are widened the same way arithmetic on an int and a float does
+impl FromValue for f32 {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    Ok(value.as_float())
+  }
+}
+
+impl FromValue for f64 {
+  fn from_value(value: Value) -> Result<Self, ConversionError> {
+    match value {
+      Value::Int(val) => Ok(val.into()),
+      Value::Float(val) => Ok(val.into()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_into_value() {
+    assert_eq!(7u8.into_value(), Value::Int(7));
+    assert_eq!((-7i16).into_value(), Value::Int(-7));
+    assert_eq!(2.5f32.into_value(), Value::Float(2.5));
+    assert_eq!(9.99f64.into_value(), Value::Float(9.99));
+    assert_eq!(Value::Int(1).into_value(), Value::Int(1));
+  }
+
+  #[test]
+  fn test_from_value() {
+    assert_eq!(i32::from_value(Value::Int(-3)), Ok(-3));
+    assert_eq!(i64::from_value(Value::Int(i32::MIN)), Ok(i32::MIN as i64));
+    assert_eq!(f64::from_value(Value::Int(i32::MAX)), Ok(i32::MAX as f64));
+    assert_eq!(f32::from_value(Value::Float(0.5)), Ok(0.5));
+    assert_eq!(
+      i32::from_value(Value::Float(1.0)),
+      Err(ConversionError {
+        expected: Type::Int,
+        found: Type::Float
+      })
+    );
+    assert_eq!(
+      i32::from_value(Value::Float(1.0)).unwrap_err().to_string(),
+      "expected int but the value is float"
+    );
+  }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use cliq_opt::value::Value;
+
+use crate::convert::{FromValue, IntoValue};
+
+//a rust function or closure that scripts can call, see Engine::register_fn
+//Args is the tuple of its argument types, it only exists so closures of every arity can implement the trait;
+//it has to be Send + Sync so an Engine holding it can be shared between threads
+pub trait NativeFunction<Args>: Send + Sync + 'static {
+  const ARITY: u8;
+
+  //the compiler rejects calls without ARITY arguments, so other counts only come from stale programs
+  fn call(&self, args: &[Value]) -> Result<Value, String>;
+}
+
+macro_rules! native_function {
+  ($arity:literal; $($arg:ident),*) => {
+    impl<F, R, $($arg),*> NativeFunction<($($arg,)*)> for F
+    where
+      F: Fn($($arg),*) -> R + Send + Sync + 'static,
+      R: IntoValue,
+      $($arg: FromValue),*
+    {
+      const ARITY: u8 = $arity;
+
+      #[allow(non_snake_case, unused_mut, unused_variables)]
+      fn call(&self, args: &[Value]) -> Result<Value, String> {
+        //a program compiled before the function was replaced with one of another arity
+        if args.len() != $arity {
+          return Err(format!("takes {} arguments but {} were given", $arity, args.len()));
+        }
+        let mut args = args.iter().copied().enumerate();
+        $(
+          let (index, value) = args.next().unwrap();
+          let $arg = $arg::from_value(value).map_err(|err| format!("argument {}: {}", index + 1, err))?;
+        )*
+        Ok(self($($arg),*).into_value())
+      }
+    }
+  };
+}
+
+native_function!(0;);
+native_function!(1; A1);
+native_function!(2; A1, A2);
+native_function!(3; A1, A2, A3);
+native_function!(4; A1, A2, A3, A4);
+native_function!(5; A1, A2, A3, A4, A5);
+native_function!(6; A1, A2, A3, A4, A5, A6);
+
+type Call = dyn Fn(&[Value]) -> Result<Value, String> + Send + Sync;
+
+//a registered function with its argument types erased
+pub(crate) struct Native {
+  pub(crate) arity: u8,
+  pub(crate) call: Box<Call>,
+}
+
+impl Native {
+  pub(crate) fn new<Args: 'static, F: NativeFunction<Args>>(function: F) -> Self {
+    Self {
+      arity: F::ARITY,
+      call: Box::new(move |args| function.call(args)),
+    }
+  }
+}
+
+impl fmt::Debug for Native {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Native").field("arity", &self.arity).finish_non_exhaustive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn call<Args, F: NativeFunction<Args>>(function: F, args: &[Value]) -> Result<Value, String> {
+    function.call(args)
+  }
+
+  #[test]
+  fn test_conversion() {
+    assert_eq!(call(|| 7, &[]), Ok(Value::Int(7)));
+    assert_eq!(call(|x: f64| x.sqrt(), &[Value::Int(16)]), Ok(Value::Float(4.0)));
+    assert_eq!(
+      call(|a: i32, b: i32| a - b, &[Value::Int(1), Value::Float(2.0)]),
+      Err("argument 2: expected int but the value is float".to_string())
+    );
+    assert_eq!(call(|| 0, &[Value::Int(1)]), Err("takes 0 arguments but 1 were given".to_string()));
+  }
+
+  #[test]
+  fn test_arity() {
+    fn arity<Args, F: NativeFunction<Args>>(_: F) -> u8 {
+      F::ARITY
+    }
+    assert_eq!(arity(|| 0), 0);
+    assert_eq!(arity(|a: Value, _: Value, _: i32| a), 3);
+    assert_eq!(Native::new(|x: f32| x).arity, 1);
+  }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+use cliq_opt::optimize;
+use cliq_parser::cst::{self, SyntaxError};
+use cliq_vm::{Chunk, CompileError, Compiler, Vm, VmError};
+
+pub use self::convert::{ConversionError, FromValue, IntoValue};
+use self::function::Native;
+pub use self::function::NativeFunction;
+pub use self::limits::Limits;
+pub use cliq_opt::value::{Type, Value};
+
+pub mod convert;
+pub mod function;
+pub mod limits;
+pub mod math;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+  Lex(String),
+  Syntax(SyntaxError),
+  Compile(CompileError),
+  Runtime(VmError),
+  Conversion(ConversionError),
+  SourceTooLong { len: usize, max: usize },
+  //the run would leave the engine with more variables than Limits::max_variables
+  TooManyVariables(usize),
+  //Engine::eval of a program without an expression statement, or Engine::get of a name that is not set
+  NoValue,
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Lex(msg) => write!(f, "{}", msg),
+      Error::Syntax(err) => write!(f, "{}", err.msg),
+      Error::Compile(err) => write!(f, "{}", err),
+      Error::Runtime(err) => write!(f, "{}", err),
+      Error::Conversion(err) => write!(f, "{}", err),
+      Error::SourceTooLong { len, max } => write!(f, "the source is {} bytes long, the limit is {}", len, max),
+      Error::TooManyVariables(max) => write!(f, "more than {} variables", max),
+      Error::NoValue => write!(f, "there is no value"),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<CompileError> for Error {
+  fn from(err: CompileError) -> Self {
+    Error::Compile(err)
+  }
+}
+
+impl From<VmError> for Error {
+  fn from(err: VmError) -> Self {
+    Error::Runtime(err)
+  }
+}
+
+impl From<ConversionError> for Error {
+  fn from(err: ConversionError) -> Self {
+    Error::Conversion(err)
+  }
+}
+
+//source that was checked and compiled once, it can be run any number of times
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+  chunk: Chunk,
+}
+
+impl Program {
+  pub fn chunk(&self) -> &Chunk {
+    &self.chunk
+  }
+}
+
+//the entry point for hosts: variables the host binds are visible to every program the engine runs,
+//variables a program declares are kept and visible to the programs after it
+#[derive(Debug, Default)]
+pub struct Engine {
+  globals: HashMap<String, Value>,
+  functions: HashMap<String, Native>,
+  limits: Limits,
+}
+
+impl Engine {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn set(&mut self, name: impl Into<String>, value: impl IntoValue) {
+    self.globals.insert(name.into(), value.into_value());
+  }
+
+  pub fn get<T: FromValue>(&self, name: &str) -> Result<T, Error> {
+    let value = self.globals.get(name).copied().ok_or(Error::NoValue)?;
+    Ok(T::from_value(value)?)
+  }
+
+  pub fn globals(&self) -> &HashMap<String, Value> {
+    &self.globals
+  }
+
+  pub fn limits(&self) -> &Limits {
+    &self.limits
+  }
+
+  //applies to every later compile and run
+  pub fn set_limits(&mut self, limits: Limits) {
+    self.limits = limits;
+  }
+
+  //arguments are converted with FromValue when the function is called, a value that does not convert fails the program,
+  //registering a name again replaces the function for programs compiled after it
+  pub fn register_fn<Args: 'static, F: NativeFunction<Args>>(&mut self, name: impl Into<String>, function: F) {
+    self.functions.insert(name.into(), Native::new(function));
+  }
+
+  //a program may only read variables that are set on the engine at this point, or that it declares itself,
+  //and only call functions registered at this point with as many arguments as they take
+  pub fn compile(&self, src: &str) -> Result<Program, Error> {
+    if let Some(max) = self.limits.max_source_len.filter(|max| src.len() > *max) {
+      return Err(Error::SourceTooLong { len: src.len(), max });
+    }
+    let ast = cst::parse(src).map_err(Error::Lex)?.to_ast().map_err(Error::Syntax)?;
+    let (ast, _) = optimize(ast);
+    let mut compiler = Compiler::new();
+    let mut names: Vec<&String> = self.globals.keys().collect();
+    //slots in the same order every time
+    names.sort();
+    for name in names {
+      compiler.declare(name)?;
+    }
+    let mut functions: Vec<(&String, &Native)> = self.functions.iter().collect();
+    functions.sort_by_key(|(name, _)| *name);
+    for (name, function) in functions {
+      compiler.declare_function(name, function.arity)?;
+    }
+    Ok(Program {
+      chunk: compiler.compile(&ast)?,
+    })
+  }
+
+  //value of the last expression statement, None if the program has none
+  pub fn run(&mut self, program: &Program) -> Result<Option<Value>, Error> {
+    let start = Instant::now();
+    if let Some(max) = self.limits.max_variables {
+      let declared = program
+        .chunk
+        .slots()
+        .iter()
+        .filter(|name| !self.globals.contains_key(*name))
+        .count();
+      if self.globals.len() + declared > max {
+        return Err(Error::TooManyVariables(max));
+      }
+    }
+    let mut vm = Vm::new(&program.chunk);
+    vm.set_limits(self.limits.vm(start));
+    for (name, value) in &self.globals {
+      vm.set(name, *value);
+    }
+    for (name, function) in &self.functions {
+      vm.set_function(name, &*function.call);
+    }
+    let result = vm.run()?;
+    for name in program.chunk.slots() {
+      if let Some(value) = vm.get(name) {
+        self.globals.insert(name.clone(), value);
+      }
+    }
+    Ok(result)
+  }
+
+  pub fn eval<T: FromValue>(&mut self, program: &Program) -> Result<T, Error> {
+    let value = self.run(program)?.ok_or(Error::NoValue)?;
+    Ok(T::from_value(value)?)
+  }
+
+  //compile and eval in one go, for source that only runs once
+  pub fn eval_source<T: FromValue>(&mut self, src: &str) -> Result<T, Error> {
+    let program = self.compile(src)?;
+    self.eval(&program)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_opt::value::ArithError;
+
+  use super::*;
+
+  #[test]
+  fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Engine>();
+    assert_send_sync::<Program>();
+  }
+
+  #[test]
+  fn test_host_variables() {
+    let mut engine = Engine::new();
+    engine.set("price", 9.99);
+    engine.set("quantity", 3);
+    let program = engine.compile("var total = price * quantity\ntotal - total % 1").unwrap();
+    assert_eq!(engine.eval::<f64>(&program).unwrap(), 29.0);
+    assert_eq!(engine.get::<f32>("total").unwrap(), 29.97);
+
+    //the same program with other values
+    engine.set("quantity", 10);
+    engine.set("price", 2);
+    assert_eq!(engine.eval::<i32>(&program).unwrap(), 20);
+  }
+
+  #[test]
+  fn test_declarations_persist() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval_source::<Value>("var a = 20\n(-a)"), Ok(Value::Int(-20)));
+    assert_eq!(engine.eval_source::<i32>("a + 22"), Ok(42));
+    assert_eq!(engine.get::<i32>("a"), Ok(20));
+    assert_eq!(engine.get::<i32>("b"), Err(Error::NoValue));
+  }
+
+  #[test]
+  fn test_native_functions() {
+    let mut engine = Engine::new();
+    engine.register_fn("sqrt", |x: f64| x.sqrt());
+    engine.register_fn("clamp", |x: i32, lo: i32, hi: i32| x.clamp(lo, hi));
+    engine.register_fn("answer", || 42u8);
+    assert_eq!(engine.eval_source::<f32>("sqrt(answer() - 33)"), Ok(3.0));
+    assert_eq!(engine.eval_source::<i32>("var x = 7\nclamp(x * 2, 0, 10)"), Ok(10));
+
+    assert_eq!(
+      engine.compile("sqrt(1, 2)"),
+      Err(Error::Compile(CompileError::WrongArgumentCount {
+        name: "sqrt".to_string(),
+        expected: 1,
+        found: 2
+      }))
+    );
+    assert_eq!(
+      engine.compile("cbrt(8)"),
+      Err(Error::Compile(CompileError::UndefinedFunction("cbrt".to_string())))
+    );
+    assert_eq!(
+      engine.eval_source::<i32>("clamp(1, 0.5, 2)").unwrap_err().to_string(),
+      "in call to `clamp`: argument 2: expected int but the value is float"
+    );
+
+    //programs compiled before a function is replaced call the new one
+    let program = engine.compile("answer()").unwrap();
+    engine.register_fn("answer", || -1);
+    assert_eq!(engine.eval::<i32>(&program), Ok(-1));
+    engine.register_fn("answer", |x: i32| x);
+    assert!(matches!(engine.run(&program), Err(Error::Runtime(VmError::Function { .. }))));
+  }
+
+  #[test]
+  fn test_limits() {
+    let mut engine = Engine::new();
+    engine.set("x", 1);
+    engine.set_limits(Limits {
+      fuel: Some(100),
+      max_stack: Some(32),
+      max_source_len: Some(4096),
+      max_variables: Some(3),
+      ..Limits::default()
+    });
+    //a long chain needs little stack but one instruction per term
+    let long = format!("var y = x{}", " + x".repeat(60));
+    assert_eq!(engine.eval_source::<i32>(&long), Err(Error::Runtime(VmError::OutOfFuel)));
+    assert_eq!(engine.get::<i32>("y"), Err(Error::NoValue));
+    let deep = format!("{}x{}", "x * (".repeat(32), ")".repeat(32));
+    assert_eq!(engine.eval_source::<i32>(&deep), Err(Error::Runtime(VmError::StackOverflow)));
+    assert_eq!(
+      engine.compile(&"x + ".repeat(1200)),
+      Err(Error::SourceTooLong { len: 4800, max: 4096 })
+    );
+
+    assert_eq!(engine.eval_source::<i32>("var a = x\nvar b = a\nb"), Ok(1));
+    assert_eq!(engine.eval_source::<i32>("var c = 2\nc"), Err(Error::TooManyVariables(3)));
+    //declaring a variable again does not count
+    assert_eq!(engine.eval_source::<i32>("var a = 5\na"), Ok(5));
+
+    engine.register_fn("slow", || {
+      std::thread::sleep(std::time::Duration::from_millis(20));
+      0
+    });
+    engine.set_limits(Limits {
+      timeout: Some(std::time::Duration::from_millis(5)),
+      ..Limits::default()
+    });
+    assert_eq!(engine.eval_source::<i32>("x + 1"), Ok(2));
+    assert_eq!(
+      engine.eval_source::<i32>("slow()").unwrap_err().to_string(),
+      "the program ran past its deadline"
+    );
+  }
+
+  #[test]
+  fn test_long_chain() {
+    //a chain nests one level per operator, compiling it walks that deep
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval_source::<i32>(&vec!["1"; 2000].join(" + ")), Ok(2000));
+  }
+
+  #[test]
+  fn test_errors() {
+    let mut engine = Engine::new();
+    assert!(matches!(engine.compile("1 $ 2"), Err(Error::Lex(_))));
+    assert!(matches!(engine.compile("var x 3"), Err(Error::Syntax(err)) if err.fix.is_some()));
+    //variables have to be set before a program that reads them is compiled
+    assert_eq!(
+      engine.compile("x + 1"),
+      Err(Error::Compile(CompileError::UndefinedVariable("x".to_string())))
+    );
+    assert_eq!(
+      engine.eval_source::<i32>("var z = 0\n1 / z"),
+      Err(Error::Runtime(VmError::Arith(ArithError::DivisionByZero)))
+    );
+    assert_eq!(engine.eval_source::<i32>("var y = 1"), Err(Error::NoValue));
+    assert_eq!(
+      engine.eval_source::<i32>("y * 0.5").unwrap_err().to_string(),
+      "expected int but the value is float"
+    );
+  }
+}
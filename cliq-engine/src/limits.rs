@@ -0,0 +1,27 @@
+use std::time::{Duration, Instant};
+
+//bounds for untrusted source, None is no bound; the defaults bound nothing
+//every limit fails with its own error, runs that hit one leave the engine's variables as they were
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+  //instructions one run may execute, the same program always uses the same amount
+  pub fuel: Option<u64>,
+  //values on the vm stack at once, deeply nested expressions and calls need one per pending operand
+  pub max_stack: Option<usize>,
+  //wall clock time of one run, a host function that blocks is not interrupted but the run stops right after it
+  pub timeout: Option<Duration>,
+  //bytes of source Engine::compile accepts
+  pub max_source_len: Option<usize>,
+  //variables the engine holds after a run, the ones set by the host count too
+  pub max_variables: Option<usize>,
+}
+
+impl Limits {
+  pub(crate) fn vm(&self, start: Instant) -> cliq_vm::Limits {
+    cliq_vm::Limits {
+      fuel: self.fuel,
+      max_stack: self.max_stack,
+      deadline: self.timeout.map(|timeout| start + timeout),
+    }
+  }
+}
@@ -0,0 +1,98 @@
+use cliq_opt::value::Value;
+use cliq_parser::expression::operator::UnOp;
+
+use crate::Engine;
+
+//the standard math functions, results are computed in f64 and rounded to the nearest cliq float once
+//abs, min, max, floor and ceil keep ints as ints, everything else returns a float
+pub fn register(engine: &mut Engine) {
+  engine.register_fn("abs", abs);
+  engine.register_fn("min", |a: Value, b: Value| pick(a, b, |a, b| a <= b));
+  engine.register_fn("max", |a: Value, b: Value| pick(a, b, |a, b| a >= b));
+  engine.register_fn("floor", |x: Value| round(x, f32::floor));
+  engine.register_fn("ceil", |x: Value| round(x, f32::ceil));
+  engine.register_fn("sqrt", f64::sqrt);
+  engine.register_fn("pow", f64::powf);
+  engine.register_fn("sin", f64::sin);
+  engine.register_fn("cos", f64::cos);
+  engine.register_fn("tan", f64::tan);
+  engine.register_fn("asin", f64::asin);
+  engine.register_fn("acos", f64::acos);
+  engine.register_fn("atan", f64::atan);
+  engine.register_fn("atan2", f64::atan2);
+}
+
+//`abs(i32::MIN)` wraps around to itself, the same way `-x` does
+fn abs(x: Value) -> Value {
+  match x {
+    Value::Int(val) if val < 0 => Value::unary(UnOp::Neg, x),
+    Value::Float(val) => Value::Float(val.abs()),
+    _ => x,
+  }
+}
+
+//ints are compared exactly, a float on either side makes both floats like arithmetic does
+fn pick(a: Value, b: Value, first: impl Fn(f64, f64) -> bool) -> Value {
+  if let (Value::Int(lhs), Value::Int(rhs)) = (a, b) {
+    return if first(lhs.into(), rhs.into()) { a } else { b };
+  }
+  let (lhs, rhs) = (a.as_float(), b.as_float());
+  //nan is never picked over a number
+  if rhs.is_nan() || (!lhs.is_nan() && first(lhs.into(), rhs.into())) {
+    Value::Float(lhs)
+  } else {
+    Value::Float(rhs)
+  }
+}
+
+fn round(x: Value, f: fn(f32) -> f32) -> Value {
+  match x {
+    Value::Int(_) => x,
+    Value::Float(val) => Value::Float(f(val)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn eval(src: &str) -> Value {
+    let mut engine = Engine::new();
+    register(&mut engine);
+    engine.eval_source(src).unwrap()
+  }
+
+  #[test]
+  fn test_ints_stay_ints() {
+    assert_eq!(eval("abs(-3)"), Value::Int(3));
+    assert_eq!(eval("abs(-2147483647 - 1)"), Value::Int(i32::MIN));
+    assert_eq!(eval("min(4, -2) + max(4, 9)"), Value::Int(7));
+    assert_eq!(eval("min(2, 2.5)"), Value::Float(2.0));
+    assert_eq!(eval("floor(7) * ceil(-3)"), Value::Int(-21));
+  }
+
+  #[test]
+  fn test_floats() {
+    assert_eq!(eval("abs(-0.5)"), Value::Float(0.5));
+    assert_eq!(eval("floor(-1.5)"), Value::Float(-2.0));
+    assert_eq!(eval("ceil(1.25)"), Value::Float(2.0));
+    assert_eq!(eval("sqrt(16)"), Value::Float(4.0));
+    assert_eq!(eval("pow(2, 10)"), Value::Float(1024.0));
+    assert_eq!(eval("max(0.0 / 0.0, 1.5)"), Value::Float(1.5));
+    assert_eq!(eval("min(1.5, 0.0 / 0.0)"), Value::Float(1.5));
+  }
+
+  #[test]
+  fn test_trig() {
+    let close = |src: &str, expected: f32| {
+      let value = eval(src).as_float();
+      assert!((value - expected).abs() < 1e-6, "{} = {}", src, value);
+    };
+    close("sin(0)", 0.0);
+    close("cos(0)", 1.0);
+    close("tan(atan(0.5))", 0.5);
+    close("asin(1) * 2", std::f32::consts::PI);
+    close("acos(-1)", std::f32::consts::PI);
+    close("atan2(1, -1)", 3.0 * std::f32::consts::FRAC_PI_4);
+  }
+}
@@ -0,0 +1,98 @@
+use cliq_parser::cst::{self, SyntaxError};
+
+pub mod printer;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+  //spaces used for continuation lines of a statement that had to be broken
+  pub indent: usize,
+  pub line_width: usize,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      indent: 2,
+      line_width: 100,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FmtError {
+  Lex(String),
+  Syntax(Vec<SyntaxError>),
+}
+
+impl std::fmt::Display for FmtError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FmtError::Lex(msg) => write!(f, "{}", msg),
+      FmtError::Syntax(errors) => {
+        let msgs: Vec<&str> = errors.iter().map(|err| err.msg.as_str()).collect();
+        write!(f, "{}", msgs.join("\n"))
+      }
+    }
+  }
+}
+
+//files with syntax errors are never touched, a half understood file would lose code
+pub fn format_source(src: &str, config: &Config) -> Result<String, FmtError> {
+  let parse = cst::parse(src).map_err(FmtError::Lex)?;
+  if !parse.errors().is_empty() {
+    return Err(FmtError::Syntax(parse.errors().to_vec()));
+  }
+  Ok(printer::Printer::new(config).print(&parse.syntax()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fmt(src: &str) -> String {
+    format_source(src, &Config::default()).unwrap()
+  }
+
+  #[test]
+  fn test_spacing() {
+    assert_eq!(fmt("var   a=1+2*  3"), "var a = 1 + 2 * 3\n");
+    assert_eq!(fmt("a/b-c\n\n\n\nd"), "a / b - c\n\nd\n");
+  }
+
+  #[test]
+  fn test_minimal_parentheses() {
+    assert_eq!(fmt("((1)) + ((2 * 3))"), "1 + 2 * 3\n");
+    assert_eq!(fmt("(1 + 2) * 3"), "(1 + 2) * 3\n");
+    assert_eq!(fmt("1 - (2 - 3)"), "1 - (2 - 3)\n");
+    assert_eq!(fmt("(1 - 2) - 3"), "1 - 2 - 3\n");
+    assert_eq!(fmt("1 / (2 * 3)"), "1 / (2 * 3)\n");
+    assert_eq!(fmt("a\n(-b * c)\n(-(-d))"), "a\n(-b * c)\n(--d)\n");
+  }
+
+  #[test]
+  fn test_calls() {
+    assert_eq!(fmt("max( (a) ,2*b )+f( )"), "max(a, 2 * b) + f()\n");
+    assert_eq!(fmt("-pow(2,(-x))"), "-pow(2, -x)\n");
+  }
+
+  #[test]
+  fn test_comments() {
+    assert_eq!(
+      fmt("// head\nvar a = 1   // one\n\n\n// two\na"),
+      "// head\nvar a = 1 // one\n\n// two\na\n"
+    );
+    assert_eq!(fmt("var a = 1 + // inner\n  2"), "// inner\nvar a = 1 + 2\n");
+  }
+
+  #[test]
+  fn test_line_width() {
+    let config = Config { indent: 4, line_width: 20 };
+    let formatted = format_source("var total = alpha + beta * 2 - gamma", &config).unwrap();
+    assert_eq!(formatted, "var total = alpha +\n    beta * 2 -\n    gamma\n");
+  }
+
+  #[test]
+  fn test_syntax_error() {
+    assert!(matches!(format_source("var = 1", &Config::default()), Err(FmtError::Syntax(_))));
+  }
+}
@@ -0,0 +1,138 @@
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use cliq_fmt::{format_source, Config, FmtError};
+use cliq_lexer::source_map::{FileId, SourceMap};
+
+const USAGE: &str = "usage: cliqfmt [--check] [--indent N] [--line-width N] [FILE...]
+
+Formats .cliq files in place, or stdin to stdout when no file is given.
+  --check         only report files that are not formatted, exit code 1 if any
+  --indent N      spaces used for continuation lines (default 2)
+  --line-width N  maximum line width before statements are broken (default 100)";
+
+struct Args {
+  check: bool,
+  config: Config,
+  files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut args = Args {
+    check: false,
+    config: Config::default(),
+    files: vec![],
+  };
+  let mut iter = std::env::args().skip(1);
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "--check" => args.check = true,
+      "--indent" | "--line-width" => {
+        let value = iter.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        let value = value
+          .parse::<usize>()
+          .map_err(|_| format!("invalid value for {}: {:?}", arg, value))?;
+        if arg == "--indent" {
+          args.config.indent = value;
+        } else {
+          args.config.line_width = value;
+        }
+      }
+      "-h" | "--help" => return Err(USAGE.to_string()),
+      _ if arg.starts_with('-') => return Err(format!("unknown option {:?}\n\n{}", arg, USAGE)),
+      _ => args.files.push(arg),
+    }
+  }
+  Ok(args)
+}
+
+fn report(map: &SourceMap, file: FileId, err: &FmtError) {
+  let file = map.get(file).unwrap();
+  match err {
+    FmtError::Syntax(errors) => {
+      for error in errors {
+        let (line, col) = file.line_col(error.range.start);
+        eprintln!("{}:{}:{}: {}", file.name(), line, col, error.msg);
+      }
+    }
+    FmtError::Lex(msg) => eprintln!("{}: {}", file.name(), msg),
+  }
+}
+
+fn main() -> ExitCode {
+  let args = match parse_args() {
+    Ok(args) => args,
+    Err(msg) => {
+      eprintln!("{}", msg);
+      return ExitCode::from(2);
+    }
+  };
+
+  if args.files.is_empty() {
+    let mut src = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut src) {
+      eprintln!("<stdin>: {}", err);
+      return ExitCode::from(2);
+    }
+    return match format_source(&src, &args.config) {
+      Ok(formatted) if args.check => ExitCode::from((formatted != src) as u8),
+      Ok(formatted) => match io::stdout().write_all(formatted.as_bytes()) {
+        Ok(()) => ExitCode::SUCCESS,
+        //a closed pipe, like `cliqfmt f.cliq | head`
+        Err(err) => {
+          eprintln!("<stdout>: {}", err);
+          ExitCode::from(2)
+        }
+      },
+      Err(err) => {
+        let mut map = SourceMap::new();
+        let file = map.add_file("<stdin>", &src);
+        report(&map, file, &err);
+        ExitCode::from(2)
+      }
+    };
+  }
+
+  let mut map = SourceMap::new();
+  let mut failed = false;
+  let mut unformatted = false;
+  for path in args.files.iter() {
+    let file = match map.load(path) {
+      Ok(file) => file,
+      Err(err) => {
+        eprintln!("{}: {}", path, err);
+        failed = true;
+        continue;
+      }
+    };
+    let src = map.get(file).unwrap().src();
+    match format_source(src, &args.config) {
+      Ok(formatted) if formatted == src => {}
+      Ok(_) if args.check => {
+        if let Err(err) = writeln!(io::stdout(), "{}", path) {
+          eprintln!("<stdout>: {}", err);
+          return ExitCode::from(2);
+        }
+        unformatted = true;
+      }
+      Ok(formatted) => {
+        if let Err(err) = std::fs::write(path, formatted) {
+          eprintln!("{}: {}", path, err);
+          failed = true;
+        }
+      }
+      Err(err) => {
+        report(&map, file, &err);
+        failed = true;
+      }
+    }
+  }
+
+  if failed {
+    ExitCode::from(2)
+  } else if unformatted {
+    ExitCode::from(1)
+  } else {
+    ExitCode::SUCCESS
+  }
+}
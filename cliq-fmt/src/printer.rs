@@ -0,0 +1,208 @@
+use cliq_parser::cst::red::{SyntaxElement, SyntaxNode};
+use cliq_parser::cst::syntax_kind::SyntaxKind;
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::stack;
+
+use crate::Config;
+
+//expression stripped of parentheses and trivia, parentheses are re-derived from precedence
+enum Expr {
+  Atom(String),
+  Binary(BinOp, Box<Expr>, Box<Expr>),
+  Unary(UnOp, Box<Expr>),
+  Call(String, Vec<Expr>),
+}
+
+impl Expr {
+  fn from_node(node: &SyntaxNode) -> Expr {
+    stack::grow(|| {
+      let first_token = || node.significant_tokens()[0].text().to_string();
+      match node.kind() {
+        SyntaxKind::PAREN_EXPR => Expr::from_node(&node.children()[0]),
+        SyntaxKind::BINARY_EXPR => {
+          let children = node.children();
+          let op = BinOp::from_symbol(&first_token()).unwrap();
+          Expr::Binary(op, Box::new(Expr::from_node(&children[0])), Box::new(Expr::from_node(&children[1])))
+        }
+        SyntaxKind::UNARY_EXPR => {
+          let op = UnOp::from_symbol(&first_token()).unwrap();
+          Expr::Unary(op, Box::new(Expr::from_node(&node.children()[0])))
+        }
+        SyntaxKind::CALL_EXPR => Expr::Call(first_token(), node.children().iter().map(Expr::from_node).collect()),
+        _ => Expr::Atom(first_token()),
+      }
+    })
+  }
+
+  fn precedence(&self) -> i32 {
+    match self {
+      Expr::Atom(_) | Expr::Call(_, _) => i32::MAX,
+      Expr::Binary(op, _, _) => op.precedence(),
+      Expr::Unary(op, _) => op.precedence(),
+    }
+  }
+
+  fn render_operand(&self, op: BinOp, is_rhs: bool) -> String {
+    if op.needs_parentheses(self.precedence(), is_rhs) {
+      format!("({})", self.render())
+    } else {
+      self.render()
+    }
+  }
+
+  fn render(&self) -> String {
+    stack::grow(|| match self {
+      Expr::Atom(text) => text.clone(),
+      Expr::Binary(op, lhs, rhs) => {
+        format!(
+          "{} {} {}",
+          lhs.render_operand(*op, false),
+          op.symbol(),
+          rhs.render_operand(*op, true)
+        )
+      }
+      Expr::Unary(op, operand) if operand.precedence() < op.precedence() => format!("{}({})", op.symbol(), operand.render()),
+      Expr::Unary(op, operand) => format!("{}{}", op.symbol(), operand.render()),
+      Expr::Call(name, args) => {
+        let args: Vec<String> = args.iter().map(Expr::render).collect();
+        format!("{}({})", name, args.join(", "))
+      }
+    })
+  }
+
+  fn starts_with_unary(&self) -> bool {
+    stack::grow(|| match self {
+      Expr::Atom(_) | Expr::Call(_, _) => false,
+      Expr::Binary(op, lhs, _) => !op.needs_parentheses(lhs.precedence(), false) && lhs.starts_with_unary(),
+      Expr::Unary(_, _) => true,
+    })
+  }
+
+  //first operand and the (operator, operand) pairs of the outermost chain of equal precedence
+  fn chain(&self) -> (&Expr, Vec<(BinOp, &Expr)>) {
+    let precedence = self.precedence();
+    let mut rest = vec![];
+    let mut current = self;
+    while let Expr::Binary(op, lhs, rhs) = current {
+      if op.precedence() != precedence {
+        break;
+      }
+      rest.push((*op, rhs.as_ref()));
+      current = lhs;
+    }
+    rest.reverse();
+    (current, rest)
+  }
+}
+
+pub struct Printer<'c> {
+  config: &'c Config,
+  out: String,
+}
+
+impl<'c> Printer<'c> {
+  pub fn new(config: &'c Config) -> Self {
+    Self {
+      config,
+      out: String::new(),
+    }
+  }
+
+  //comments between statements keep their place, blank lines are collapsed to one
+  //comments inside a statement are moved onto their own lines in front of it
+  pub fn print(mut self, root: &SyntaxNode) -> String {
+    let mut newlines = 0;
+    for element in root.children_with_tokens() {
+      match element {
+        SyntaxElement::Token(token) if token.kind() == SyntaxKind::WHITESPACE => {
+          newlines += token.text().matches('\n').count();
+        }
+        SyntaxElement::Token(token) => {
+          if newlines == 0 && !self.out.is_empty() {
+            self.out.push(' ');
+          } else {
+            self.separate(newlines);
+          }
+          self.out.push_str(token.text());
+          newlines = 0;
+        }
+        SyntaxElement::Node(node) => {
+          self.separate(newlines);
+          self.statement(&node);
+          newlines = 0;
+        }
+      }
+    }
+    if !self.out.is_empty() {
+      self.out.push('\n');
+    }
+    self.out
+  }
+
+  fn separate(&mut self, newlines: usize) {
+    if self.out.is_empty() {
+      return;
+    }
+    self.out.push('\n');
+    if newlines >= 2 {
+      self.out.push('\n');
+    }
+  }
+
+  fn statement(&mut self, node: &SyntaxNode) {
+    let follows_code = !self.out.is_empty();
+    for comment in node.tokens().iter().filter(|token| token.kind() == SyntaxKind::COMMENT) {
+      self.out.push_str(comment.text());
+      self.out.push('\n');
+    }
+
+    let prefix = match node.kind() {
+      SyntaxKind::ASSIGN_STMT => {
+        let name = node.significant_tokens().into_iter().find(|t| t.kind() == SyntaxKind::IDENTIFIER);
+        format!("var {} = ", name.map(|t| t.text().to_string()).unwrap_or_default())
+      }
+      _ => String::new(),
+    };
+    let expr = Expr::from_node(&node.children()[0]);
+    //statements are not terminated, a leading `-` would continue the statement before it
+    if prefix.is_empty() && follows_code && expr.starts_with_unary() {
+      self.out.push_str(&format!("({})", expr.render()));
+      return;
+    }
+    let layout = self.layout(&expr, 0, prefix.len());
+    self.out.push_str(&prefix);
+    self.out.push_str(&layout);
+  }
+
+  //breaks the outermost operator chain after each operator when it does not fit,
+  //operands that are chains themselves are broken again one indent level deeper
+  fn layout(&self, expr: &Expr, depth: usize, column: usize) -> String {
+    stack::grow(|| {
+      let flat = expr.render();
+      let op = match expr {
+        Expr::Binary(op, _, _) if column + flat.len() > self.config.line_width => *op,
+        _ => return flat,
+      };
+
+      let indent = " ".repeat(self.config.indent * (depth + 1));
+      let (first, rest) = expr.chain();
+      let mut out = self.layout_operand(first, op, false, depth, column);
+      for (op, operand) in rest {
+        out.push(' ');
+        out.push_str(op.symbol());
+        out.push('\n');
+        out.push_str(&indent);
+        out.push_str(&self.layout_operand(operand, op, true, depth, indent.len()));
+      }
+      out
+    })
+  }
+
+  fn layout_operand(&self, operand: &Expr, op: BinOp, is_rhs: bool, depth: usize, column: usize) -> String {
+    if op.needs_parentheses(operand.precedence(), is_rhs) {
+      operand.render_operand(op, is_rhs)
+    } else {
+      self.layout(operand, depth + 1, column)
+    }
+  }
+}
@@ -0,0 +1,102 @@
+use cliq_fmt::{format_source, Config};
+use cliq_parser::cst;
+
+const CASES: &[&str] = &[
+  "var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2",
+  "4 + 3 * 2 / 7 - 5",
+  "(4 + 3) * 2",
+  "  123 + 321 * 123 / 312 - 123",
+  "a - (b - (c - d))\n\n\n(((a))) / (b / c)",
+  "// leading comment\n\nvar a = 1 // trailing\n// between\nvar b = a *\n  // inside\n  2\n\n// last",
+  "var x = .5 + 1.25\nx x x",
+  "var long_name_one = first_operand * second_operand + third_operand * fourth_operand - fifth_operand / sixth",
+  "var nested = (aaaaaaaaaa + bbbbbbbbbb) * (cccccccccc - dddddddddd) * (eeeeeeeeee + ffffffffff) * gggggggggg",
+  "",
+  "\n\n// only a comment\n\n",
+];
+
+fn configs() -> Vec<Config> {
+  vec![
+    Config::default(),
+    Config { indent: 4, line_width: 20 },
+    Config { indent: 0, line_width: 1 },
+  ]
+}
+
+fn sources() -> Vec<String> {
+  let mut sources: Vec<String> = CASES.iter().map(|case| case.to_string()).collect();
+  let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../tests");
+  for entry in std::fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().is_some_and(|ext| ext == "cliq") {
+      sources.push(std::fs::read_to_string(path).unwrap());
+    }
+  }
+  sources
+}
+
+fn comments(src: &str) -> Vec<String> {
+  let root = cst::parse(src).unwrap().syntax();
+  let tokens = root.tokens();
+  let mut comments: Vec<String> = tokens
+    .iter()
+    .filter(|t| t.kind() == cst::syntax_kind::SyntaxKind::COMMENT)
+    .map(|t| t.text().to_string())
+    .collect();
+  comments.sort();
+  comments
+}
+
+#[test]
+fn formatting_is_idempotent() {
+  for config in configs() {
+    for src in sources() {
+      let once = format_source(&src, &config).unwrap();
+      let twice = format_source(&once, &config).unwrap();
+      assert_eq!(once, twice, "not idempotent for {:?} with {:?}", src, config);
+    }
+  }
+}
+
+#[test]
+fn formatting_keeps_the_program() {
+  for config in configs() {
+    for src in sources() {
+      let formatted = format_source(&src, &config).unwrap();
+      let before = cst::parse(&src).unwrap().to_ast().unwrap();
+      let after = cst::parse(&formatted).unwrap().to_ast().unwrap();
+      assert_eq!(before, after, "program changed for {:?}:\n{}", src, formatted);
+      assert_eq!(comments(&src), comments(&formatted));
+    }
+  }
+}
+
+//operators outside of parentheses, parenthesized operands are never broken
+fn top_level_operators(line: &str) -> usize {
+  let mut depth = 0;
+  let mut count = 0;
+  for c in line.trim_end_matches(['+', '-', '*', '/']).chars() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      '+' | '-' | '*' | '/' if depth == 0 => count += 1,
+      _ => {}
+    }
+  }
+  count
+}
+
+#[test]
+fn formatting_respects_line_width() {
+  for line_width in [1, 20, 40] {
+    let config = Config { indent: 2, line_width };
+    for src in sources() {
+      let formatted = format_source(&src, &config).unwrap();
+      for line in formatted.lines().filter(|line| !line.starts_with("//")) {
+        let line = line.split(" //").next().unwrap();
+        let line = line.trim_start_matches("var ").split(" = ").last().unwrap();
+        assert!(line.len() <= line_width || top_level_operators(line) == 0, "{:?} too long", line);
+      }
+    }
+  }
+}
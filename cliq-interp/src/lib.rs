@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use cliq_opt::value::{ArithError, Value};
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::Statement;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+  UndefinedVariable(String),
+  //functions are registered by a host through cliq-engine, the interpreter knows none
+  UndefinedFunction(String),
+  Arith(ArithError),
+}
+
+impl fmt::Display for RuntimeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RuntimeError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+      RuntimeError::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+      RuntimeError::Arith(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl From<ArithError> for RuntimeError {
+  fn from(err: ArithError) -> Self {
+    RuntimeError::Arith(err)
+  }
+}
+
+//tree walking interpreter, the reference every other backend is compared against
+//bindings outlive a call to run so statements can be fed in one at a time
+#[derive(Debug, Default)]
+pub struct Interpreter {
+  globals: HashMap<String, Value>,
+}
+
+impl Interpreter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn get(&self, name: &str) -> Option<Value> {
+    self.globals.get(name).copied()
+  }
+
+  pub fn set(&mut self, name: impl Into<String>, value: Value) {
+    self.globals.insert(name.into(), value);
+  }
+
+  pub fn globals(&self) -> &HashMap<String, Value> {
+    &self.globals
+  }
+
+  //value of the last expression statement, None if the program has none
+  pub fn run(&mut self, ast: &[Statement]) -> Result<Option<Value>, RuntimeError> {
+    let mut result = None;
+    for stmt in ast {
+      match stmt {
+        Statement::Expression(expr) => result = Some(self.eval(expr)?),
+        Statement::Assign(stmt) => {
+          let value = self.eval(&stmt.value)?;
+          if let Expression::VariableExpression(variable) = &stmt.variable {
+            self.set(variable.name.clone(), value);
+          }
+        }
+      }
+    }
+    Ok(result)
+  }
+
+  pub fn eval(&self, expr: &Expression) -> Result<Value, RuntimeError> {
+    stack::grow(|| match expr {
+      Expression::ValueExpression(_) => Ok(Value::from_expression(expr).unwrap()),
+      Expression::VariableExpression(variable) => self
+        .get(&variable.name)
+        .ok_or_else(|| RuntimeError::UndefinedVariable(variable.name.clone())),
+      Expression::Binary { op, lhs, rhs } => Ok(Value::binary(*op, self.eval(lhs)?, self.eval(rhs)?)?),
+      Expression::Unary { op, operand } => Ok(Value::unary(*op, self.eval(operand)?)),
+      Expression::Call(call) => Err(RuntimeError::UndefinedFunction(call.name.clone())),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+
+  fn run(input: &str) -> Result<Option<Value>, RuntimeError> {
+    Interpreter::new().run(&Parser::new(Lexer::new().lex(input).unwrap()).parse())
+  }
+
+  #[test]
+  fn test_run() {
+    assert_eq!(run("var a = 7\nvar b = a / 2\nb * 2.5 - -a"), Ok(Some(Value::Float(14.5))));
+    assert_eq!(run("var a = 1"), Ok(None));
+    assert_eq!(run("10 % 4\n1 + 1"), Ok(Some(Value::Int(2))));
+  }
+
+  #[test]
+  fn test_errors() {
+    assert_eq!(run("a + 1"), Err(RuntimeError::UndefinedVariable("a".to_string())));
+    assert_eq!(run("abs(1)"), Err(RuntimeError::UndefinedFunction("abs".to_string())));
+    assert_eq!(run("var z = 0\n1 / z"), Err(RuntimeError::Arith(ArithError::DivisionByZero)));
+  }
+
+  #[test]
+  fn test_bindings_persist() {
+    let mut interp = Interpreter::new();
+    interp.set("x", Value::Int(40));
+    let ast = Parser::new(Lexer::new().lex("var y = x + 2").unwrap()).parse();
+    interp.run(&ast).unwrap();
+    assert_eq!(interp.get("y"), Some(Value::Int(42)));
+  }
+}
@@ -0,0 +1,44 @@
+use cliq_opt::value::{ArithError, Value};
+
+use crate::{Function, InstKind, Terminator};
+
+//reference semantics of the ir, lowering is tested by comparing it with the ast interpreter
+pub fn eval(function: &Function) -> Result<Option<Value>, ArithError> {
+  eval_with(function, &[])
+}
+
+//args are the parameters of the entry block, they must have its types
+pub fn eval_with(function: &Function, args: &[Value]) -> Result<Option<Value>, ArithError> {
+  let mut values: Vec<Option<Value>> = vec![None; function.value_count()];
+  let mut block = &function.blocks[0];
+  assert_eq!(
+    args.len(),
+    block.params.len(),
+    "{} takes {} arguments",
+    function.name,
+    block.params.len()
+  );
+  let mut args = args.to_vec();
+  loop {
+    for ((param, _), arg) in block.params.iter().zip(args.drain(..)) {
+      values[param.index()] = Some(arg);
+    }
+    for inst in &block.insts {
+      let get = |id: crate::ValueId| values[id.index()].expect("verified ir defines values before use");
+      let value = match &inst.kind {
+        InstKind::Const(value) => *value,
+        InstKind::Binary(op, lhs, rhs) => Value::binary(*op, get(*lhs), get(*rhs))?,
+        InstKind::Unary(op, operand) => Value::unary(*op, get(*operand)),
+        InstKind::IntToFloat(operand) => Value::Float(get(*operand).as_float()),
+      };
+      values[inst.result.index()] = Some(value);
+    }
+    match &block.terminator {
+      Terminator::Return(value) => return Ok(value.map(|id| values[id.index()].unwrap())),
+      Terminator::Jump(target, jump_args) => {
+        args = jump_args.iter().map(|id| values[id.index()].unwrap()).collect();
+        block = function.block(*target).expect("verified ir only jumps to its own blocks");
+      }
+    }
+  }
+}
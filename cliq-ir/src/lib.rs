@@ -0,0 +1,111 @@
+use cliq_opt::value::{Type, Value};
+use cliq_parser::expression::operator::{BinOp, UnOp};
+
+pub mod eval;
+pub mod lower;
+pub mod text;
+pub mod verify;
+
+pub use self::lower::{lower, lower_with_inputs};
+pub use self::text::{parse, ParseError};
+pub use self::verify::{verify, VerifyError};
+
+//ssa value, defined exactly once by an instruction or a block parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValueId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+impl ValueId {
+  pub fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+impl BlockId {
+  pub fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+//operands of an instruction always have the type the instruction is annotated with,
+//ints are converted explicitly with IntToFloat before they meet a float
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstKind {
+  Const(Value),
+  Binary(BinOp, ValueId, ValueId),
+  Unary(UnOp, ValueId),
+  IntToFloat(ValueId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inst {
+  pub result: ValueId,
+  pub ty: Type,
+  pub kind: InstKind,
+}
+
+impl Inst {
+  pub fn operands(&self) -> Vec<ValueId> {
+    match &self.kind {
+      InstKind::Const(_) => vec![],
+      InstKind::Binary(_, lhs, rhs) => vec![*lhs, *rhs],
+      InstKind::Unary(_, operand) | InstKind::IntToFloat(operand) => vec![*operand],
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+  Return(Option<ValueId>),
+  //block arguments take the place of phi nodes
+  Jump(BlockId, Vec<ValueId>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+  pub id: BlockId,
+  pub params: Vec<(ValueId, Type)>,
+  pub insts: Vec<Inst>,
+  pub terminator: Terminator,
+}
+
+//execution starts at the first block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+  pub name: String,
+  pub ret: Option<Type>,
+  pub blocks: Vec<Block>,
+}
+
+impl Function {
+  pub fn new(name: impl Into<String>, ret: Option<Type>) -> Self {
+    Self {
+      name: name.into(),
+      ret,
+      blocks: Vec::new(),
+    }
+  }
+
+  pub fn block(&self, id: BlockId) -> Option<&Block> {
+    self.blocks.iter().find(|block| block.id == id)
+  }
+
+  //number of values, ids are dense from 0
+  pub fn value_count(&self) -> usize {
+    self
+      .blocks
+      .iter()
+      .flat_map(|block| {
+        block
+          .params
+          .iter()
+          .map(|(id, _)| *id)
+          .chain(block.insts.iter().map(|inst| inst.result))
+      })
+      .map(|id| id.index() + 1)
+      .max()
+      .unwrap_or(0)
+  }
+}
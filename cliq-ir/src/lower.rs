@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use cliq_opt::typeck::{TypeChecker, TypeError};
+use cliq_opt::value::{Type, Value};
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::Statement;
+
+use crate::{Block, BlockId, Function, Inst, InstKind, Terminator, ValueId};
+
+//variables of a lowered program in declaration order, with the value each one holds at the end
+pub type Vars = Vec<(String, ValueId, Type)>;
+
+//a program becomes `main`, returning its last expression statement
+//`var`s are only names for ssa values, every other expression statement is kept for its errors
+pub fn lower(ast: &[Statement]) -> Result<Function, TypeError> {
+  lower_with_inputs(ast, &[]).map(|(function, _)| function)
+}
+
+//inputs are variables the host sets before the program runs, they become the parameters of the entry block
+//and come first in the variables
+pub fn lower_with_inputs(ast: &[Statement], inputs: &[(&str, Type)]) -> Result<(Function, Vars), TypeError> {
+  let mut checker = TypeChecker::new();
+  for (name, ty) in inputs {
+    checker.declare(*name, *ty);
+  }
+  let ret = checker.check(ast)?;
+  let mut lowering = Lowering::default();
+  let mut params = Vec::new();
+  for (name, ty) in inputs {
+    let param = lowering.value();
+    params.push((param, *ty));
+    lowering.assign(name, (param, *ty));
+  }
+  let mut result = None;
+  for stmt in ast {
+    match stmt {
+      Statement::Expression(expr) => result = Some(lowering.expression(expr)?.0),
+      Statement::Assign(stmt) => {
+        let value = lowering.expression(&stmt.value)?;
+        if let Expression::VariableExpression(variable) = &stmt.variable {
+          lowering.assign(&variable.name, value);
+        }
+      }
+    }
+  }
+
+  let vars = lowering.order.iter().map(|name| {
+    let (value, ty) = lowering.vars[name];
+    (name.clone(), value, ty)
+  });
+  let vars = vars.collect();
+  let mut function = Function::new("main", ret);
+  function.blocks.push(Block {
+    id: BlockId(0),
+    params,
+    insts: lowering.insts,
+    terminator: Terminator::Return(result),
+  });
+  Ok((function, vars))
+}
+
+#[derive(Default)]
+struct Lowering {
+  insts: Vec<Inst>,
+  next: u32,
+  vars: HashMap<String, (ValueId, Type)>,
+  //declaration order, a variable declared again keeps its place
+  order: Vec<String>,
+}
+
+impl Lowering {
+  fn value(&mut self) -> ValueId {
+    self.next += 1;
+    ValueId(self.next - 1)
+  }
+
+  fn push(&mut self, ty: Type, kind: InstKind) -> (ValueId, Type) {
+    let result = self.value();
+    self.insts.push(Inst { result, ty, kind });
+    (result, ty)
+  }
+
+  fn assign(&mut self, name: &str, value: (ValueId, Type)) {
+    if self.vars.insert(name.to_string(), value).is_none() {
+      self.order.push(name.to_string());
+    }
+  }
+
+  fn float(&mut self, (value, ty): (ValueId, Type)) -> ValueId {
+    match ty {
+      Type::Int => self.push(Type::Float, InstKind::IntToFloat(value)).0,
+      Type::Float => value,
+    }
+  }
+
+  fn expression(&mut self, expr: &Expression) -> Result<(ValueId, Type), TypeError> {
+    stack::grow(|| {
+      Ok(match expr {
+        Expression::ValueExpression(_) => {
+          let value = Value::from_expression(expr).unwrap();
+          self.push(value.ty(), InstKind::Const(value))
+        }
+        Expression::VariableExpression(variable) => *self
+          .vars
+          .get(&variable.name)
+          .ok_or_else(|| TypeError::UndefinedVariable(variable.name.clone()))?,
+        Expression::Binary { op, lhs, rhs } => {
+          let lhs = self.expression(lhs)?;
+          let rhs = self.expression(rhs)?;
+          match Type::binary(lhs.1, rhs.1) {
+            Type::Int => self.push(Type::Int, InstKind::Binary(*op, lhs.0, rhs.0)),
+            Type::Float => {
+              let lhs = self.float(lhs);
+              let rhs = self.float(rhs);
+              self.push(Type::Float, InstKind::Binary(*op, lhs, rhs))
+            }
+          }
+        }
+        Expression::Unary { op, operand } => {
+          let (operand, ty) = self.expression(operand)?;
+          self.push(ty, InstKind::Unary(*op, operand))
+        }
+        //lower already ran the type checker, which has no functions
+        Expression::Call(call) => return Err(TypeError::UndefinedFunction(call.name.clone())),
+      })
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+  use crate::eval::eval_with;
+  use crate::verify;
+
+  #[test]
+  fn test_inputs() {
+    let ast = Parser::new(Lexer::new().lex("var y = x * n\nvar n = n - 1\ny").unwrap()).parse();
+    let (function, vars) = lower_with_inputs(&ast, &[("x", Type::Float), ("n", Type::Int)]).unwrap();
+    verify(&function).unwrap();
+    assert_eq!(function.blocks[0].params, vec![(ValueId(0), Type::Float), (ValueId(1), Type::Int)]);
+    assert_eq!(
+      vars.iter().map(|(name, _, ty)| (name.as_str(), *ty)).collect::<Vec<_>>(),
+      vec![("x", Type::Float), ("n", Type::Int), ("y", Type::Float)]
+    );
+    assert_eq!(vars[0].1, ValueId(0));
+    assert_eq!(
+      eval_with(&function, &[Value::Float(1.5), Value::Int(4)]),
+      Ok(Some(Value::Float(6.0)))
+    );
+    assert_eq!(
+      lower_with_inputs(&ast, &[("x", Type::Float)]).err(),
+      Some(TypeError::UndefinedVariable("n".to_string()))
+    );
+  }
+}
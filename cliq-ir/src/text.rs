@@ -0,0 +1,308 @@
+use std::fmt;
+
+use cliq_opt::value::{Type, Value};
+use cliq_parser::expression::operator::{BinOp, UnOp};
+
+use crate::{Block, BlockId, Function, Inst, InstKind, Terminator, ValueId};
+
+//textual form, one instruction per line:
+//  fn main() -> float {
+//  bb0:
+//    %0 = const.int 7
+//    %1 = itof %0
+//    %2 = mul.float %1, %1
+//    ret %2
+//  }
+
+impl fmt::Display for ValueId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "%{}", self.0)
+  }
+}
+
+impl fmt::Display for BlockId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "bb{}", self.0)
+  }
+}
+
+fn binary_name(op: BinOp) -> &'static str {
+  match op {
+    BinOp::Add => "add",
+    BinOp::Sub => "sub",
+    BinOp::Mul => "mul",
+    BinOp::Div => "div",
+    BinOp::Rem => "rem",
+  }
+}
+
+fn unary_name(op: UnOp) -> &'static str {
+  match op {
+    UnOp::Neg => "neg",
+  }
+}
+
+fn join(values: &[ValueId]) -> String {
+  values.iter().map(ValueId::to_string).collect::<Vec<_>>().join(", ")
+}
+
+impl fmt::Display for Inst {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} = ", self.result)?;
+    match &self.kind {
+      //debug formatting reads back as the same f32, including inf and NaN
+      InstKind::Const(Value::Int(val)) => write!(f, "const.int {}", val),
+      InstKind::Const(Value::Float(val)) => write!(f, "const.float {:?}", val),
+      InstKind::Binary(op, lhs, rhs) => write!(f, "{}.{} {}, {}", binary_name(*op), self.ty, lhs, rhs),
+      InstKind::Unary(op, operand) => write!(f, "{}.{} {}", unary_name(*op), self.ty, operand),
+      InstKind::IntToFloat(operand) => write!(f, "itof {}", operand),
+    }
+  }
+}
+
+impl fmt::Display for Terminator {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Terminator::Return(None) => write!(f, "ret"),
+      Terminator::Return(Some(value)) => write!(f, "ret {}", value),
+      Terminator::Jump(target, args) if args.is_empty() => write!(f, "jmp {}", target),
+      Terminator::Jump(target, args) => write!(f, "jmp {}({})", target, join(args)),
+    }
+  }
+}
+
+impl fmt::Display for Block {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.id)?;
+    if !self.params.is_empty() {
+      let params: Vec<String> = self.params.iter().map(|(id, ty)| format!("{}: {}", id, ty)).collect();
+      write!(f, "({})", params.join(", "))?;
+    }
+    writeln!(f, ":")?;
+    for inst in &self.insts {
+      writeln!(f, "  {}", inst)?;
+    }
+    writeln!(f, "  {}", self.terminator)
+  }
+}
+
+impl fmt::Display for Function {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.ret {
+      Some(ty) => writeln!(f, "fn {}() -> {} {{", self.name, ty)?,
+      None => writeln!(f, "fn {}() {{", self.name)?,
+    }
+    for block in &self.blocks {
+      write!(f, "{}", block)?;
+    }
+    writeln!(f, "}}")
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  //1-based
+  pub line: usize,
+  pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.msg)
+  }
+}
+
+//reads the textual form back, blank lines and `;` comments are ignored
+//the result is only well formed, run verify to check types and ssa
+pub fn parse(text: &str) -> Result<Function, ParseError> {
+  let mut lines = text
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i + 1, line.split(';').next().unwrap().trim()))
+    .filter(|(_, line)| !line.is_empty());
+
+  let (line, header) = lines.next().ok_or(ParseError {
+    line: 1,
+    msg: "expected `fn`".to_string(),
+  })?;
+  let err = |line: usize, msg: &str| ParseError {
+    line,
+    msg: msg.to_string(),
+  };
+  let mut function = parse_header(header).ok_or_else(|| err(line, "expected `fn name() [-> type] {`"))?;
+
+  let mut current: Option<Block> = None;
+  for (line, text) in lines {
+    if text == "}" {
+      if current.is_some() {
+        return Err(err(line, "block has no terminator"));
+      }
+      return Ok(function);
+    }
+    if let Some(label) = text.strip_suffix(':') {
+      if current.is_some() {
+        return Err(err(line, "block has no terminator"));
+      }
+      current = Some(parse_label(label).ok_or_else(|| err(line, "malformed block label"))?);
+      continue;
+    }
+    let block = current.as_mut().ok_or_else(|| err(line, "instruction outside of a block"))?;
+    if let Some(terminator) = parse_terminator(text) {
+      block.terminator = terminator.ok_or_else(|| err(line, "malformed terminator"))?;
+      function.blocks.push(current.take().unwrap());
+    } else {
+      block
+        .insts
+        .push(parse_inst(text).ok_or_else(|| err(line, "malformed instruction"))?);
+    }
+  }
+  Err(err(text.lines().count(), "expected `}`"))
+}
+
+fn parse_type(text: &str) -> Option<Type> {
+  match text {
+    "int" => Some(Type::Int),
+    "float" => Some(Type::Float),
+    _ => None,
+  }
+}
+
+fn parse_value(text: &str) -> Option<ValueId> {
+  text.trim().strip_prefix('%')?.parse().ok().map(ValueId)
+}
+
+fn parse_block_id(text: &str) -> Option<BlockId> {
+  text.trim().strip_prefix("bb")?.parse().ok().map(BlockId)
+}
+
+//`name(a, b)` or `name`
+fn split_call(text: &str) -> Option<(&str, Vec<&str>)> {
+  match text.split_once('(') {
+    Some((name, rest)) => {
+      let args = rest.strip_suffix(')')?;
+      let args = if args.trim().is_empty() {
+        vec![]
+      } else {
+        args.split(',').collect()
+      };
+      Some((name.trim(), args))
+    }
+    None => Some((text.trim(), vec![])),
+  }
+}
+
+fn parse_header(text: &str) -> Option<Function> {
+  let rest = text.strip_prefix("fn ")?.strip_suffix('{')?.trim();
+  let (signature, ret) = match rest.split_once("->") {
+    Some((signature, ret)) => (signature.trim(), Some(parse_type(ret.trim())?)),
+    None => (rest, None),
+  };
+  let name = signature.strip_suffix("()")?;
+  Some(Function::new(name, ret))
+}
+
+fn parse_label(text: &str) -> Option<Block> {
+  let (id, params) = split_call(text)?;
+  let params = params
+    .into_iter()
+    .map(|param| {
+      let (value, ty) = param.split_once(':')?;
+      Some((parse_value(value)?, parse_type(ty.trim())?))
+    })
+    .collect::<Option<Vec<_>>>()?;
+  Some(Block {
+    id: parse_block_id(id)?,
+    params,
+    insts: Vec::new(),
+    terminator: Terminator::Return(None),
+  })
+}
+
+//None if the line is not a terminator, Some(None) if it is a malformed one
+fn parse_terminator(text: &str) -> Option<Option<Terminator>> {
+  if text == "ret" {
+    return Some(Some(Terminator::Return(None)));
+  }
+  if let Some(value) = text.strip_prefix("ret ") {
+    return Some(parse_value(value).map(|value| Terminator::Return(Some(value))));
+  }
+  let target = text.strip_prefix("jmp ")?;
+  Some((|| {
+    let (target, args) = split_call(target)?;
+    let args = args.into_iter().map(parse_value).collect::<Option<Vec<_>>>()?;
+    Some(Terminator::Jump(parse_block_id(target)?, args))
+  })())
+}
+
+fn parse_inst(text: &str) -> Option<Inst> {
+  let (result, rhs) = text.split_once('=')?;
+  let result = parse_value(result)?;
+  let (opcode, operands) = rhs.trim().split_once(' ')?;
+  let (name, ty) = match opcode.split_once('.') {
+    Some((name, ty)) => (name, Some(parse_type(ty)?)),
+    None => (opcode, None),
+  };
+  let operand_list: Vec<&str> = operands.split(',').collect();
+
+  let (ty, kind) = match (name, ty, operand_list.as_slice()) {
+    ("const", Some(Type::Int), [val]) => (Type::Int, InstKind::Const(Value::Int(val.trim().parse().ok()?))),
+    ("const", Some(Type::Float), [val]) => (Type::Float, InstKind::Const(Value::Float(val.trim().parse().ok()?))),
+    ("itof", None, [operand]) => (Type::Float, InstKind::IntToFloat(parse_value(operand)?)),
+    (name, Some(ty), [operand]) => {
+      let op = UnOp::ALL.into_iter().find(|op| unary_name(*op) == name)?;
+      (ty, InstKind::Unary(op, parse_value(operand)?))
+    }
+    (name, Some(ty), [lhs, rhs]) => {
+      let op = BinOp::ALL.into_iter().find(|op| binary_name(*op) == name)?;
+      (ty, InstKind::Binary(op, parse_value(lhs)?, parse_value(rhs)?))
+    }
+    _ => return None,
+  };
+  Some(Inst { result, ty, kind })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TEXT: &str = "fn main() -> float {
+bb0:
+  %0 = const.int 7
+  %1 = const.float -0.0
+  %2 = itof %0
+  %3 = rem.float %2, %1
+  jmp bb1(%3, %0)
+bb1(%4: float, %5: int):
+  %6 = neg.int %5
+  %7 = const.float NaN
+  ret %4
+}
+";
+
+  #[test]
+  fn test_roundtrip() {
+    let function = parse(TEXT).unwrap();
+    assert_eq!(function.blocks.len(), 2);
+    assert_eq!(function.blocks[1].params, vec![(ValueId(4), Type::Float), (ValueId(5), Type::Int)]);
+    assert_eq!(function.to_string(), TEXT);
+  }
+
+  #[test]
+  fn test_comments_and_blank_lines() {
+    let function = parse("; header\nfn f() {\n\nbb0: ; entry\n  ret\n}").unwrap();
+    assert_eq!(function.to_string(), "fn f() {\nbb0:\n  ret\n}\n");
+  }
+
+  #[test]
+  fn test_errors() {
+    let err = |text: &str| parse(text).unwrap_err().to_string();
+    assert_eq!(err(""), "line 1: expected `fn`");
+    assert_eq!(err("fn f() {\n  %0 = const.int 1\n}"), "line 2: instruction outside of a block");
+    assert_eq!(
+      err("fn f() {\nbb0:\n  %0 = pow.int %1, %2\n  ret\n}"),
+      "line 3: malformed instruction"
+    );
+    assert_eq!(err("fn f() {\nbb0:\n  %0 = const.int 1\n}"), "line 4: block has no terminator");
+    assert_eq!(err("fn f() {\nbb0:\n  ret"), "line 3: expected `}`");
+  }
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use cliq_opt::value::Type;
+
+use crate::{BlockId, Function, InstKind, Terminator, ValueId};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+  pub block: Option<BlockId>,
+  pub msg: String,
+}
+
+impl fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.block {
+      Some(block) => write!(f, "{}: {}", block, self.msg),
+      None => write!(f, "{}", self.msg),
+    }
+  }
+}
+
+//values are local to the block defining them, other blocks only see them through jump arguments
+//so every use must follow its definition in the same block; the parameters of the entry block are the function's arguments
+pub fn verify(function: &Function) -> Result<(), VerifyError> {
+  let fail = |block: Option<BlockId>, msg: String| Err(VerifyError { block, msg });
+
+  if function.blocks.is_empty() {
+    return fail(None, "function has no blocks".to_string());
+  }
+  let mut params: HashMap<BlockId, Vec<Type>> = HashMap::new();
+  for block in &function.blocks {
+    if params.insert(block.id, block.params.iter().map(|(_, ty)| *ty).collect()).is_some() {
+      return fail(Some(block.id), "block is defined twice".to_string());
+    }
+  }
+
+  let mut defined = HashSet::new();
+  for block in &function.blocks {
+    let mut local: HashMap<ValueId, Type> = HashMap::new();
+    let mut define = |id: ValueId, ty: Type, local: &mut HashMap<ValueId, Type>| {
+      if !defined.insert(id) {
+        return fail(Some(block.id), format!("{} is defined twice", id));
+      }
+      local.insert(id, ty);
+      Ok(())
+    };
+    let use_as = |id: ValueId, ty: Type, local: &HashMap<ValueId, Type>| match local.get(&id) {
+      None => fail(Some(block.id), format!("{} is used before it is defined in this block", id)),
+      Some(actual) if *actual != ty => fail(Some(block.id), format!("{} is {} but {} is expected", id, actual, ty)),
+      Some(_) => Ok(()),
+    };
+
+    for (id, ty) in &block.params {
+      define(*id, *ty, &mut local)?;
+    }
+    for inst in &block.insts {
+      match &inst.kind {
+        InstKind::Const(value) if value.ty() != inst.ty => {
+          return fail(
+            Some(block.id),
+            format!("{} holds a constant of type {} but is typed {}", inst.result, value.ty(), inst.ty),
+          )
+        }
+        InstKind::Const(_) => {}
+        InstKind::Binary(_, lhs, rhs) => {
+          use_as(*lhs, inst.ty, &local)?;
+          use_as(*rhs, inst.ty, &local)?;
+        }
+        InstKind::Unary(_, operand) => use_as(*operand, inst.ty, &local)?,
+        InstKind::IntToFloat(_) if inst.ty != Type::Float => {
+          return fail(
+            Some(block.id),
+            format!("{} converts to float but is typed {}", inst.result, inst.ty),
+          )
+        }
+        InstKind::IntToFloat(operand) => use_as(*operand, Type::Int, &local)?,
+      }
+      define(inst.result, inst.ty, &mut local)?;
+    }
+
+    match &block.terminator {
+      Terminator::Return(value) => match (value, function.ret) {
+        (None, None) => {}
+        (Some(value), Some(ty)) => use_as(*value, ty, &local)?,
+        _ => return fail(Some(block.id), "return does not match the function's result".to_string()),
+      },
+      Terminator::Jump(target, args) => {
+        let expected = match params.get(target) {
+          Some(expected) => expected,
+          None => return fail(Some(block.id), format!("jump to unknown block {}", target)),
+        };
+        if expected.len() != args.len() {
+          return fail(
+            Some(block.id),
+            format!("{} takes {} arguments, got {}", target, expected.len(), args.len()),
+          );
+        }
+        for (arg, ty) in args.iter().zip(expected) {
+          use_as(*arg, *ty, &local)?;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parse;
+
+  fn check(body: &str) -> Result<(), String> {
+    verify(&parse(&format!("fn f() -> int {{\n{}\n}}", body)).unwrap()).map_err(|err| err.to_string())
+  }
+
+  #[test]
+  fn test_valid() {
+    assert_eq!(
+      check("bb0:\n%0 = const.int 1\njmp bb1(%0)\nbb1(%1: int):\n%2 = add.int %1, %1\nret %2"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_entry_params() {
+    assert_eq!(check("bb0(%0: int, %1: float):
+%2 = neg.int %0
+ret %2"), Ok(()));
+    assert_eq!(
+      check("bb0(%0: float):
+ret %0"),
+      Err("bb0: %0 is float but int is expected".to_string())
+    );
+  }
+
+  #[test]
+  fn test_mistyped_constant() {
+    //the text form can not express this, the type is part of the opcode
+    let mut function = parse("fn f() {\nbb0:\n%0 = const.int 1\nret\n}").unwrap();
+    function.blocks[0].insts[0].ty = Type::Float;
+    assert_eq!(verify(&function).unwrap_err().to_string(), "bb0: %0 holds a constant of type int but is typed float");
+  }
+
+  #[test]
+  fn test_errors() {
+    assert_eq!(
+      check("bb0:\n%0 = const.float 1.0\nret %0"),
+      Err("bb0: %0 is float but int is expected".to_string())
+    );
+    assert_eq!(
+      check("bb0:\n%0 = const.int 1\n%0 = neg.int %0\nret %0"),
+      Err("bb0: %0 is defined twice".to_string())
+    );
+    assert_eq!(
+      check("bb0:\n%1 = neg.int %0\n%0 = const.int 1\nret %1"),
+      Err("bb0: %0 is used before it is defined in this block".to_string())
+    );
+    assert_eq!(
+      check("bb0:\n%0 = const.int 1\njmp bb1\nbb1:\nret %0"),
+      Err("bb1: %0 is used before it is defined in this block".to_string())
+    );
+    assert_eq!(
+      check("bb0:\n%0 = const.int 1\njmp bb2(%0)"),
+      Err("bb0: jump to unknown block bb2".to_string())
+    );
+    assert_eq!(
+      check("bb0:\nret"),
+      Err("bb0: return does not match the function's result".to_string())
+    );
+  }
+}
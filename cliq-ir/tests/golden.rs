@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use cliq_interp::{Interpreter, RuntimeError};
+use cliq_ir::eval::eval;
+use cliq_ir::{lower, parse, verify};
+use cliq_lexer::lexer::Lexer;
+use cliq_parser::{
+  expression::{
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  parser::Parser,
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+use proptest::prelude::*;
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+//every golden/*.cliq is lowered and compared with the .ir next to it
+#[test]
+fn golden_dumps() {
+  let mut checked = 0;
+  for entry in std::fs::read_dir(GOLDEN_DIR).unwrap() {
+    let path = entry.unwrap().path();
+    if path.extension().is_none_or(|ext| ext != "cliq") {
+      continue;
+    }
+    let src = std::fs::read_to_string(&path).unwrap();
+    let function = lower(&Parser::new(Lexer::new().lex(&src).unwrap()).parse()).unwrap();
+    verify(&function).unwrap();
+    let dump = function.to_string();
+
+    let ir_path = path.with_extension("ir");
+    if std::env::var_os("CLIQ_UPDATE_GOLDEN").is_some() {
+      std::fs::write(&ir_path, &dump).unwrap();
+    }
+    let committed = std::fs::read_to_string(&ir_path).unwrap_or_default();
+    assert_eq!(
+      committed,
+      dump,
+      "{} is out of date, rerun with CLIQ_UPDATE_GOLDEN=1",
+      name(&ir_path)
+    );
+    assert_eq!(parse(&committed), Ok(function), "{} does not parse back", name(&ir_path));
+    checked += 1;
+  }
+  assert!(checked > 0, "no golden programs found");
+}
+
+fn name(path: &Path) -> String {
+  path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+const NAMES: [&str; 3] = ["a", "b", "c"];
+
+fn var_name() -> impl Strategy<Value = String> {
+  prop::sample::select(NAMES.to_vec()).prop_map(str::to_string)
+}
+
+fn expression() -> impl Strategy<Value = Expression> {
+  let leaf = prop_oneof![
+    (0..4).prop_map(ValueExpression::int_value),
+    (0..=i32::MAX).prop_map(ValueExpression::int_value),
+    prop::sample::select(vec![0.0f32, 0.5, 1.0, 3.25, 1.0e30]).prop_map(ValueExpression::float_value),
+    var_name().prop_map(VariableExpression::expression),
+  ];
+  leaf.prop_recursive(5, 32, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), inner.clone(), prop::sample::select(BinOp::ALL.to_vec())).prop_map(|(lhs, rhs, op)| Expression::binary(op, lhs, rhs)),
+      inner.prop_map(|operand| Expression::unary(UnOp::Neg, operand)),
+    ]
+  })
+}
+
+fn statement() -> impl Strategy<Value = Statement> {
+  prop_oneof![
+    expression().prop_map(Statement::Expression),
+    (var_name(), expression()).prop_map(|(name, value)| AssignStmt::statement(VariableExpression::expression(name), value, false)),
+  ]
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(256))]
+
+  #[test]
+  fn lowering_matches_interpreter(ast in prop::collection::vec(statement(), 1..8)) {
+    let expected = Interpreter::new().run(&ast);
+    //programs reading undeclared variables are rejected by lowering and fail when interpreted
+    let Ok(function) = lower(&ast) else {
+      prop_assert!(expected.is_err());
+      return Ok(());
+    };
+    prop_assert_eq!(verify(&function), Ok(()));
+    prop_assert_eq!(parse(&function.to_string()), Ok(function.clone()));
+    let actual = eval(&function).map_err(RuntimeError::Arith);
+    prop_assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+  }
+}
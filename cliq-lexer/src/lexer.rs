@@ -2,27 +2,90 @@ extern crate nom;
 extern crate regex;
 
 use nom::{
-  character::complete::{digit1, multispace1, one_of},
+  bytes::complete::tag,
+  character::complete::{digit1, multispace1, not_line_ending, one_of},
   IResult,
 };
 
 use regex::Regex;
 
 use crate::codepos::Codepos;
+use crate::source_map::{FileId, Span};
 use crate::token::Token;
 use crate::token_t::TokenT;
 
+//where a token starts, taken before its text is consumed
+struct Mark {
+  offset: usize,
+  line: usize,
+  col: usize,
+}
+
 pub struct Lexer {
   line: usize,
   col: usize,
+  offset: usize,
+  file: Option<FileId>,
+  file_name: Option<String>,
+}
+
+impl Default for Lexer {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Lexer {
   pub fn new() -> Lexer {
-    Lexer { line: 1, col: 1 }
+    Lexer {
+      line: 1,
+      col: 1,
+      offset: 0,
+      file: None,
+      file_name: None,
+    }
+  }
+
+  //positions of the produced tokens carry the file id and name
+  pub fn for_file(file: FileId, file_name: &str) -> Lexer {
+    Lexer {
+      file: Some(file),
+      file_name: Some(file_name.to_string()),
+      ..Lexer::new()
+    }
+  }
+
+  //lexes a slice of a larger text, positions continue from where the slice starts in it
+  pub fn at(line: usize, col: usize, offset: usize) -> Lexer {
+    Lexer {
+      line,
+      col,
+      offset,
+      ..Lexer::new()
+    }
+  }
+
+  //byte offset of the next character to lex, after lex failed it is the character that was rejected
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  fn mark(&self) -> Mark {
+    Mark {
+      offset: self.offset,
+      line: self.line,
+      col: self.col,
+    }
+  }
+
+  //the codepos is the first character of the token, the same position SourceMap::resolve gives for its span
+  fn token(&self, token_t: TokenT, value: String, start: Mark) -> Token {
+    let codepos = Codepos::new(start.line, start.col, self.file_name.clone());
+    Token::new(token_t, value, codepos, Span::new(self.file, start.offset, self.offset))
   }
 
   fn update_codepos(&mut self, text: &str) {
+    self.offset += text.len();
     for c in text.chars() {
       if c == '\n' {
         self.line += 1;
@@ -44,30 +107,41 @@ impl Lexer {
   }
 
   fn lex_whitespace<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
+    let start = self.mark();
     let (input, matched) = multispace1(input)?;
     self.update_codepos(matched);
-    let codepos = Codepos::new(self.line, self.col, None);
-    let token = Token::new(TokenT::WHITESPACE, "".to_string(), codepos);
+    let token = self.token(TokenT::WHITESPACE, matched.to_string(), start);
+    Ok((input, token))
+  }
+
+  fn lex_comment<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
+    //=> // until the end of the line, the line break is left for lex_whitespace
+    let (input, _) = tag("//")(input)?;
+    let (input, text) = not_line_ending(input)?;
+    let start = self.mark();
+    let matched = format!("//{}", text);
+    self.update_codepos(&matched);
+    let token = self.token(TokenT::COMMENT, matched, start);
     Ok((input, token))
   }
 
   fn lex_integer<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
+    let start = self.mark();
     let (input, value) = digit1(input)?;
     let value = value.to_string();
     self.update_codepos(&value);
-    let codepos = Codepos::new(self.line, self.col, None);
-    let token = Token::new(TokenT::INTEGER, value, codepos);
+    let token = self.token(TokenT::INTEGER, value, start);
     Ok((input, token))
   }
 
   fn lex_float<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
     let float_regex = Regex::new(r"^\d*\.\d+").unwrap();
     if let Some(mat) = float_regex.find(input) {
+      let start = self.mark();
       let matched = &input[..mat.end()];
       self.update_codepos(matched);
-      let codepos = Codepos::new(self.line, self.col, None);
       let corrected = Lexer::parse_and_format_float(matched).unwrap();
-      let token = Token::new(TokenT::FLOAT, corrected, codepos);
+      let token = self.token(TokenT::FLOAT, corrected, start);
       Ok((&input[mat.end()..], token))
     } else {
       Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Digit)))
@@ -75,29 +149,37 @@ impl Lexer {
   }
 
   fn lex_operator<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
+    let start = self.mark();
     let (input, matched) = one_of("+-*/%=")(input)?;
     self.update_codepos(String::from(matched).as_str());
-    let codepos = Codepos::new(self.line, self.col, None);
-    let token = Token::new(TokenT::OPERATOR, matched.to_string(), codepos);
+    let token = self.token(TokenT::OPERATOR, matched.to_string(), start);
     Ok((input, token))
   }
 
   fn lex_bracket<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
     //=> (), [], {}
+    let start = self.mark();
     let (input, matched) = one_of("()[]{}")(input)?;
     self.update_codepos(String::from(matched).as_str());
-    let codepos = Codepos::new(self.line, self.col, None);
-    let token = Token::new(TokenT::BRACKET, matched.to_string(), codepos);
+    let token = self.token(TokenT::BRACKET, matched.to_string(), start);
+    Ok((input, token))
+  }
+
+  fn lex_comma<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
+    let start = self.mark();
+    let (input, matched) = tag(",")(input)?;
+    self.update_codepos(matched);
+    let token = self.token(TokenT::COMMA, matched.to_string(), start);
     Ok((input, token))
   }
 
   fn lex_identifier<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
     let identifier_regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*").unwrap();
     if let Some(mat) = identifier_regex.find(input) {
+      let start = self.mark();
       let matched = &input[..mat.end()];
       self.update_codepos(matched);
-      let codepos = Codepos::new(self.line, self.col, None);
-      let token = Token::new(TokenT::IDENTIFIER, matched.to_string(), codepos);
+      let token = self.token(TokenT::IDENTIFIER, matched.to_string(), start);
       Ok((&input[mat.end()..], token))
     } else {
       Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Alpha)))
@@ -105,12 +187,12 @@ impl Lexer {
   }
 
   fn lex_k_var<'a>(&mut self, input: &'a str) -> IResult<&'a str, Token> {
-    let var_regex = Regex::new(r"^var").unwrap();
+    let var_regex = Regex::new(r"^var\b").unwrap();
     if let Some(mat) = var_regex.find(input) {
+      let start = self.mark();
       let matched = &input[..mat.end()];
       self.update_codepos(matched);
-      let codepos = Codepos::new(self.line, self.col, None);
-      let token = Token::new(TokenT::VAR, matched.to_string(), codepos);
+      let token = self.token(TokenT::VAR, matched.to_string(), start);
       Ok((&input[mat.end()..], token))
     } else {
       Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Alpha)))
@@ -124,6 +206,8 @@ impl Lexer {
     while !current_input.is_empty() {
       let token_result = if let Ok(result) = self.lex_whitespace(current_input) {
         result
+      } else if let Ok(result) = self.lex_comment(current_input) {
+        result
       } else if let Ok(result) = self.lex_float(current_input) {
         result
       } else if let Ok(result) = self.lex_integer(current_input) {
@@ -132,12 +216,17 @@ impl Lexer {
         result
       } else if let Ok(result) = self.lex_bracket(current_input) {
         result
+      } else if let Ok(result) = self.lex_comma(current_input) {
+        result
       } else if let Ok(result) = self.lex_k_var(current_input) {
         result
       } else if let Ok(result) = self.lex_identifier(current_input) {
         result
       } else {
-        return Err(format!("Unexpected character at line {}, column {}", self.line, self.col));
+        return Err(match &self.file_name {
+          Some(file) => format!("Unexpected character at {}:{}:{}", file, self.line, self.col),
+          None => format!("Unexpected character at line {}, column {}", self.line, self.col),
+        });
       };
 
       let (next_input, token) = token_result;
@@ -184,4 +273,61 @@ mod tests {
     let tokens = lexer.lex(input).unwrap();
     println!("{:#?}", tokens);
   }
+
+  #[test]
+  fn test_lexer_var_prefix() {
+    let mut lexer = Lexer::new();
+    let tokens = lexer.lex("var variableName = 123").unwrap();
+    assert_eq!(tokens[0].token_t, TokenT::VAR);
+    assert_eq!(tokens[2].token_t, TokenT::IDENTIFIER);
+    assert_eq!(tokens[2].value, "variableName");
+  }
+
+  #[test]
+  fn test_lexer_call() {
+    let mut lexer = Lexer::new();
+    let tokens = lexer.lex("max(a,2)").unwrap();
+    let kinds: Vec<_> = tokens.iter().map(|t| t.token_t).collect();
+    assert_eq!(
+      kinds,
+      vec![
+        TokenT::IDENTIFIER,
+        TokenT::BRACKET,
+        TokenT::IDENTIFIER,
+        TokenT::COMMA,
+        TokenT::INTEGER,
+        TokenT::BRACKET
+      ]
+    );
+    assert_eq!(tokens[3].span.start..tokens[3].span.end, 5..6);
+  }
+
+  #[test]
+  fn test_lexer_trivia() {
+    let mut lexer = Lexer::new();
+    let input = "var a = 1 // one\n  a / 2";
+    let tokens = lexer.lex(input).unwrap();
+    let comment = tokens.iter().find(|t| t.token_t == TokenT::COMMENT).unwrap();
+    assert_eq!(comment.value, "// one");
+    assert_eq!(tokens.iter().filter(|t| t.token_t == TokenT::OPERATOR).count(), 2);
+    let text: String = tokens.iter().map(|t| &input[t.span.start..t.span.end]).collect();
+    assert_eq!(text, input);
+    assert_eq!(tokens.iter().find(|t| t.token_t == TokenT::WHITESPACE).unwrap().value, " ");
+  }
+
+  #[test]
+  fn test_lexer_codepos() {
+    let mut lexer = Lexer::new();
+    let tokens = lexer.lex("var a = 1\n  abc / 2").unwrap();
+    let abc = tokens.iter().find(|t| t.value == "abc").unwrap();
+    assert_eq!((abc.codepos.line, abc.codepos.col), (2, 3));
+    assert_eq!((tokens[0].codepos.line, tokens[0].codepos.col), (1, 1));
+  }
+
+  #[test]
+  fn test_lexer_error_offset() {
+    let mut lexer = Lexer::new();
+    assert!(lexer.lex("a +\n  $b").is_err());
+    assert_eq!(lexer.offset(), 6);
+  }
 }
@@ -1,4 +1,5 @@
 pub mod codepos;
 pub mod token;
 pub mod token_t;
-pub mod lexer;
\ No newline at end of file
+pub mod lexer;
+pub mod source_map;
\ No newline at end of file
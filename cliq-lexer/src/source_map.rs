@@ -0,0 +1,206 @@
+use std::{fs, io, path::Path};
+
+use serde::Serialize;
+
+use crate::codepos::Codepos;
+use crate::lexer::Lexer;
+use crate::token::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct FileId(usize);
+
+impl FileId {
+  pub fn index(self) -> usize {
+    self.0
+  }
+}
+
+//byte range inside a source file, files lexed without a source map have no file id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub struct Span {
+  pub file: Option<FileId>,
+  pub start: usize,
+  pub end: usize,
+}
+
+impl Span {
+  pub fn new(file: Option<FileId>, start: usize, end: usize) -> Self {
+    Self { file, start, end }
+  }
+
+  pub fn len(&self) -> usize {
+    self.end - self.start
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.start == self.end
+  }
+
+  //smallest span covering both, only meaningful for spans of the same file
+  pub fn to(&self, other: Span) -> Span {
+    Span::new(self.file, self.start.min(other.start), self.end.max(other.end))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+  id: FileId,
+  name: String,
+  src: String,
+  //byte offset of the first character of every line
+  line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+  fn new(id: FileId, name: String, src: String) -> Self {
+    let mut line_starts = vec![0];
+    for (i, c) in src.char_indices() {
+      if c == '\n' {
+        line_starts.push(i + 1);
+      }
+    }
+    Self {
+      id,
+      name,
+      src,
+      line_starts,
+    }
+  }
+
+  pub fn id(&self) -> FileId {
+    self.id
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn src(&self) -> &str {
+    &self.src
+  }
+
+  pub fn line_count(&self) -> usize {
+    self.line_starts.len()
+  }
+
+  //1 based line and column of a byte offset, columns count characters like the lexer does
+  pub fn line_col(&self, offset: usize) -> (usize, usize) {
+    let offset = offset.min(self.src.len());
+    let line = match self.line_starts.binary_search(&offset) {
+      Ok(line) => line,
+      Err(next) => next - 1,
+    };
+    let col = self.src[self.line_starts[line]..offset].chars().count();
+    (line + 1, col + 1)
+  }
+
+  pub fn codepos(&self, offset: usize) -> Codepos {
+    let (line, col) = self.line_col(offset);
+    Codepos::new(line, col, Some(self.name.clone()))
+  }
+
+  //text of a 1 based line without its line break
+  pub fn line(&self, line: usize) -> Option<&str> {
+    let start = *self.line_starts.get(line.checked_sub(1)?)?;
+    let end = self.line_starts.get(line).copied().unwrap_or(self.src.len());
+    Some(self.src[start..end].trim_end_matches(['\n', '\r']))
+  }
+
+  pub fn lexer(&self) -> Lexer {
+    Lexer::for_file(self.id, &self.name)
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+  pub fn new() -> Self {
+    Self { files: vec![] }
+  }
+
+  pub fn add_file(&mut self, name: &str, src: &str) -> FileId {
+    let id = FileId(self.files.len());
+    self.files.push(SourceFile::new(id, name.to_string(), src.to_string()));
+    id
+  }
+
+  pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<FileId> {
+    let path = path.as_ref();
+    let src = fs::read_to_string(path)?;
+    Ok(self.add_file(&path.display().to_string(), &src))
+  }
+
+  pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+    self.files.get(id.0)
+  }
+
+  pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+    self.files.iter()
+  }
+
+  pub fn lexer(&self, id: FileId) -> Option<Lexer> {
+    self.get(id).map(|file| file.lexer())
+  }
+
+  pub fn lex(&self, id: FileId) -> Result<Vec<Token>, String> {
+    let file = self.get(id).ok_or_else(|| format!("Unknown file id {}", id.0))?;
+    file.lexer().lex(file.src())
+  }
+
+  pub fn source_text(&self, span: Span) -> Option<&str> {
+    self.get(span.file?)?.src().get(span.start..span.end)
+  }
+
+  //position of the start of a span
+  pub fn resolve(&self, span: Span) -> Option<Codepos> {
+    Some(self.get(span.file?)?.codepos(span.start))
+  }
+
+  pub fn resolve_end(&self, span: Span) -> Option<Codepos> {
+    Some(self.get(span.file?)?.codepos(span.end))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::token_t::TokenT;
+
+  #[test]
+  fn test_line_col() {
+    let mut map = SourceMap::new();
+    let id = map.add_file("a.cliq", "var a = 1\nvar bb = 2\n");
+    let file = map.get(id).unwrap();
+    assert_eq!(file.line_col(0), (1, 1));
+    assert_eq!(file.line_col(4), (1, 5));
+    assert_eq!(file.line_col(10), (2, 1));
+    assert_eq!(file.line_col(14), (2, 5));
+    assert_eq!(file.line(2), Some("var bb = 2"));
+    assert_eq!(file.line(4), None);
+  }
+
+  #[test]
+  fn test_multiple_files() {
+    let mut map = SourceMap::new();
+    let a = map.add_file("a.cliq", "var a = 1");
+    let b = map.add_file("b.cliq", "\n\nvar b = a + 2");
+    assert_ne!(a, b);
+
+    let tokens_a = map.lex(a).unwrap();
+    let tokens_b = map.lex(b).unwrap();
+    let ident_a = tokens_a.iter().find(|t| t.token_t == TokenT::IDENTIFIER).unwrap();
+    let ident_b = tokens_b.iter().find(|t| t.token_t == TokenT::IDENTIFIER).unwrap();
+
+    assert_eq!(ident_a.span.file, Some(a));
+    assert_eq!(ident_b.span.file, Some(b));
+    assert_eq!(ident_a.codepos.file.as_deref(), Some("a.cliq"));
+    assert_eq!(map.source_text(ident_b.span), Some("b"));
+    assert_eq!(map.resolve(ident_b.span), Some(ident_b.codepos.clone()));
+
+    let pos = map.resolve(ident_b.span).unwrap();
+    assert_eq!(pos, Codepos::new(3, 5, Some("b.cliq".to_string())));
+  }
+}
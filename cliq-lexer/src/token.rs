@@ -1,4 +1,4 @@
-use crate::{token_t::TokenT, codepos::Codepos};
+use crate::{codepos::Codepos, source_map::Span, token_t::TokenT};
 use serde::Serialize;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -6,14 +6,16 @@ pub struct Token {
   pub token_t: TokenT,
   pub value: String,
   pub codepos: Codepos,
+  pub span: Span,
 }
 
 impl Token {
-  pub fn new(token_t: TokenT, value: String, codepos: Codepos) -> Self {
+  pub fn new(token_t: TokenT, value: String, codepos: Codepos, span: Span) -> Self {
     Self {
       token_t,
       value,
       codepos,
+      span,
     }
   }
 }
\ No newline at end of file
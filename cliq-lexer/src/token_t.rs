@@ -3,6 +3,7 @@ use serde::Serialize;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum TokenT {
   WHITESPACE,
+  COMMENT, // until end of line
 
   //VALUES
   INTEGER,
@@ -14,6 +15,7 @@ pub enum TokenT {
 
   //SYMBOL
   BRACKET, // ( ) [ ] { }
+  COMMA,   // between the arguments of a call
 
   //KEYWORD
   VAR, //used for variable declaration always immutable
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use cliq_parser::cst::syntax_kind::SyntaxKind;
+use serde::Deserialize;
+
+use crate::{Context, Level, Lint};
+
+//file a project keeps next to its sources, read by `cliq lint` when no other config is given
+pub const CONFIG_FILE: &str = "cliq-lint.toml";
+
+//comments starting with this change the level of rules for one statement:
+//on a line of their own for the statement below, behind code for the statement on that line
+pub const DIRECTIVE: &str = "cliq-lint:";
+
+//levels by rule name, rules that are not mentioned keep their default level
+//  [rules]
+//  redundant_parens = "allow"
+//  non_snake_case = "deny"
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+  #[serde(default)]
+  rules: BTreeMap<String, Level>,
+}
+
+impl Config {
+  pub fn parse(src: &str) -> Result<Config, String> {
+    toml::from_str(src).map_err(|err| err.message().to_string())
+  }
+
+  pub fn set(&mut self, rule: &str, level: Level) {
+    self.rules.insert(rule.to_string(), level);
+  }
+
+  pub fn level(&self, rule: &str) -> Option<Level> {
+    self.rules.get(rule).copied()
+  }
+
+  //rule names a linter does not know, most likely typos
+  pub fn unknown_rules<'a>(&'a self, known: impl Fn(&str) -> bool + 'a) -> impl Iterator<Item = &'a str> + 'a {
+    self.rules.keys().map(String::as_str).filter(move |rule| !known(rule))
+  }
+}
+
+//the inline comments of one source
+pub(crate) struct Directives {
+  //statement range, rule and level
+  levels: Vec<(Range<usize>, String, Level)>,
+  errors: Vec<Lint>,
+}
+
+impl Directives {
+  pub(crate) fn new(cx: &Context<'_>, known: impl Fn(&str) -> bool) -> Self {
+    let mut directives = Directives {
+      levels: vec![],
+      errors: vec![],
+    };
+    let statements: Vec<Range<usize>> = cx.statements().iter().map(|statement| statement.text_range()).collect();
+    let tokens = cx.root.tokens();
+    for (i, token) in tokens.iter().enumerate() {
+      if token.kind() != SyntaxKind::COMMENT {
+        continue;
+      }
+      let Some(directive) = token.text()[2..].trim().strip_prefix(DIRECTIVE) else {
+        continue;
+      };
+      let range = token.text_range();
+      let Some((level, rules)) = parse(directive) else {
+        directives
+          .errors
+          .push(Lint::new("directives", range, "expected `allow(...)`, `warn(...)` or `deny(...)`"));
+        continue;
+      };
+
+      //behind code the comment belongs to the statement on its line
+      let trailing = tokens[..i]
+        .iter()
+        .rev()
+        .take_while(|token| !token.text().contains('\n'))
+        .any(|token| !token.kind().is_trivia());
+      let statement = if trailing {
+        statements.iter().rev().find(|statement| statement.start < range.start)
+      } else {
+        statements.iter().find(|statement| statement.start > range.start)
+      };
+      for rule in rules {
+        if !known(rule) {
+          directives
+            .errors
+            .push(Lint::new("directives", range.clone(), format!("unknown lint rule `{}`", rule)));
+        } else if let Some(statement) = statement {
+          directives.levels.push((statement.clone(), rule.to_string(), level));
+        }
+      }
+    }
+    directives
+  }
+
+  //the level set for the statement at `offset`, later comments win
+  pub(crate) fn level(&self, rule: &str, offset: usize) -> Option<Level> {
+    self
+      .levels
+      .iter()
+      .rev()
+      .find(|(statement, name, _)| name == rule && statement.contains(&offset))
+      .map(|(_, _, level)| *level)
+  }
+
+  pub(crate) fn errors(&self) -> &[Lint] {
+    &self.errors
+  }
+}
+
+//`allow(a, b)`
+fn parse(directive: &str) -> Option<(Level, Vec<&str>)> {
+  let (level, rules) = directive.trim().strip_suffix(')')?.split_once('(')?;
+  let level = Level::from_name(level.trim())?;
+  let rules: Vec<&str> = rules.split(',').map(str::trim).collect();
+  if rules.iter().any(|rule| rule.is_empty()) {
+    return None;
+  }
+  Some((level, rules))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_config() {
+    let config = Config::parse("[rules]\nshadowing = \"deny\"\nunused_variables = \"allow\"").unwrap();
+    assert_eq!(config.level("shadowing"), Some(Level::Deny));
+    assert_eq!(config.level("unused_variables"), Some(Level::Allow));
+    assert_eq!(config.level("redundant_parens"), None);
+    assert_eq!(Config::parse(""), Ok(Config::default()));
+
+    let mut set = Config::default();
+    set.set("shadowing", Level::Deny);
+    set.set("unused_variables", Level::Allow);
+    assert_eq!(set, config);
+
+    assert!(Config::parse("[rules]\nshadowing = \"never\"")
+      .unwrap_err()
+      .contains("unknown variant `never`"));
+    assert!(Config::parse("[lints]").is_err());
+    let config = Config::parse("[rules]\nshadowin = \"deny\"").unwrap();
+    assert_eq!(
+      config.unknown_rules(|rule| rule == "shadowing").collect::<Vec<_>>(),
+      vec!["shadowin"]
+    );
+  }
+
+  #[test]
+  fn test_parse_directive() {
+    assert_eq!(parse(" allow(a, b_c) "), Some((Level::Allow, vec!["a", "b_c"])));
+    assert_eq!(parse("deny(a)"), Some((Level::Deny, vec!["a"])));
+    assert_eq!(parse("allow()"), None);
+    assert_eq!(parse("allow(a"), None);
+    assert_eq!(parse("forbid(a)"), None);
+  }
+}
@@ -0,0 +1,119 @@
+use cliq_parser::cst::{self, fix};
+
+use crate::{Applicability, Fix, LintError, Linter};
+
+//the source never converges when fixes keep making room for each other, this is plenty for real code
+const MAX_ROUNDS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixed {
+  pub src: String,
+  //the messages of the applied fixes, in the order they were applied
+  pub applied: Vec<String>,
+}
+
+//applies the machine applicable fixes of syntax errors and lints until there are none left,
+//a round whose result has more syntax errors than before, or lints that do not parse anymore, is thrown away
+pub fn fix(src: &str, linter: &Linter) -> Result<Fixed, LintError> {
+  let mut fixed = Fixed {
+    src: src.to_string(),
+    applied: vec![],
+  };
+  let mut errors = syntax_errors(src)?;
+  for _ in 0..MAX_ROUNDS {
+    let candidates = if errors > 0 {
+      cst::parse(&fixed.src)
+        .map_err(LintError::Lex)?
+        .errors()
+        .iter()
+        .filter_map(|err| err.fix.clone())
+        .collect()
+    } else {
+      linter.check(&fixed.src)?.into_iter().filter_map(|lint| lint.fix).collect()
+    };
+    let selected = select(candidates);
+    if selected.is_empty() {
+      break;
+    }
+    //all of them at once, or only the first if they do not go together
+    let round = [selected.as_slice(), &selected[..1]].into_iter().find_map(|fixes| {
+      let src = fix::apply(&fixed.src, fixes);
+      let after = syntax_errors(&src).ok()?;
+      let better = if errors > 0 {
+        after < errors
+      } else {
+        after == 0 && linter.check(&src).is_ok()
+      };
+      better.then_some((fixes, src, after))
+    });
+    let Some((fixes, src, after)) = round else {
+      break;
+    };
+    fixed.applied.extend(fixes.iter().map(|fix| fix.msg.clone()));
+    fixed.src = src;
+    errors = after;
+  }
+  Ok(fixed)
+}
+
+fn syntax_errors(src: &str) -> Result<usize, LintError> {
+  Ok(cst::parse(src).map_err(LintError::Lex)?.errors().len())
+}
+
+//the first of overlapping fixes wins, the others are left for the next round
+fn select(candidates: Vec<Fix>) -> Vec<Fix> {
+  let mut selected: Vec<Fix> = vec![];
+  for fix in candidates {
+    if fix.applicability == Applicability::MachineApplicable && !selected.iter().any(|other| other.overlaps(&fix)) {
+      selected.push(fix);
+    }
+  }
+  selected
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::Level;
+
+  #[test]
+  fn test_fix_syntax_errors() {
+    let fixed = fix("var x 3\nvar y = (x + 2\ny * (x", &Linter::default()).unwrap();
+    //the parentheses that were closed are redundant in the next round
+    assert_eq!(fixed.src, "var x = 3\nvar y = x + 2\ny * x");
+    assert_eq!(
+      fixed.applied,
+      vec!["insert `=`", "insert `)`", "insert `)`", "remove the parentheses", "remove the parentheses"]
+    );
+  }
+
+  #[test]
+  fn test_fix_lints() {
+    //syntax errors first, then the lints of the result
+    let fixed = fix("var a = ((1)\nvar b = b\nvar unused = a\nb + cont", &Linter::default()).unwrap();
+    assert_eq!(fixed.src, "var a = 1\nvar unused = a\nb + cont");
+    //renames are only suggested
+    let lints = Linter::default().check(&fixed.src).unwrap();
+    assert!(lints.iter().all(|lint| lint
+      .fix
+      .as_ref()
+      .is_none_or(|fix| fix.applicability == Applicability::MaybeIncorrect)));
+  }
+
+  #[test]
+  fn test_allowed_lints_are_not_fixed() {
+    let mut config = Config::default();
+    config.set("redundant_parens", Level::Allow);
+    let fixed = fix("var a = (1)\na", &Linter::new(config)).unwrap();
+    assert_eq!(fixed.src, "var a = (1)\na");
+    assert!(fixed.applied.is_empty());
+  }
+
+  #[test]
+  fn test_unfixable() {
+    let fixed = fix("var 3", &Linter::default()).unwrap();
+    assert_eq!(fixed.src, "var 3");
+    assert!(matches!(fix("var a = 1 $", &Linter::default()), Err(LintError::Lex(_))));
+  }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::ops::Range;
+
+use cliq_lexer::{lexer::Lexer, token::Token};
+use cliq_opt::resolve::{self, Resolution};
+use cliq_parser::arena::{Ast, Spans};
+use cliq_parser::cst::{self, red::SyntaxNode, SyntaxError};
+use serde::Deserialize;
+
+use self::config::{Config, Directives};
+
+pub mod config;
+pub mod fix;
+pub mod rules;
+
+pub use cliq_parser::cst::fix::{Applicability, Fix};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+impl Level {
+  pub fn from_name(name: &str) -> Option<Level> {
+    match name {
+      "allow" => Some(Level::Allow),
+      "warn" => Some(Level::Warn),
+      "deny" => Some(Level::Deny),
+      _ => None,
+    }
+  }
+
+  //as written in the config file and inline comments
+  pub fn name(self) -> &'static str {
+    match self {
+      Level::Allow => "allow",
+      Level::Warn => "warn",
+      Level::Deny => "deny",
+    }
+  }
+}
+
+impl fmt::Display for Level {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Level::Allow => write!(f, "allow"),
+      Level::Warn => write!(f, "warning"),
+      Level::Deny => write!(f, "error"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+  pub rule: &'static str,
+  //the rule's level after the config and inline comments were applied
+  pub level: Level,
+  pub range: Range<usize>,
+  pub msg: String,
+  pub fix: Option<Fix>,
+}
+
+impl Lint {
+  pub fn new(rule: &'static str, range: Range<usize>, msg: impl Into<String>) -> Self {
+    Self {
+      rule,
+      level: Level::Warn,
+      range,
+      msg: msg.into(),
+      fix: None,
+    }
+  }
+
+  pub fn with_fix(mut self, fix: Fix) -> Self {
+    self.fix = Some(fix);
+    self
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintError {
+  Lex(String),
+  Syntax(Vec<SyntaxError>),
+}
+
+impl fmt::Display for LintError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LintError::Lex(msg) => write!(f, "{}", msg),
+      LintError::Syntax(errors) => {
+        let msgs: Vec<&str> = errors.iter().map(|err| err.msg.as_str()).collect();
+        write!(f, "{}", msgs.join("\n"))
+      }
+    }
+  }
+}
+
+//everything a rule can look at, all views are of the same error free source
+pub struct Context<'a> {
+  pub src: &'a str,
+  pub tokens: &'a [Token],
+  pub root: SyntaxNode,
+  pub ast: Ast,
+  pub spans: Spans,
+  pub resolution: Resolution,
+}
+
+impl Context<'_> {
+  //the top level statement nodes of the tree, in the same order as the Ast body
+  pub fn statements(&self) -> Vec<SyntaxNode> {
+    self.root.children()
+  }
+}
+
+pub trait Rule {
+  //the name used in the config file and in inline comments
+  fn name(&self) -> &'static str;
+
+  fn description(&self) -> &'static str;
+
+  fn default_level(&self) -> Level {
+    Level::Warn
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint>;
+}
+
+pub struct Linter {
+  rules: Vec<Box<dyn Rule>>,
+  config: Config,
+}
+
+impl Default for Linter {
+  fn default() -> Self {
+    Self::new(Config::default())
+  }
+}
+
+impl Linter {
+  //with every rule of the rules module
+  pub fn new(config: Config) -> Self {
+    Self {
+      rules: rules::all(),
+      config,
+    }
+  }
+
+  pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+    self.rules.push(rule);
+  }
+
+  pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+    self.rules.iter().map(|rule| rule.as_ref())
+  }
+
+  pub fn rule(&self, name: &str) -> Option<&dyn Rule> {
+    self.rules().find(|rule| rule.name() == name)
+  }
+
+  //lints of sources with syntax errors would point at code that is not what the author meant, such sources are refused
+  pub fn check(&self, src: &str) -> Result<Vec<Lint>, LintError> {
+    let tokens = Lexer::new().lex(src).map_err(LintError::Lex)?;
+    let parse = cst::parse_tokens(src, &tokens);
+    if !parse.errors().is_empty() {
+      return Err(LintError::Syntax(parse.errors().to_vec()));
+    }
+    let root = parse.syntax();
+    let (ast, spans) = Ast::from_cst(&root).map_err(|err| LintError::Syntax(vec![err]))?;
+    let resolution = resolve::resolve(&ast);
+    let cx = Context {
+      src,
+      tokens: &tokens,
+      root,
+      ast,
+      spans,
+      resolution,
+    };
+
+    let directives = Directives::new(&cx, |name| self.rule(name).is_some());
+    let mut lints = directives.errors().to_vec();
+    for rule in self.rules() {
+      let configured = self.config.level(rule.name()).unwrap_or(rule.default_level());
+      for mut lint in rule.check(&cx) {
+        lint.level = directives.level(rule.name(), lint.range.start).unwrap_or(configured);
+        if lint.level != Level::Allow {
+          lints.push(lint);
+        }
+      }
+    }
+    lints.sort_by(|a, b| (a.range.start, a.rule).cmp(&(b.range.start, b.rule)));
+    Ok(lints)
+  }
+}
+
+pub fn lint(src: &str, config: &Config) -> Result<Vec<Lint>, LintError> {
+  Linter::new(config.clone()).check(src)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_config_and_directives() {
+    let src =
+      "var fooBar = (1)\n// cliq-lint: allow(redundant_parens)\nvar x = (2) + fooBar\nx / (3) // cliq-lint: deny(redundant_parens)\n";
+    let lints = lint(src, &Config::default()).unwrap();
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, lint.level, &src[lint.range.clone()])).collect();
+    assert_eq!(
+      found,
+      vec![
+        ("non_snake_case", Level::Warn, "fooBar"),
+        ("redundant_parens", Level::Warn, "(1)"),
+        ("redundant_parens", Level::Deny, "(3)"),
+      ]
+    );
+
+    let config = Config::parse("[rules]\nnon_snake_case = \"deny\"\nredundant_parens = \"allow\"\n").unwrap();
+    let lints = lint(src, &config).unwrap();
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, lint.level)).collect();
+    assert_eq!(found, vec![("non_snake_case", Level::Deny), ("redundant_parens", Level::Deny)]);
+  }
+
+  #[test]
+  fn test_invalid_directives() {
+    let lints = lint("// cliq-lint: allow(no_such_rule)\n1\n// cliq-lint: forbid\n2", &Config::default()).unwrap();
+    let found: Vec<_> = lints
+      .iter()
+      .map(|lint| (lint.rule, lint.range.clone(), lint.msg.as_str()))
+      .collect();
+    assert_eq!(
+      found,
+      vec![
+        ("directives", 0..33, "unknown lint rule `no_such_rule`"),
+        ("directives", 36..56, "expected `allow(...)`, `warn(...)` or `deny(...)`"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sources_with_errors() {
+    assert_eq!(
+      lint("a $ b", &Config::default()),
+      Err(LintError::Lex("Unexpected character at line 1, column 3".to_string()))
+    );
+    assert!(matches!(lint("var = 1", &Config::default()), Err(LintError::Syntax(_))));
+  }
+}
@@ -0,0 +1,148 @@
+use crate::Rule;
+
+pub mod names;
+pub mod parens;
+pub mod suspicious;
+
+pub use self::names::{NonSnakeCase, Shadowing, UndefinedVariables, UnusedVariables};
+pub use self::parens::RedundantParens;
+pub use self::suspicious::{DivisionByZero, SelfAssignment};
+
+pub fn all() -> Vec<Box<dyn Rule>> {
+  vec![
+    Box::new(UndefinedVariables),
+    Box::new(UnusedVariables),
+    Box::new(Shadowing),
+    Box::new(NonSnakeCase),
+    Box::new(RedundantParens),
+    Box::new(SelfAssignment),
+    Box::new(DivisionByZero),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::{Level, Linter};
+
+  //the text each lint points at and its message, with the source after applying every fix
+  fn check(rule: &str, src: &str) -> (Vec<(String, String)>, String) {
+    let mut config = Config::default();
+    for other in all() {
+      let level = if other.name() == rule { Level::Warn } else { Level::Allow };
+      config.set(other.name(), level);
+    }
+    let lints = Linter::new(config).check(src).unwrap();
+    let found = lints
+      .iter()
+      .map(|lint| (src[lint.range.clone()].to_string(), lint.msg.clone()))
+      .collect();
+    let mut edits: Vec<_> = lints.iter().filter_map(|lint| lint.fix.clone()).flat_map(|fix| fix.edits).collect();
+    edits.sort_by_key(|edit| edit.range.start);
+    let fixed = edits.iter().rev().fold(src.to_string(), |src, edit| edit.apply(&src));
+    (found, fixed)
+  }
+
+  fn texts(found: &[(String, String)]) -> Vec<&str> {
+    found.iter().map(|(text, _)| text.as_str()).collect()
+  }
+
+  //a fixed source must be clean for the rule and still parse
+  fn assert_fixed(rule: &str, fixed: &str) {
+    assert_eq!(check(rule, fixed).0, vec![], "{:?}", fixed);
+  }
+
+  #[test]
+  fn test_rule_names_are_unique() {
+    let mut names: Vec<_> = all().iter().map(|rule| rule.name()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), all().len());
+    assert!(all().iter().all(|rule| !rule.description().is_empty()));
+  }
+
+  #[test]
+  fn test_undefined_variables() {
+    let (found, fixed) = check("undefined_variables", "var a = b\na + c\nvar c = 1\nvar count = 2\ncont + a");
+    assert_eq!(
+      found,
+      vec![
+        ("b".to_string(), "undefined variable `b`".to_string()),
+        ("c".to_string(), "variable `c` is used before it is declared".to_string()),
+        ("cont".to_string(), "undefined variable `cont`".to_string()),
+      ]
+    );
+    //`b` is too short to guess which variable was meant
+    assert_eq!(fixed, "var a = b\na + c\nvar c = 1\nvar count = 2\ncount + a");
+    assert_eq!(UndefinedVariables.default_level(), Level::Deny);
+  }
+
+  #[test]
+  fn test_unused_variables() {
+    let (found, fixed) = check("unused_variables", "var a = 1\nvar b = 2\nvar _c = 3\nb");
+    assert_eq!(found, vec![("a".to_string(), "unused variable `a`".to_string())]);
+    assert_eq!(fixed, "var _a = 1\nvar b = 2\nvar _c = 3\nb");
+    assert_fixed("unused_variables", &fixed);
+  }
+
+  #[test]
+  fn test_shadowing() {
+    let (found, _) = check("shadowing", "var a = 1\nvar b = a\nvar a = b + a\na");
+    assert_eq!(found, vec![("a".to_string(), "`a` shadows an earlier declaration".to_string())]);
+  }
+
+  #[test]
+  fn test_non_snake_case() {
+    let (found, fixed) = check(
+      "non_snake_case",
+      "var fooBar = 1\nvar HTTPServer = fooBar * 2\nvar ok_1 = HTTPServer + fooBar\nok_1",
+    );
+    assert_eq!(texts(&found), vec!["fooBar", "HTTPServer"]);
+    assert_eq!(found[0].1, "variable `fooBar` should have a snake case name such as `foo_bar`");
+    assert_eq!(
+      fixed,
+      "var foo_bar = 1\nvar http_server = foo_bar * 2\nvar ok_1 = http_server + foo_bar\nok_1"
+    );
+    assert_fixed("non_snake_case", &fixed);
+  }
+
+  #[test]
+  fn test_redundant_parens() {
+    let src = "var a = (1)\nvar b = (a * 2) + (a - 1) - (a)\n(-b) * -(a) - (-(b))\nvar c = -((a + 1) * (b / 2))\n(b % a)";
+    let (found, fixed) = check("redundant_parens", src);
+    assert_eq!(texts(&found), vec!["(1)", "(a * 2)", "(a)", "(a)", "(-(b))", "(b)", "(b % a)"]);
+    assert_eq!(found[0].1, "unnecessary parentheses");
+    assert_eq!(
+      fixed,
+      "var a = 1\nvar b = a * 2 + (a - 1) - a\n(-b) * -a - -b\nvar c = -((a + 1) * (b / 2))\nb % a"
+    );
+    assert_fixed("redundant_parens", &fixed);
+    assert_eq!(
+      cliq_parser::cst::parse(&fixed).unwrap().to_ast(),
+      cliq_parser::cst::parse(src).unwrap().to_ast()
+    );
+  }
+
+  #[test]
+  fn test_self_assignment() {
+    let (found, fixed) = check(
+      "self_assignment",
+      "var a = 1\nvar a = a // same\nvar a = (a)\nvar b = a\nvar b = b\n(-b)",
+    );
+    assert_eq!(texts(&found), vec!["var a = a", "var a = (a)", "var b = b"]);
+    assert_eq!(found[0].1, "`a` is assigned to itself");
+    assert_eq!(fixed, "var a = 1\nvar b = a\n(-b)");
+    assert_fixed("self_assignment", &fixed);
+  }
+
+  #[test]
+  fn test_division_by_zero() {
+    let (found, fixed) = check("division_by_zero", "var a = 1 / 0\na % (0)\na / 0.0\na / 0.5\na * 0");
+    assert_eq!(texts(&found), vec!["1 / 0", "a % (0)", "a / 0.0"]);
+    assert_eq!(found[0].1, "division by zero");
+    assert_eq!(found[1].1, "remainder of a division by zero");
+    assert_eq!(fixed, "var a = 1 / 0\na % (0)\na / 0.0\na / 0.5\na * 0");
+    assert_eq!(DivisionByZero.default_level(), Level::Deny);
+  }
+}
@@ -0,0 +1,184 @@
+use cliq_opt::resolve::NameErrorKind;
+use cliq_parser::cst::incremental::TextEdit;
+
+use crate::{Context, Fix, Level, Lint, Rule};
+
+//rules built on the name resolution of cliq_opt
+
+pub struct UndefinedVariables;
+
+impl Rule for UndefinedVariables {
+  fn name(&self) -> &'static str {
+    "undefined_variables"
+  }
+
+  fn description(&self) -> &'static str {
+    "variables that are not declared, or only further down"
+  }
+
+  //such a program fails as soon as it gets there
+  fn default_level(&self) -> Level {
+    Level::Deny
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    cx.resolution
+      .errors()
+      .iter()
+      .filter(|err| matches!(err.kind, NameErrorKind::UndefinedVariable | NameErrorKind::UseBeforeDefinition(_)))
+      .map(|err| {
+        let range = cx.spans.exprs[err.expr].clone();
+        let mut lint = Lint::new(self.name(), range.clone(), err.to_string());
+        //most likely a typo
+        if err.kind == NameErrorKind::UndefinedVariable {
+          if let Some(similar) = cx.resolution.similar_declaration(&err.name, err.expr) {
+            let name = &cx.resolution.declaration(similar).name;
+            let fix = Fix::new(
+              format!("a variable with a similar name exists: `{}`", name),
+              vec![TextEdit::new(range, name.clone())],
+            );
+            lint = lint.with_fix(fix.maybe_incorrect());
+          }
+        }
+        lint
+      })
+      .collect()
+  }
+}
+
+pub struct UnusedVariables;
+
+impl Rule for UnusedVariables {
+  fn name(&self) -> &'static str {
+    "unused_variables"
+  }
+
+  fn description(&self) -> &'static str {
+    "variables that are declared but never read, names starting with `_` are exempt"
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    cx.resolution
+      .errors()
+      .iter()
+      .filter(|err| err.kind == NameErrorKind::UnusedVariable)
+      .map(|err| {
+        let range = cx.spans.exprs[err.expr].clone();
+        let fix = Fix::new(
+          format!("if this is intentional, prefix it with an underscore: `_{}`", err.name),
+          vec![TextEdit::new(range.start..range.start, "_")],
+        )
+        //the host may read the variable by its name
+        .maybe_incorrect();
+        Lint::new(self.name(), range, err.to_string()).with_fix(fix)
+      })
+      .collect()
+  }
+}
+
+pub struct Shadowing;
+
+impl Rule for Shadowing {
+  fn name(&self) -> &'static str {
+    "shadowing"
+  }
+
+  fn description(&self) -> &'static str {
+    "declarations that hide an earlier variable of the same name"
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    let declarations: Vec<_> = cx.resolution.declarations().collect();
+    let mut lints = vec![];
+    for (i, (_, declaration)) in declarations.iter().enumerate() {
+      let Some(variable) = declaration.variable else {
+        continue;
+      };
+      let shadows = declarations[..i]
+        .iter()
+        .any(|(_, earlier)| earlier.name == declaration.name && cx.resolution.encloses(earlier.scope, declaration.scope));
+      if shadows {
+        lints.push(Lint::new(
+          self.name(),
+          cx.spans.exprs[variable].clone(),
+          format!("`{}` shadows an earlier declaration", declaration.name),
+        ));
+      }
+    }
+    lints
+  }
+}
+
+pub struct NonSnakeCase;
+
+impl Rule for NonSnakeCase {
+  fn name(&self) -> &'static str {
+    "non_snake_case"
+  }
+
+  fn description(&self) -> &'static str {
+    "variable names that are not lowercase words joined by underscores"
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    let mut lints = vec![];
+    for (_, declaration) in cx.resolution.declarations() {
+      let Some(variable) = declaration.variable else {
+        continue;
+      };
+      let snake = to_snake_case(&declaration.name);
+      if snake == declaration.name {
+        continue;
+      }
+      let range = cx.spans.exprs[variable].clone();
+      let mut lint = Lint::new(
+        self.name(),
+        range.clone(),
+        format!("variable `{}` should have a snake case name such as `{}`", declaration.name, snake),
+      );
+      //renaming onto a name that is taken would change what the uses refer to
+      let taken = snake == "var" || cx.resolution.declarations().any(|(_, other)| other.name == snake);
+      if !taken {
+        let edits = std::iter::once(range)
+          .chain(declaration.uses.iter().map(|expr| cx.spans.exprs[*expr].clone()))
+          .map(|range| TextEdit::new(range, snake.clone()))
+          .collect();
+        lint = lint.with_fix(Fix::new(format!("rename to `{}`", snake), edits).maybe_incorrect());
+      }
+      lints.push(lint);
+    }
+    lints
+  }
+}
+
+//`fooBar` and `FooBar` become `foo_bar`, runs of capitals are one word: `HTTPServer` becomes `http_server`
+pub fn to_snake_case(name: &str) -> String {
+  let chars: Vec<char> = name.chars().collect();
+  let mut snake = String::new();
+  for (i, c) in chars.iter().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      let previous = chars[i - 1];
+      let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+      if previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower) {
+        snake.push('_');
+      }
+    }
+    snake.extend(c.to_lowercase());
+  }
+  snake
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_to_snake_case() {
+    assert_eq!(to_snake_case("fooBar"), "foo_bar");
+    assert_eq!(to_snake_case("FooBar"), "foo_bar");
+    assert_eq!(to_snake_case("HTTPServer"), "http_server");
+    assert_eq!(to_snake_case("value2X"), "value2_x");
+    assert_eq!(to_snake_case("_Private"), "_private");
+    assert_eq!(to_snake_case("already_snake_1"), "already_snake_1");
+  }
+}
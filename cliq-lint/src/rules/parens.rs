@@ -0,0 +1,84 @@
+use cliq_parser::cst::incremental::TextEdit;
+use cliq_parser::cst::red::SyntaxNode;
+use cliq_parser::cst::syntax_kind::SyntaxKind;
+use cliq_parser::expression::operator::{BinOp, UnOp};
+
+use crate::{Context, Fix, Lint, Rule};
+
+pub struct RedundantParens;
+
+impl Rule for RedundantParens {
+  fn name(&self) -> &'static str {
+    "redundant_parens"
+  }
+
+  fn description(&self) -> &'static str {
+    "parentheses that do not change how an expression is grouped"
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    let mut parens: Vec<SyntaxNode> = cx
+      .root
+      .descendants()
+      .into_iter()
+      .filter(|node| node.kind() == SyntaxKind::PAREN_EXPR && is_redundant(node))
+      .collect();
+    parens.sort_by_key(|node| node.text_range().start);
+    parens
+      .into_iter()
+      .map(|node| {
+        //only the brackets go, whatever is between them stays as it is
+        let edits = node
+          .significant_tokens()
+          .into_iter()
+          .map(|bracket| TextEdit::new(bracket.text_range(), ""))
+          .collect();
+        Lint::new(self.name(), node.text_range(), "unnecessary parentheses").with_fix(Fix::new("remove the parentheses", edits))
+      })
+      .collect()
+  }
+}
+
+//same rules the printer of cliq_fmt uses to decide where parentheses are needed
+fn precedence(node: &SyntaxNode) -> i32 {
+  let operator = || node.significant_tokens()[0].text().to_string();
+  match node.kind() {
+    SyntaxKind::BINARY_EXPR => BinOp::from_symbol(&operator()).map_or(i32::MAX, BinOp::precedence),
+    SyntaxKind::UNARY_EXPR => UnOp::from_symbol(&operator()).map_or(i32::MAX, UnOp::precedence),
+    _ => i32::MAX,
+  }
+}
+
+fn is_redundant(paren: &SyntaxNode) -> bool {
+  let Some(parent) = paren.parent() else {
+    return false;
+  };
+  let inner = precedence(&paren.children()[0]);
+  let needed = match parent.kind() {
+    SyntaxKind::BINARY_EXPR => {
+      let op = BinOp::from_symbol(parent.significant_tokens()[0].text());
+      let is_rhs = parent.children().get(1) == Some(paren);
+      op.is_some_and(|op| op.needs_parentheses(inner, is_rhs))
+    }
+    SyntaxKind::UNARY_EXPR => UnOp::from_symbol(parent.significant_tokens()[0].text()).is_some_and(|op| inner < op.precedence()),
+    _ => false,
+  };
+  !needed && !starts_with_minus(paren)
+}
+
+//a statement that starts with `-` continues the one above, `a\n(-b)` are two statements but `a\n-b` is one
+fn starts_with_minus(paren: &SyntaxNode) -> bool {
+  let tokens = paren.significant_tokens();
+  let Some(inner) = paren.children()[0].tokens().into_iter().find(|token| !token.kind().is_trivia()) else {
+    return false;
+  };
+  let mut statement = paren.clone();
+  while let Some(parent) = statement.parent() {
+    if parent.kind() == SyntaxKind::ROOT {
+      break;
+    }
+    statement = parent.clone();
+  }
+  let first = statement.tokens().into_iter().find(|token| !token.kind().is_trivia());
+  first.as_ref() == tokens.first() && inner.text() == "-"
+}
@@ -0,0 +1,78 @@
+use cliq_parser::arena::{Expr, Stmt};
+use cliq_parser::cst::incremental::TextEdit;
+use cliq_parser::expression::operator::BinOp;
+
+use crate::{Context, Fix, Level, Lint, Rule};
+
+pub struct SelfAssignment;
+
+impl Rule for SelfAssignment {
+  fn name(&self) -> &'static str {
+    "self_assignment"
+  }
+
+  fn description(&self) -> &'static str {
+    "`var a = a`, which declares a variable with the value it already has"
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    let mut lints = vec![];
+    for id in cx.ast.body() {
+      let Stmt::Assign { variable, value, .. } = *cx.ast.stmt(*id) else {
+        continue;
+      };
+      //parentheses are already gone in the Ast
+      let (Expr::Variable(name), Expr::Variable(read)) = (cx.ast.expr(variable), cx.ast.expr(value)) else {
+        continue;
+      };
+      if name != read {
+        continue;
+      }
+      //the statement together with the rest of its line
+      let range = cx.spans.stmts[*id].clone();
+      let end = cx.src[range.end..].find('\n').map_or(cx.src.len(), |i| range.end + i + 1);
+      let fix = Fix::new("remove the statement", vec![TextEdit::new(range.start..end, "")]);
+      lints.push(Lint::new(self.name(), range, format!("`{}` is assigned to itself", cx.ast.name(*name))).with_fix(fix));
+    }
+    lints
+  }
+}
+
+pub struct DivisionByZero;
+
+impl Rule for DivisionByZero {
+  fn name(&self) -> &'static str {
+    "division_by_zero"
+  }
+
+  fn description(&self) -> &'static str {
+    "`/` and `%` with a literal zero on the right"
+  }
+
+  //an int division by zero is an error when it runs
+  fn default_level(&self) -> Level {
+    Level::Deny
+  }
+
+  fn check(&self, cx: &Context<'_>) -> Vec<Lint> {
+    cx.ast
+      .expr_ids()
+      .filter_map(|id| {
+        let Expr::Binary(op, _, rhs) = *cx.ast.expr(id) else {
+          return None;
+        };
+        let zero = match *cx.ast.expr(rhs) {
+          Expr::Int(val) => val == 0,
+          Expr::Float(val) => val == 0.0,
+          _ => false,
+        };
+        let msg = match op {
+          BinOp::Div if zero => "division by zero",
+          BinOp::Rem if zero => "remainder of a division by zero",
+          _ => return None,
+        };
+        Some(Lint::new(self.name(), cx.spans.exprs[id].clone(), msg))
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,363 @@
+use std::ops::Range;
+
+use cliq_fmt::{format_source, Config};
+use cliq_lexer::lexer::Lexer;
+use cliq_opt::typeck::TypeChecker;
+use cliq_opt::value::Type;
+use cliq_parser::cst::{
+  self,
+  incremental::IncrementalParse,
+  lower::{lower_expression, lower_statement},
+  red::{SyntaxNode, SyntaxToken},
+  syntax_kind::SyntaxKind,
+  Parse,
+};
+use cliq_parser::statement::Statement;
+
+//everything the server answers about one version of a document, in byte offsets
+//the protocol side converts them with a LineIndex
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub range: Range<usize>,
+  pub msg: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+  Keyword,
+  Variable,
+  Number,
+  Operator,
+  Comment,
+  Function,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightedToken {
+  pub range: Range<usize>,
+  pub highlight: Highlight,
+  //the name of a `var` statement rather than a use of it
+  pub declaration: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoverInfo {
+  pub range: Range<usize>,
+  pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+  pub name: String,
+  pub range: Range<usize>,
+  pub name_range: Range<usize>,
+  pub ty: Option<Type>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+  src: String,
+  //a source that does not lex has no tree, only the lexer error
+  parse: Result<Parse, Diagnostic>,
+}
+
+impl Analysis {
+  pub fn new(src: impl Into<String>) -> Self {
+    let src = src.into();
+    let mut lexer = Lexer::new();
+    let parse = match lexer.lex(&src) {
+      Ok(tokens) => Ok(cst::parse_tokens(&src, &tokens)),
+      Err(msg) => {
+        let start = lexer.offset();
+        let end = start + src[start..].chars().next().map_or(0, char::len_utf8);
+        Err(Diagnostic { range: start..end, msg })
+      }
+    };
+    Self { src, parse }
+  }
+
+  //shares the tree of a parse that is kept up to date with the edits of the editor
+  pub fn from_incremental(incremental: &IncrementalParse) -> Self {
+    match incremental.parse() {
+      Ok(parse) => Self {
+        src: incremental.src().to_string(),
+        parse: Ok(parse.clone()),
+      },
+      //the lexer error only has a position in its message, lexing again finds the offset
+      Err(_) => Self::new(incremental.src()),
+    }
+  }
+
+  pub fn src(&self) -> &str {
+    &self.src
+  }
+
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    match &self.parse {
+      Ok(parse) => parse
+        .errors()
+        .iter()
+        .map(|err| Diagnostic {
+          range: err.range.clone(),
+          msg: err.msg.clone(),
+        })
+        .collect(),
+      Err(err) => vec![err.clone()],
+    }
+  }
+
+  pub fn highlights(&self) -> Vec<HighlightedToken> {
+    let Some(root) = self.root() else {
+      return vec![];
+    };
+    root
+      .tokens()
+      .into_iter()
+      .filter_map(|token| {
+        let highlight = match token.kind() {
+          SyntaxKind::VAR_KW => Highlight::Keyword,
+          SyntaxKind::IDENTIFIER if is_function(&token) => Highlight::Function,
+          SyntaxKind::IDENTIFIER => Highlight::Variable,
+          SyntaxKind::INTEGER | SyntaxKind::FLOAT => Highlight::Number,
+          SyntaxKind::OPERATOR => Highlight::Operator,
+          SyntaxKind::COMMENT => Highlight::Comment,
+          _ => return None,
+        };
+        Some(HighlightedToken {
+          range: token.text_range(),
+          highlight,
+          declaration: is_declaration(&token),
+        })
+      })
+      .collect()
+  }
+
+  pub fn hover(&self, offset: usize) -> Option<HoverInfo> {
+    let root = self.root()?;
+    let token = token_at(&root, offset)?;
+    let checker = checker_before(&root, statement_index(&root, &token)?);
+    let parent = token.parent();
+    match (token.kind(), parent.kind()) {
+      (SyntaxKind::IDENTIFIER, SyntaxKind::ASSIGN_STMT) => {
+        let value = lower_expression(parent.children().first()?).ok()?;
+        let ty = checker.expression(&value).ok()?;
+        Some(HoverInfo {
+          range: token.text_range(),
+          text: format!("var {}: {}", token.text(), ty),
+        })
+      }
+      //functions are registered by the host, the editor knows nothing about them
+      (SyntaxKind::IDENTIFIER, SyntaxKind::CALL_EXPR) => None,
+      (SyntaxKind::IDENTIFIER, _) => Some(HoverInfo {
+        range: token.text_range(),
+        text: format!("{}: {}", token.text(), checker.get(token.text())?),
+      }),
+      (SyntaxKind::VAR_KW, _) | (_, SyntaxKind::ASSIGN_STMT | SyntaxKind::EXPR_STMT | SyntaxKind::ROOT) => None,
+      _ => {
+        let ty = checker.expression(&lower_expression(parent).ok()?).ok()?;
+        Some(HoverInfo {
+          range: parent.text_range(),
+          text: ty.to_string(),
+        })
+      }
+    }
+  }
+
+  //range of the name in the closest `var` statement before the use
+  pub fn definition(&self, offset: usize) -> Option<Range<usize>> {
+    let root = self.root()?;
+    let token = token_at(&root, offset)?;
+    if token.kind() != SyntaxKind::IDENTIFIER || is_function(&token) {
+      return None;
+    }
+    if is_declaration(&token) {
+      return Some(token.text_range());
+    }
+    let index = statement_index(&root, &token)?;
+    root.children()[..index]
+      .iter()
+      .rev()
+      .filter_map(declared_name)
+      .find(|name| name.text() == token.text())
+      .map(|name| name.text_range())
+  }
+
+  pub fn symbols(&self) -> Vec<Symbol> {
+    let Some(root) = self.root() else {
+      return vec![];
+    };
+    let mut checker = TypeChecker::new();
+    let mut symbols = vec![];
+    for statement in root.children() {
+      let Ok(ast) = lower_statement(&statement) else {
+        continue;
+      };
+      if let Some(name) = declared_name(&statement) {
+        let ty = match &ast {
+          Statement::Assign(stmt) => checker.expression(&stmt.value).ok(),
+          _ => None,
+        };
+        symbols.push(Symbol {
+          name: name.text().to_string(),
+          range: statement.text_range(),
+          name_range: name.text_range(),
+          ty,
+        });
+      }
+      let _ = checker.check(&[ast]);
+    }
+    symbols
+  }
+
+  //same output as cliqfmt so editors and the command line agree, None when the source has errors
+  pub fn format(&self) -> Option<String> {
+    format_source(&self.src, &Config::default()).ok()
+  }
+
+  fn root(&self) -> Option<SyntaxNode> {
+    self.parse.as_ref().ok().map(Parse::syntax)
+  }
+}
+
+//a cursor right behind a token still refers to it
+fn token_at(root: &SyntaxNode, offset: usize) -> Option<SyntaxToken> {
+  let significant = |token: &SyntaxToken| !token.kind().is_trivia();
+  root
+    .token_at_offset(offset)
+    .filter(significant)
+    .or_else(|| root.token_at_offset(offset.checked_sub(1)?).filter(significant))
+}
+
+fn statement_index(root: &SyntaxNode, token: &SyntaxToken) -> Option<usize> {
+  let mut node = token.parent().clone();
+  while let Some(parent) = node.parent() {
+    if parent.kind() == SyntaxKind::ROOT {
+      break;
+    }
+    node = parent.clone();
+  }
+  root.children().iter().position(|statement| statement == &node)
+}
+
+fn declared_name(statement: &SyntaxNode) -> Option<SyntaxToken> {
+  if statement.kind() != SyntaxKind::ASSIGN_STMT {
+    return None;
+  }
+  statement
+    .significant_tokens()
+    .into_iter()
+    .find(|token| token.kind() == SyntaxKind::IDENTIFIER)
+}
+
+fn is_declaration(token: &SyntaxToken) -> bool {
+  token.kind() == SyntaxKind::IDENTIFIER && token.parent().kind() == SyntaxKind::ASSIGN_STMT
+}
+
+//the name of a call, the only identifier directly inside a CALL_EXPR
+fn is_function(token: &SyntaxToken) -> bool {
+  token.kind() == SyntaxKind::IDENTIFIER && token.parent().kind() == SyntaxKind::CALL_EXPR
+}
+
+//types of the variables declared by the statements in front of `index`
+fn checker_before(root: &SyntaxNode, index: usize) -> TypeChecker {
+  let mut checker = TypeChecker::new();
+  for statement in &root.children()[..index] {
+    if let Ok(ast) = lower_statement(statement) {
+      let _ = checker.check(&[ast]);
+    }
+  }
+  checker
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SRC: &str = "var a = 1 // one\nvar b = a * 2.5\n(b - a) / -a";
+
+  #[test]
+  fn test_diagnostics() {
+    assert_eq!(Analysis::new(SRC).diagnostics(), vec![]);
+    assert_eq!(
+      Analysis::new("var a = 1\na $ 2").diagnostics(),
+      vec![Diagnostic {
+        range: 12..13,
+        msg: "Unexpected character at line 2, column 3".to_string()
+      }]
+    );
+    let diagnostics = Analysis::new("var = 1").diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range, 4..5);
+  }
+
+  #[test]
+  fn test_from_incremental() {
+    let mut incremental = IncrementalParse::new(SRC);
+    incremental.edit(&cst::incremental::TextEdit::new(8..9, "$"));
+    assert_eq!(Analysis::from_incremental(&incremental).diagnostics()[0].range, 8..9);
+    incremental.edit(&cst::incremental::TextEdit::new(8..9, "1.5"));
+    let analysis = Analysis::from_incremental(&incremental);
+    assert_eq!(analysis.diagnostics(), vec![]);
+    assert_eq!(analysis.hover(4).unwrap().text, "var a: float");
+  }
+
+  #[test]
+  fn test_highlights() {
+    let highlights = Analysis::new("var a = -1.5 // x\na").highlights();
+    let kinds: Vec<_> = highlights
+      .iter()
+      .map(|token| (token.range.clone(), token.highlight, token.declaration))
+      .collect();
+    assert_eq!(
+      kinds,
+      vec![
+        (0..3, Highlight::Keyword, false),
+        (4..5, Highlight::Variable, true),
+        (6..7, Highlight::Operator, false),
+        (8..9, Highlight::Operator, false),
+        (9..12, Highlight::Number, false),
+        (13..17, Highlight::Comment, false),
+        (18..19, Highlight::Variable, false),
+      ]
+    );
+    let highlights = Analysis::new("max(a, 1)").highlights();
+    assert_eq!(highlights[0].highlight, Highlight::Function);
+    assert_eq!(highlights[1].highlight, Highlight::Variable);
+  }
+
+  #[test]
+  fn test_hover() {
+    let analysis = Analysis::new(SRC);
+    let hover = |offset| analysis.hover(offset).map(|hover| (hover.range, hover.text));
+    assert_eq!(hover(4), Some((4..5, "var a: int".to_string())));
+    assert_eq!(hover(21), Some((21..22, "var b: float".to_string())));
+    assert_eq!(hover(26), Some((25..26, "a: int".to_string())));
+    //cursor right behind the name
+    assert_eq!(hover(26), hover(25));
+    assert_eq!(hover(36), Some((34..39, "float".to_string())));
+    assert_eq!(hover(41), Some((33..45, "float".to_string())));
+    assert_eq!(hover(43), Some((43..45, "int".to_string())));
+    assert_eq!(hover(0), None);
+    assert_eq!(hover(12), None);
+  }
+
+  #[test]
+  fn test_definition() {
+    let analysis = Analysis::new("var a = 1\nvar a = a + 1\na");
+    assert_eq!(analysis.definition(18), Some(4..5));
+    assert_eq!(analysis.definition(24), Some(14..15));
+    assert_eq!(analysis.definition(14), Some(14..15));
+    assert_eq!(Analysis::new("a\nvar a = 1").definition(0), None);
+  }
+
+  #[test]
+  fn test_symbols() {
+    let symbols = Analysis::new(SRC).symbols();
+    let symbols: Vec<_> = symbols
+      .iter()
+      .map(|symbol| (symbol.name.as_str(), symbol.range.clone(), symbol.ty))
+      .collect();
+    assert_eq!(symbols, vec![("a", 0..9, Some(Type::Int)), ("b", 17..32, Some(Type::Float))]);
+  }
+}
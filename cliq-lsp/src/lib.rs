@@ -0,0 +1,5 @@
+pub mod analysis;
+pub mod line_index;
+mod server;
+
+pub use self::server::{capabilities, run, ServerError};
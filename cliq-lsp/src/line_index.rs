@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+use lsp_types::Position;
+
+//converts byte offsets to protocol positions, whose characters are utf-16 code units
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+  line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+  pub fn new(src: &str) -> Self {
+    let mut line_starts = vec![0];
+    line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+    Self { line_starts }
+  }
+
+  pub fn position(&self, src: &str, offset: usize) -> Position {
+    let offset = offset.min(src.len());
+    let line = match self.line_starts.binary_search(&offset) {
+      Ok(line) => line,
+      Err(next) => next - 1,
+    };
+    let character = src[self.line_starts[line]..offset].encode_utf16().count();
+    Position::new(line as u32, character as u32)
+  }
+
+  //positions past the end of a line are clamped to it
+  pub fn offset(&self, src: &str, position: Position) -> usize {
+    let Some(&start) = self.line_starts.get(position.line as usize) else {
+      return src.len();
+    };
+    let end = self.line_starts.get(position.line as usize + 1).map_or(src.len(), |next| next - 1);
+    let mut units = 0;
+    for (i, c) in src[start..end].char_indices() {
+      if units >= position.character as usize {
+        return start + i;
+      }
+      units += c.len_utf16();
+    }
+    end
+  }
+
+  pub fn range(&self, src: &str, range: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(self.position(src, range.start), self.position(src, range.end))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_utf16_columns() {
+    let src = "a // ü𝄞\nbc\n";
+    let index = LineIndex::new(src);
+    assert_eq!(index.position(src, 0), Position::new(0, 0));
+    assert_eq!(index.position(src, src.find('\n').unwrap()), Position::new(0, 8));
+    assert_eq!(index.position(src, src.find('b').unwrap()), Position::new(1, 0));
+    assert_eq!(index.position(src, src.len()), Position::new(2, 0));
+
+    for offset in [0, 5, 7, 11, 12, 13, src.len()] {
+      assert_eq!(index.offset(src, index.position(src, offset)), offset);
+    }
+    assert_eq!(index.offset(src, Position::new(1, 10)), src.len() - 1);
+    assert_eq!(index.offset(src, Position::new(7, 0)), src.len());
+  }
+}
@@ -0,0 +1,16 @@
+use std::process::ExitCode;
+
+use lsp_server::Connection;
+
+//speaks the language server protocol on stdin and stdout, errors go to stderr
+fn main() -> ExitCode {
+  let (connection, io_threads) = Connection::stdio();
+  let result = cliq_lsp::run(connection).and_then(|()| io_threads.join().map_err(Into::into));
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("cliq-lsp: {}", err);
+      ExitCode::FAILURE
+    }
+  }
+}
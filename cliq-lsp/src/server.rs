@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics};
+use lsp_types::request::{DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Request as _, SemanticTokensFullRequest};
+use lsp_types::{
+  DiagnosticSeverity, DocumentFormattingParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+  GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf,
+  PublishDiagnosticsParams, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensFullOptions,
+  SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities,
+  ServerCapabilities, SymbolKind, TextDocumentContentChangeEvent, TextDocumentPositionParams, TextDocumentSyncCapability,
+  TextDocumentSyncKind, TextEdit, Uri,
+};
+
+use cliq_parser::cst::incremental::{self, IncrementalParse};
+
+use crate::analysis::{Analysis, Highlight};
+use crate::line_index::LineIndex;
+
+pub type ServerError = Box<dyn Error + Send + Sync>;
+
+//order of the legend, a highlight is encoded as its index
+const TOKEN_TYPES: [SemanticTokenType; 6] = [
+  SemanticTokenType::KEYWORD,
+  SemanticTokenType::VARIABLE,
+  SemanticTokenType::NUMBER,
+  SemanticTokenType::OPERATOR,
+  SemanticTokenType::COMMENT,
+  SemanticTokenType::FUNCTION,
+];
+//variables are always immutable, declarations additionally carry the first bit
+const TOKEN_MODIFIERS: [SemanticTokenModifier; 2] = [SemanticTokenModifier::DECLARATION, SemanticTokenModifier::READONLY];
+
+pub fn capabilities() -> ServerCapabilities {
+  ServerCapabilities {
+    text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL)),
+    hover_provider: Some(HoverProviderCapability::Simple(true)),
+    definition_provider: Some(OneOf::Left(true)),
+    document_symbol_provider: Some(OneOf::Left(true)),
+    document_formatting_provider: Some(OneOf::Left(true)),
+    semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+      legend: SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+      },
+      full: Some(SemanticTokensFullOptions::Bool(true)),
+      ..SemanticTokensOptions::default()
+    })),
+    ..ServerCapabilities::default()
+  }
+}
+
+//answers requests until the client asks for shutdown, the connection is closed afterwards
+pub fn run(connection: Connection) -> Result<(), ServerError> {
+  connection.initialize(serde_json::to_value(capabilities())?)?;
+  let mut server = Server::default();
+  for msg in &connection.receiver {
+    match msg {
+      Message::Request(req) => {
+        if connection.handle_shutdown(&req)? {
+          return Ok(());
+        }
+        connection.sender.send(Message::Response(server.request(req)))?;
+      }
+      Message::Notification(notification) => {
+        if let Some(diagnostics) = server.notification(notification) {
+          connection.sender.send(Message::Notification(diagnostics))?;
+        }
+      }
+      Message::Response(_) => {}
+    }
+  }
+  Ok(())
+}
+
+struct Document {
+  version: i32,
+  parse: IncrementalParse,
+  analysis: Analysis,
+  index: LineIndex,
+}
+
+impl Document {
+  fn new(text: String, version: i32) -> Self {
+    let parse = IncrementalParse::new(text);
+    Self {
+      version,
+      index: LineIndex::new(parse.src()),
+      analysis: Analysis::from_incremental(&parse),
+      parse,
+    }
+  }
+
+  //changes are applied in order, the range of each one refers to the text left by the one before
+  fn change(&mut self, changes: Vec<TextDocumentContentChangeEvent>, version: i32) {
+    for change in changes {
+      match change.range {
+        Some(range) => {
+          let range = self.offset(range.start)..self.offset(range.end);
+          self.parse.edit(&incremental::TextEdit::new(range, change.text));
+        }
+        None => self.parse = IncrementalParse::new(change.text),
+      }
+      self.index = LineIndex::new(self.parse.src());
+    }
+    self.analysis = Analysis::from_incremental(&self.parse);
+    self.version = version;
+  }
+
+  fn range(&self, range: std::ops::Range<usize>) -> lsp_types::Range {
+    self.index.range(self.analysis.src(), range)
+  }
+
+  fn offset(&self, position: lsp_types::Position) -> usize {
+    self.index.offset(self.parse.src(), position)
+  }
+}
+
+#[derive(Default)]
+struct Server {
+  documents: HashMap<Uri, Document>,
+}
+
+impl Server {
+  fn request(&self, req: Request) -> Response {
+    let id = req.id.clone();
+    let result = match req.method.as_str() {
+      HoverRequest::METHOD => self.call::<HoverRequest>(req, Server::hover),
+      GotoDefinition::METHOD => self.call::<GotoDefinition>(req, Server::definition),
+      DocumentSymbolRequest::METHOD => self.call::<DocumentSymbolRequest>(req, Server::symbols),
+      SemanticTokensFullRequest::METHOD => self.call::<SemanticTokensFullRequest>(req, Server::semantic_tokens),
+      Formatting::METHOD => self.call::<Formatting>(req, Server::format),
+      method => return Response::new_err(id, ErrorCode::MethodNotFound as i32, format!("unsupported request {}", method)),
+    };
+    match result {
+      Ok(value) => Response::new_ok(id, value),
+      Err(msg) => Response::new_err(id, ErrorCode::InvalidParams as i32, msg),
+    }
+  }
+
+  fn call<R: lsp_types::request::Request>(
+    &self,
+    req: Request,
+    handler: fn(&Server, R::Params) -> R::Result,
+  ) -> Result<serde_json::Value, String> {
+    let params = serde_json::from_value(req.params).map_err(|err| err.to_string())?;
+    serde_json::to_value(handler(self, params)).map_err(|err| err.to_string())
+  }
+
+  //returns the diagnostics to publish when a document changed
+  fn notification(&mut self, notification: Notification) -> Option<Notification> {
+    let uri = match notification.method.as_str() {
+      DidOpenTextDocument::METHOD => {
+        let params: lsp_types::DidOpenTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+        let document = params.text_document;
+        self
+          .documents
+          .insert(document.uri.clone(), Document::new(document.text, document.version));
+        document.uri
+      }
+      DidChangeTextDocument::METHOD => {
+        let params: lsp_types::DidChangeTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+        let document = params.text_document;
+        self
+          .documents
+          .get_mut(&document.uri)?
+          .change(params.content_changes, document.version);
+        document.uri
+      }
+      DidCloseTextDocument::METHOD => {
+        let params: lsp_types::DidCloseTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+        self.documents.remove(&params.text_document.uri);
+        let params = PublishDiagnosticsParams::new(params.text_document.uri, vec![], None);
+        return Some(Notification::new(PublishDiagnostics::METHOD.to_string(), params));
+      }
+      _ => return None,
+    };
+
+    let document = &self.documents[&uri];
+    let diagnostics = document
+      .analysis
+      .diagnostics()
+      .into_iter()
+      .map(|diagnostic| lsp_types::Diagnostic {
+        range: document.range(diagnostic.range),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("cliq".to_string()),
+        message: diagnostic.msg,
+        ..lsp_types::Diagnostic::default()
+      })
+      .collect();
+    let params = PublishDiagnosticsParams::new(uri, diagnostics, Some(document.version));
+    Some(Notification::new(PublishDiagnostics::METHOD.to_string(), params))
+  }
+
+  fn document(&self, position: &TextDocumentPositionParams) -> Option<(&Document, usize)> {
+    let document = self.documents.get(&position.text_document.uri)?;
+    Some((document, document.offset(position.position)))
+  }
+
+  fn hover(&self, params: HoverParams) -> Option<Hover> {
+    let (document, offset) = self.document(&params.text_document_position_params)?;
+    let hover = document.analysis.hover(offset)?;
+    Some(Hover {
+      contents: HoverContents::Markup(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: format!("```cliq\n{}\n```", hover.text),
+      }),
+      range: Some(document.range(hover.range)),
+    })
+  }
+
+  fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+    let position = params.text_document_position_params;
+    let (document, offset) = self.document(&position)?;
+    let range = document.analysis.definition(offset)?;
+    Some(GotoDefinitionResponse::Scalar(Location::new(
+      position.text_document.uri,
+      document.range(range),
+    )))
+  }
+
+  fn symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+    let document = self.documents.get(&params.text_document.uri)?;
+    #[allow(deprecated)]
+    let symbols = document
+      .analysis
+      .symbols()
+      .into_iter()
+      .map(|symbol| DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.ty.map(|ty| ty.to_string()),
+        kind: SymbolKind::VARIABLE,
+        tags: None,
+        deprecated: None,
+        range: document.range(symbol.range),
+        selection_range: document.range(symbol.name_range),
+        children: None,
+      })
+      .collect();
+    Some(DocumentSymbolResponse::Nested(symbols))
+  }
+
+  //tokens are relative to the one before: line delta, then start delta within the same line
+  fn semantic_tokens(&self, params: SemanticTokensParams) -> Option<SemanticTokensResult> {
+    let document = self.documents.get(&params.text_document.uri)?;
+    let mut data = vec![];
+    let mut previous = lsp_types::Position::new(0, 0);
+    for token in document.analysis.highlights() {
+      let range = document.range(token.range);
+      let delta_line = range.start.line - previous.line;
+      let delta_start = if delta_line == 0 {
+        range.start.character - previous.character
+      } else {
+        range.start.character
+      };
+      let token_type = match token.highlight {
+        Highlight::Keyword => 0,
+        Highlight::Variable => 1,
+        Highlight::Number => 2,
+        Highlight::Operator => 3,
+        Highlight::Comment => 4,
+        Highlight::Function => 5,
+      };
+      let token_modifiers_bitset = match token.highlight {
+        Highlight::Variable if token.declaration => 0b11,
+        Highlight::Variable => 0b10,
+        _ => 0,
+      };
+      data.push(SemanticToken {
+        delta_line,
+        delta_start,
+        length: range.end.character - range.start.character,
+        token_type,
+        token_modifiers_bitset,
+      });
+      previous = range.start;
+    }
+    Some(SemanticTokensResult::Tokens(SemanticTokens { result_id: None, data }))
+  }
+
+  fn format(&self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+    let document = self.documents.get(&params.text_document.uri)?;
+    let src = document.analysis.src();
+    let formatted = document.analysis.format()?;
+    if formatted == src {
+      return Some(vec![]);
+    }
+    Some(vec![TextEdit::new(document.range(0..src.len()), formatted)])
+  }
+}
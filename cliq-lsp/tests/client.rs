@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId};
+use lsp_types::notification::{
+  DidChangeTextDocument, DidOpenTextDocument, Exit, Initialized, Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+  DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Initialize, Request as LspRequest, SemanticTokensFullRequest, Shutdown,
+};
+use lsp_types::{
+  DidChangeTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+  FormattingOptions, GotoDefinitionParams, GotoDefinitionResponse, HoverContents, HoverParams, InitializeParams, Position,
+  PublishDiagnosticsParams, Range, SemanticTokensParams, SemanticTokensResult, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+  TextDocumentItem, TextDocumentPositionParams, Uri, VersionedTextDocumentIdentifier,
+};
+
+//plays the editor side of the protocol against a server running on another thread
+struct Client {
+  connection: Connection,
+  server: JoinHandle<()>,
+  next_id: i32,
+  //notifications that arrived while waiting for a response
+  pending: VecDeque<Notification>,
+}
+
+impl Client {
+  fn start() -> Self {
+    let (server, connection) = Connection::memory();
+    let server = std::thread::spawn(move || cliq_lsp::run(server).unwrap());
+    let mut client = Self {
+      connection,
+      server,
+      next_id: 0,
+      pending: VecDeque::new(),
+    };
+    let result = client.request::<Initialize>(InitializeParams::default());
+    assert!(result.capabilities.hover_provider.is_some());
+    client.notify::<Initialized>(lsp_types::InitializedParams {});
+    client
+  }
+
+  fn recv(&self) -> Message {
+    self
+      .connection
+      .receiver
+      .recv_timeout(Duration::from_secs(10))
+      .expect("server did not answer")
+  }
+
+  fn request<R: LspRequest>(&mut self, params: R::Params) -> R::Result {
+    self.next_id += 1;
+    let id = RequestId::from(self.next_id);
+    let request = Request::new(id.clone(), R::METHOD.to_string(), params);
+    self.connection.sender.send(Message::Request(request)).unwrap();
+    loop {
+      match self.recv() {
+        Message::Response(response) if response.id == id => {
+          assert!(response.error.is_none(), "{}: {:?}", R::METHOD, response.error);
+          return serde_json::from_value(response.result.unwrap_or_default()).unwrap();
+        }
+        Message::Notification(notification) => self.pending.push_back(notification),
+        msg => panic!("unexpected message {:?}", msg),
+      }
+    }
+  }
+
+  fn notify<N: LspNotification>(&self, params: N::Params) {
+    let notification = Notification::new(N::METHOD.to_string(), params);
+    self.connection.sender.send(Message::Notification(notification)).unwrap();
+  }
+
+  fn diagnostics(&mut self) -> PublishDiagnosticsParams {
+    let notification = match self.pending.pop_front() {
+      Some(notification) => notification,
+      None => match self.recv() {
+        Message::Notification(notification) => notification,
+        msg => panic!("unexpected message {:?}", msg),
+      },
+    };
+    assert_eq!(notification.method, PublishDiagnostics::METHOD);
+    serde_json::from_value(notification.params).unwrap()
+  }
+
+  fn shutdown(mut self) {
+    self.request::<Shutdown>(());
+    self.notify::<Exit>(());
+    self.server.join().unwrap();
+  }
+}
+
+fn uri() -> Uri {
+  "file:///tmp/main.cliq".parse().unwrap()
+}
+
+fn document() -> TextDocumentIdentifier {
+  TextDocumentIdentifier::new(uri())
+}
+
+fn at(line: u32, character: u32) -> TextDocumentPositionParams {
+  TextDocumentPositionParams::new(document(), Position::new(line, character))
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+  Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+fn open(client: &mut Client, text: &str) -> PublishDiagnosticsParams {
+  let item = TextDocumentItem::new(uri(), "cliq".to_string(), 1, text.to_string());
+  client.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams { text_document: item });
+  client.diagnostics()
+}
+
+fn change(client: &mut Client, version: i32, text: &str) -> PublishDiagnosticsParams {
+  edit(client, version, vec![(None, text)])
+}
+
+fn edit(client: &mut Client, version: i32, changes: Vec<(Option<Range>, &str)>) -> PublishDiagnosticsParams {
+  client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+    text_document: VersionedTextDocumentIdentifier::new(uri(), version),
+    content_changes: changes
+      .into_iter()
+      .map(|(range, text)| TextDocumentContentChangeEvent {
+        range,
+        range_length: None,
+        text: text.to_string(),
+      })
+      .collect(),
+  });
+  client.diagnostics()
+}
+
+fn hover(client: &mut Client, line: u32, character: u32) -> Option<(String, Range)> {
+  let hover = client.request::<HoverRequest>(HoverParams {
+    text_document_position_params: at(line, character),
+    work_done_progress_params: Default::default(),
+  })?;
+  let HoverContents::Markup(markup) = hover.contents else {
+    panic!("expected markdown");
+  };
+  Some((markup.value, hover.range.unwrap()))
+}
+
+#[test]
+fn diagnostics_follow_changes() {
+  let mut client = Client::start();
+  let diagnostics = open(&mut client, "var a = 1\nvar = a");
+  assert_eq!(diagnostics.version, Some(1));
+  assert_eq!(diagnostics.diagnostics.len(), 1);
+  assert_eq!(diagnostics.diagnostics[0].range, range((1, 4), (1, 5)));
+  assert_eq!(diagnostics.diagnostics[0].source.as_deref(), Some("cliq"));
+
+  let diagnostics = change(&mut client, 2, "var a = 1\nvar b = a $ 2");
+  assert_eq!(diagnostics.version, Some(2));
+  assert_eq!(diagnostics.diagnostics[0].range, range((1, 10), (1, 11)));
+  assert_eq!(diagnostics.diagnostics[0].message, "Unexpected character at line 2, column 11");
+
+  assert!(change(&mut client, 3, "var a = 1\nvar b = a * 2").diagnostics.is_empty());
+
+  //incremental changes, the second range already sees the text of the first, the parenthesis is still open
+  let diagnostics = edit(
+    &mut client,
+    4,
+    vec![(Some(range((0, 8), (0, 9))), "(1 // ü\n"), (Some(range((2, 4), (2, 5))), "c")],
+  );
+  assert_eq!(diagnostics.version, Some(4));
+  assert_eq!(diagnostics.diagnostics.len(), 1);
+  assert_eq!(diagnostics.diagnostics[0].range, range((2, 0), (2, 3)));
+  assert!(edit(&mut client, 5, vec![(Some(range((1, 0), (1, 0))), ")")])
+    .diagnostics
+    .is_empty());
+  assert_eq!(
+    hover(&mut client, 2, 9),
+    Some(("```cliq\na: int\n```".to_string(), range((2, 8), (2, 9))))
+  );
+  client.shutdown();
+}
+
+#[test]
+fn navigation() {
+  let mut client = Client::start();
+  open(&mut client, "var width = 3 // ünits\nvar area = width * 2.5\narea / width");
+
+  assert_eq!(
+    hover(&mut client, 0, 5),
+    Some(("```cliq\nvar width: int\n```".to_string(), range((0, 4), (0, 9))))
+  );
+  assert_eq!(
+    hover(&mut client, 2, 2),
+    Some(("```cliq\narea: float\n```".to_string(), range((2, 0), (2, 4))))
+  );
+  assert_eq!(
+    hover(&mut client, 2, 5),
+    Some(("```cliq\nfloat\n```".to_string(), range((2, 0), (2, 12))))
+  );
+  assert_eq!(hover(&mut client, 0, 16), None);
+
+  let definition = client.request::<GotoDefinition>(GotoDefinitionParams {
+    text_document_position_params: at(2, 9),
+    work_done_progress_params: Default::default(),
+    partial_result_params: Default::default(),
+  });
+  let Some(GotoDefinitionResponse::Scalar(location)) = definition else {
+    panic!("expected a single location, got {:?}", definition);
+  };
+  assert_eq!(location.uri, uri());
+  assert_eq!(location.range, range((0, 4), (0, 9)));
+
+  let symbols = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+    text_document: document(),
+    work_done_progress_params: Default::default(),
+    partial_result_params: Default::default(),
+  });
+  let Some(DocumentSymbolResponse::Nested(symbols)) = symbols else {
+    panic!("expected nested symbols");
+  };
+  let symbols: Vec<_> = symbols
+    .iter()
+    .map(|symbol| (symbol.name.as_str(), symbol.detail.as_deref(), symbol.range, symbol.selection_range))
+    .collect();
+  assert_eq!(
+    symbols,
+    vec![
+      ("width", Some("int"), range((0, 0), (0, 13)), range((0, 4), (0, 9))),
+      ("area", Some("float"), range((1, 0), (1, 22)), range((1, 4), (1, 8))),
+    ]
+  );
+  client.shutdown();
+}
+
+#[test]
+fn semantic_tokens_and_formatting() {
+  let mut client = Client::start();
+  open(&mut client, "var  a=1 // ü\na*-2");
+
+  let tokens = client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+    text_document: document(),
+    work_done_progress_params: Default::default(),
+    partial_result_params: Default::default(),
+  });
+  let Some(SemanticTokensResult::Tokens(tokens)) = tokens else {
+    panic!("expected tokens");
+  };
+  let data: Vec<_> = tokens
+    .data
+    .iter()
+    .map(|token| {
+      [
+        token.delta_line,
+        token.delta_start,
+        token.length,
+        token.token_type,
+        token.token_modifiers_bitset,
+      ]
+    })
+    .collect();
+  assert_eq!(
+    data,
+    vec![
+      [0, 0, 3, 0, 0],
+      [0, 5, 1, 1, 0b11],
+      [0, 1, 1, 3, 0],
+      [0, 1, 1, 2, 0],
+      [0, 2, 4, 4, 0],
+      [1, 0, 1, 1, 0b10],
+      [0, 1, 1, 3, 0],
+      [0, 1, 1, 3, 0],
+      [0, 1, 1, 2, 0],
+    ]
+  );
+
+  let formatting = |client: &mut Client| {
+    client.request::<Formatting>(DocumentFormattingParams {
+      text_document: document(),
+      options: FormattingOptions {
+        tab_size: 2,
+        insert_spaces: true,
+        ..Default::default()
+      },
+      work_done_progress_params: Default::default(),
+    })
+  };
+  let edits = formatting(&mut client).unwrap();
+  assert_eq!(edits.len(), 1);
+  assert_eq!(edits[0].range, range((0, 0), (1, 4)));
+  assert_eq!(edits[0].new_text, "var a = 1 // ü\na * -2\n");
+
+  change(&mut client, 2, "var a = 1 // ü\na * -2\n");
+  assert_eq!(formatting(&mut client), Some(vec![]));
+  change(&mut client, 3, "var a = (1");
+  assert_eq!(formatting(&mut client), None);
+  client.shutdown();
+}
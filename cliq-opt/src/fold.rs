@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::expression::variable_expression::VariableExpression;
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::assign_stmt::AssignStmt;
+use cliq_parser::statement::Statement;
+use cliq_parser::visitor::fold::{self, Fold};
+
+use crate::typeck::TypeChecker;
+use crate::value::{ArithError, Type, Value};
+use crate::{Diagnostic, DiagnosticKind};
+
+//bottom up constant folder, immutable `var`s bound to a constant are substituted at their uses
+//types are tracked per variable so identities are only applied where they cannot change a result
+#[derive(Debug, Default)]
+pub struct ConstFolder {
+  constants: HashMap<String, Value>,
+  types: TypeChecker,
+  statement: usize,
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl ConstFolder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  //a variable that exists when the program runs but whose value is not known here, e.g. a host binding
+  pub fn declare(&mut self, name: impl Into<String>, ty: Type) {
+    let name = name.into();
+    self.constants.remove(&name);
+    self.types.declare(name, ty);
+  }
+
+  pub fn diagnostics(&self) -> &[Diagnostic] {
+    &self.diagnostics
+  }
+
+  pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+    self.diagnostics
+  }
+
+  //None when the expression reads a variable that was never declared
+  pub fn static_type(&self, expr: &Expression) -> Option<Type> {
+    self.types.expression(expr).ok()
+  }
+
+  //whether evaluating the expression can raise an error, such expressions are never dropped
+  fn may_fail(&self, expr: &Expression) -> bool {
+    stack::grow(|| match expr {
+      Expression::ValueExpression(_) => false,
+      Expression::VariableExpression(variable) => self.types.get(&variable.name).is_none(),
+      Expression::Binary { op, lhs, rhs } => {
+        let divides = matches!(op, BinOp::Div | BinOp::Rem)
+          && self.static_type(expr) != Some(Type::Float)
+          && !matches!(Value::from_expression(rhs), Some(Value::Int(val)) if val != 0);
+        divides || self.may_fail(lhs) || self.may_fail(rhs)
+      }
+      Expression::Unary { operand, .. } => self.may_fail(operand),
+      //a host function can fail for any argument
+      Expression::Call(_) => true,
+    })
+  }
+
+  fn division_by_zero(&mut self, expression: Expression) -> Expression {
+    self.diagnostics.push(Diagnostic {
+      kind: DiagnosticKind::DivisionByZero,
+      statement: self.statement,
+      expression: expression.clone(),
+    });
+    expression
+  }
+
+  //`x op c` or `c op x` that evaluates to `x`, or to `0` for `x * 0`
+  fn simplify(&self, op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+    let is = |expr: &Expression, constant: i32| match Value::from_expression(expr) {
+      Some(Value::Int(val)) => val == constant,
+      Some(Value::Float(val)) => val == constant as f32,
+      None => false,
+    };
+    //`x op c` has the type of `x`, so returning `x` does not turn a float into an int or back
+    let keeps_type = |x: &Expression, c: &Expression| match (self.static_type(x), self.static_type(c)) {
+      (Some(x), Some(c)) => Type::binary(x, c) == x,
+      _ => false,
+    };
+    let is_int = |expr: &Expression| self.static_type(expr) == Some(Type::Int);
+
+    match op {
+      //float `-0.0 + 0` is `0.0`, so adding zero is only dropped for ints
+      BinOp::Add if is(&rhs, 0) && is_int(&lhs) && is_int(&rhs) => lhs,
+      BinOp::Add if is(&lhs, 0) && is_int(&lhs) && is_int(&rhs) => rhs,
+      BinOp::Sub if is(&rhs, 0) && keeps_type(&lhs, &rhs) => lhs,
+      BinOp::Mul | BinOp::Div if is(&rhs, 1) && keeps_type(&lhs, &rhs) => lhs,
+      BinOp::Mul if is(&lhs, 1) && keeps_type(&rhs, &lhs) => rhs,
+      //float `nan * 0` is `nan`, so only ints collapse to zero
+      BinOp::Mul if is_int(&lhs) && is_int(&rhs) && ((is(&rhs, 0) && !self.may_fail(&lhs)) || (is(&lhs, 0) && !self.may_fail(&rhs))) => {
+        Value::Int(0).to_expression().unwrap()
+      }
+      _ => Expression::binary(op, lhs, rhs),
+    }
+  }
+}
+
+impl Fold for ConstFolder {
+  fn fold_statement(&mut self, stmt: Statement) -> Statement {
+    let stmt = fold::walk_statement(self, stmt);
+    self.statement += 1;
+    stmt
+  }
+
+  //the variable being declared is left alone, only its value is folded
+  fn fold_assign_stmt(&mut self, stmt: AssignStmt) -> AssignStmt {
+    let value = self.fold_expression(stmt.value);
+    if let Expression::VariableExpression(variable) = &stmt.variable {
+      let name = variable.name.clone();
+      self.constants.remove(&name);
+      self.types.forget(&name);
+      if !stmt.mutable {
+        if let Some(constant) = Value::from_expression(&value) {
+          self.constants.insert(name.clone(), constant);
+        }
+        if let Some(ty) = self.static_type(&value) {
+          self.types.declare(name, ty);
+        }
+      }
+    }
+    AssignStmt::new(stmt.variable, value, stmt.mutable)
+  }
+
+  fn fold_variable(&mut self, variable: VariableExpression) -> Expression {
+    match self.constants.get(&variable.name).and_then(|constant| constant.to_expression()) {
+      Some(expr) => expr,
+      None => Expression::VariableExpression(variable),
+    }
+  }
+
+  fn fold_binary(&mut self, op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+    let lhs = self.fold_expression(lhs);
+    let rhs = self.fold_expression(rhs);
+    match (Value::from_expression(&lhs), Value::from_expression(&rhs)) {
+      (Some(lhs_val), Some(rhs_val)) => match Value::binary(op, lhs_val, rhs_val) {
+        Ok(result) => result.to_expression().unwrap_or_else(|| Expression::binary(op, lhs, rhs)),
+        Err(ArithError::DivisionByZero) => self.division_by_zero(Expression::binary(op, lhs, rhs)),
+      },
+      (None, Some(Value::Int(0))) if matches!(op, BinOp::Div | BinOp::Rem) && self.static_type(&lhs) == Some(Type::Int) => {
+        self.division_by_zero(Expression::binary(op, lhs, rhs))
+      }
+      _ => self.simplify(op, lhs, rhs),
+    }
+  }
+
+  fn fold_unary(&mut self, op: UnOp, operand: Expression) -> Expression {
+    let mut operand = self.fold_expression(operand);
+    if let Some(val) = Value::from_expression(&operand) {
+      if let Some(expr) = Value::unary(op, val).to_expression() {
+        return expr;
+      }
+    }
+    match &mut operand {
+      //negation is its own inverse for ints (wrapping) and floats
+      Expression::Unary { op: inner, operand } if op == UnOp::Neg && *inner == UnOp::Neg => operand.take(),
+      _ => Expression::unary(op, operand),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::cst;
+  use cliq_parser::parser::{Parser, DEFAULT_MAX_CHAIN, DEFAULT_MAX_DEPTH};
+  use cliq_parser::statement::to_source;
+
+  use crate::optimize;
+
+  use super::*;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  fn opt(input: &str) -> String {
+    let (ast, diagnostics) = optimize(parse(input));
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    to_source(&ast)
+  }
+
+  #[test]
+  fn test_output_reparses() {
+    //negative constants come back as negations, so the reparsed output is compared after folding it again
+    for input in [
+      "(33 + 22) * 2",
+      "ac - (33 + 22) * 2",
+      "-(2 * 3) % 4",
+      "2147483647 + 1",
+      "-2147483647 - 1 - x",
+      "var m = 0 - 2147483647 - 1\nm * (0 - 1)",
+      "x\n3 - 5",
+      "f(0 - 7, 2147483647 + 1) / -1.5",
+      "-(-x) * 0.5",
+    ] {
+      let (folded, _) = optimize(parse(input));
+      let source = to_source(&folded);
+      let (reparsed, _) = optimize(parse(&source));
+      assert_eq!(reparsed, folded, "{} folded to {}", input, source);
+    }
+  }
+
+  #[test]
+  fn test_deepest_input() {
+    //the deepest trees the lossless parser lets through make it through lowering, folding and printing:
+    //calls nested as deep as they may be around a chain as long as it may be
+    let calls = DEFAULT_MAX_DEPTH - 1;
+    let chain = vec!["a"; DEFAULT_MAX_CHAIN + 1].join(" + ");
+    let input = format!("{}{}{}", "f(".repeat(calls), chain, ")".repeat(calls));
+    let (folded, _) = optimize(cst::parse(&input).unwrap().to_ast().unwrap());
+    assert_eq!(to_source(&folded), format!("{}\n", input));
+  }
+
+  #[test]
+  fn test_fold_constants() {
+    assert_eq!(opt("(33 + 22) * 2"), "110\n");
+    assert_eq!(opt("ac - (33 + 22) * 2"), "ac - 110\n");
+    assert_eq!(opt("7 / 2 + 0.5"), "3.5\n");
+    assert_eq!(opt("-(2 * 3) % 4"), "-2\n");
+    assert_eq!(opt("2147483647 + 1"), "(-2147483647 - 1)\n");
+    assert_eq!(opt("x\n3 - 5"), "x\n(-2)\n");
+  }
+
+  #[test]
+  fn test_propagate_immutable_vars() {
+    assert_eq!(opt("var a = 2\nvar b = a * 3\nb + c"), "var a = 2\nvar b = 6\n6 + c\n");
+    assert_eq!(opt("var a = 2\nvar a = a + 1\na"), "var a = 2\nvar a = 3\n3\n");
+    //inf has no literal, the variable keeps its float type but is not substituted
+    assert_eq!(opt("var big = 1.0 / 0\nbig * 1"), "var big = 1.0 / 0\nbig\n");
+  }
+
+  fn opt_declared(input: &str) -> String {
+    let mut folder = ConstFolder::new();
+    folder.declare("i", Type::Int);
+    folder.declare("n", Type::Int);
+    folder.declare("f", Type::Float);
+    let ast = folder.fold_statements(parse(input));
+    assert!(folder.diagnostics().is_empty(), "{:?}", folder.diagnostics());
+    to_source(&ast)
+  }
+
+  #[test]
+  fn test_identities() {
+    assert_eq!(
+      opt_declared("i * 1\n1 * i\ni + 0\n0 + i\ni - 0\ni / 1\n(--i)"),
+      "i\ni\ni\ni\ni\ni\ni\n"
+    );
+    assert_eq!(opt_declared("f * 1\nf / 1\nf - 0\nf * 1.0"), "f\nf\nf\nf\n");
+    assert_eq!(opt_declared("i * 0\n0 * (i / 2)\n(i + n) * (3 - 3)"), "0\n0\n0\n");
+  }
+
+  #[test]
+  fn test_unsafe_identities_are_kept() {
+    assert_eq!(opt_declared("f + 0"), "f + 0\n");
+    assert_eq!(opt_declared("i * 1.0"), "i * 1.0\n");
+    assert_eq!(opt_declared("f * 0"), "f * 0\n");
+    assert_eq!(opt_declared("i / n * 0"), "i / n * 0\n");
+    assert_eq!(opt_declared("u * 1\nu * 0"), "u * 1\nu * 0\n");
+  }
+
+  #[test]
+  fn test_division_by_zero() {
+    let (ast, diagnostics) = optimize(parse("var z = 0\n1 + 4 / z\n1.0 / z"));
+    assert_eq!(to_source(&ast), "var z = 0\n1 + 4 / 0\n1.0 / 0\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DivisionByZero);
+    assert_eq!(diagnostics[0].statement, 1);
+    assert_eq!(diagnostics[0].to_string(), "statement 2: division by zero in `4 / 0`");
+
+    let mut folder = ConstFolder::new();
+    folder.declare("i", Type::Int);
+    folder.declare("f", Type::Float);
+    folder.fold_statements(parse("i % (2 - 2)\nf / 0"));
+    assert_eq!(folder.diagnostics().len(), 1);
+    assert_eq!(folder.diagnostics()[0].expression.to_string(), "i % 0");
+  }
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+use cliq_parser::expression::Expression;
+use cliq_parser::statement::Statement;
+use cliq_parser::visitor::Fold;
+
+use self::fold::ConstFolder;
+
+pub mod fold;
+pub mod resolve;
+pub mod typeck;
+pub mod value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+  DivisionByZero,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub kind: DiagnosticKind,
+  //index of the statement in the folded program
+  pub statement: usize,
+  pub expression: Expression,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.kind {
+      DiagnosticKind::DivisionByZero => write!(f, "statement {}: division by zero in `{}`", self.statement + 1, self.expression),
+    }
+  }
+}
+
+//folds constants and simplifies the program, behaviour at runtime stays the same
+//an expression that always divides by zero is kept so it still fails when it is run
+pub fn optimize(ast: Vec<Statement>) -> (Vec<Statement>, Vec<Diagnostic>) {
+  let mut folder = ConstFolder::new();
+  let ast = folder.fold_statements(ast);
+  (ast, folder.into_diagnostics())
+}
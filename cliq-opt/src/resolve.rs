@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use cliq_parser::arena::{ArenaId, Ast, Expr, ExprId, SideTable, Stmt, StmtId};
+use cliq_parser::stack;
+
+//links every variable to the `var` that declared it, in scopes that nest like blocks and functions will
+//the program itself is the outermost scope, variables of the host are declared in it up front
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeclId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(u32);
+
+impl ArenaId for DeclId {
+  fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+impl ArenaId for ScopeId {
+  fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+  pub name: String,
+  //the name in the `var` statement, None for a variable of the host
+  pub variable: Option<ExprId>,
+  pub scope: ScopeId,
+  pub mutable: bool,
+  pub uses: Vec<ExprId>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+  pub parent: Option<ScopeId>,
+  pub declarations: Vec<DeclId>,
+  //the declaration a name refers to at the current point of the walk
+  visible: HashMap<String, DeclId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameErrorKind {
+  UndefinedVariable,
+  //the declaration that comes later in a scope the use is in
+  UseBeforeDefinition(DeclId),
+  //the earlier immutable declaration in the same scope
+  DuplicateDeclaration(DeclId),
+  UnusedVariable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameError {
+  pub kind: NameErrorKind,
+  pub name: String,
+  //the use, or the name of the declaration for duplicates and unused variables
+  pub expr: ExprId,
+}
+
+impl NameError {
+  //unused variables do not stop a program from running
+  pub fn is_warning(&self) -> bool {
+    self.kind == NameErrorKind::UnusedVariable
+  }
+}
+
+impl fmt::Display for NameError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.kind {
+      NameErrorKind::UndefinedVariable => write!(f, "undefined variable `{}`", self.name),
+      NameErrorKind::UseBeforeDefinition(_) => write!(f, "variable `{}` is used before it is declared", self.name),
+      NameErrorKind::DuplicateDeclaration(_) => write!(f, "immutable variable `{}` is declared twice", self.name),
+      NameErrorKind::UnusedVariable => write!(f, "unused variable `{}`", self.name),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Resolution {
+  declarations: Vec<Declaration>,
+  scopes: Vec<Scope>,
+  //uses as well as the names in `var` statements
+  links: SideTable<ExprId, DeclId>,
+  //for uses that are not linked when they are reached: the scope they are in and how many declarations came before
+  unresolved: SideTable<ExprId, (ScopeId, usize)>,
+  errors: Vec<NameError>,
+}
+
+impl Resolution {
+  pub fn declaration(&self, id: DeclId) -> &Declaration {
+    &self.declarations[id.index()]
+  }
+
+  pub fn declarations(&self) -> impl Iterator<Item = (DeclId, &Declaration)> {
+    self
+      .declarations
+      .iter()
+      .enumerate()
+      .map(|(i, declaration)| (DeclId(i as u32), declaration))
+  }
+
+  pub fn scope(&self, id: ScopeId) -> &Scope {
+    &self.scopes[id.index()]
+  }
+
+  //None for variables that are not declared anywhere and for expressions that are no variable
+  pub fn declaration_of(&self, expr: ExprId) -> Option<DeclId> {
+    self.links.get(expr).copied()
+  }
+
+  //in source order, warnings included
+  pub fn errors(&self) -> &[NameError] {
+    &self.errors
+  }
+
+  //the closest name that is visible at the undefined variable `expr`, to suggest for a typo,
+  //names that differ in more than a third of their characters are too far off
+  pub fn similar_declaration(&self, name: &str, expr: ExprId) -> Option<DeclId> {
+    let (scope, before) = *self.unresolved.get(expr)?;
+    let max = (name.chars().count() / 3).max(1);
+    self
+      .declarations()
+      .take(before)
+      .filter(|(_, declaration)| declaration.name != name && self.encloses(declaration.scope, scope))
+      .map(|(id, declaration)| (edit_distance(name, &declaration.name), &declaration.name, id))
+      .filter(|(distance, ..)| *distance <= max)
+      .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+      .map(|(.., id)| id)
+  }
+
+  //whether `inner` is `outer` or nested in it
+  pub fn encloses(&self, outer: ScopeId, inner: ScopeId) -> bool {
+    let mut scope = Some(inner);
+    while let Some(id) = scope {
+      if id == outer {
+        return true;
+      }
+      scope = self.scope(id).parent;
+    }
+    false
+  }
+}
+
+//the number of characters that have to be inserted, removed or replaced to turn `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  //the distances of the prefix of `a` seen so far to every prefix of `b`
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let replace = diagonal + usize::from(ca != *cb);
+      diagonal = row[j + 1];
+      row[j + 1] = replace.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+  row[b.len()]
+}
+
+#[derive(Debug)]
+pub struct Resolver {
+  resolution: Resolution,
+  //open scopes, innermost last
+  stack: Vec<ScopeId>,
+  //uses of names that were not declared yet, per open scope, settled when it closes
+  pending: Vec<Vec<(String, ExprId)>>,
+}
+
+impl Default for Resolver {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Resolver {
+  pub fn new() -> Self {
+    let mut resolver = Self {
+      resolution: Resolution {
+        declarations: vec![],
+        scopes: vec![],
+        links: SideTable::new(),
+        unresolved: SideTable::new(),
+        errors: vec![],
+      },
+      stack: vec![],
+      pending: vec![],
+    };
+    resolver.enter_scope();
+    resolver
+  }
+
+  //a variable the host provides, it is never reported as unused
+  pub fn declare(&mut self, name: impl Into<String>) -> DeclId {
+    self.add_declaration(name.into(), None, false)
+  }
+
+  pub fn enter_scope(&mut self) -> ScopeId {
+    let id = ScopeId(self.resolution.scopes.len() as u32);
+    self.resolution.scopes.push(Scope {
+      parent: self.stack.last().copied(),
+      ..Scope::default()
+    });
+    self.stack.push(id);
+    self.pending.push(vec![]);
+    id
+  }
+
+  //reports what is only known once the whole scope was seen: uses of later declarations and unused variables
+  pub fn exit_scope(&mut self) {
+    let scope = self.stack.pop().expect("no scope to exit");
+    let pending = self.pending.pop().unwrap();
+    let declarations = &self.resolution.scopes[scope.index()].declarations;
+    for (name, expr) in pending {
+      let later = declarations
+        .iter()
+        .copied()
+        .find(|id| self.resolution.declarations[id.index()].name == name);
+      match (later, self.pending.last_mut()) {
+        (Some(id), _) => self.resolution.errors.push(NameError {
+          kind: NameErrorKind::UseBeforeDefinition(id),
+          name,
+          expr,
+        }),
+        //an enclosing scope may still declare it further down
+        (None, Some(outer)) => outer.push((name, expr)),
+        (None, None) => self.resolution.errors.push(NameError {
+          kind: NameErrorKind::UndefinedVariable,
+          name,
+          expr,
+        }),
+      }
+    }
+    for id in declarations {
+      let declaration = &self.resolution.declarations[id.index()];
+      //a variable that is used too early is reported there already
+      let used_early = self
+        .resolution
+        .errors
+        .iter()
+        .any(|err| err.kind == NameErrorKind::UseBeforeDefinition(*id));
+      if let (Some(expr), true) = (declaration.variable, declaration.uses.is_empty()) {
+        if !declaration.name.starts_with('_') && !used_early {
+          self.resolution.errors.push(NameError {
+            kind: NameErrorKind::UnusedVariable,
+            name: declaration.name.clone(),
+            expr,
+          });
+        }
+      }
+    }
+  }
+
+  pub fn statement(&mut self, ast: &Ast, id: StmtId) {
+    match *ast.stmt(id) {
+      Stmt::Expression(expr) => self.expression(ast, expr),
+      Stmt::Assign { variable, value, mutable } => {
+        //the value still sees what the name referred to before
+        self.expression(ast, value);
+        match *ast.expr(variable) {
+          Expr::Variable(symbol) => {
+            let declaration = self.add_declaration(ast.name(symbol).to_string(), Some(variable), mutable);
+            self.resolution.links.insert(variable, declaration);
+          }
+          _ => self.expression(ast, variable),
+        }
+      }
+    }
+  }
+
+  fn expression(&mut self, ast: &Ast, id: ExprId) {
+    stack::grow(|| match *ast.expr(id) {
+      Expr::Int(_) | Expr::Float(_) => {}
+      Expr::Variable(symbol) => {
+        let name = ast.name(symbol);
+        let visible = self
+          .stack
+          .iter()
+          .rev()
+          .find_map(|scope| self.resolution.scopes[scope.index()].visible.get(name).copied());
+        match visible {
+          Some(declaration) => {
+            self.resolution.links.insert(id, declaration);
+            self.resolution.declarations[declaration.index()].uses.push(id);
+          }
+          None => {
+            let scope = *self.stack.last().unwrap();
+            self.resolution.unresolved.insert(id, (scope, self.resolution.declarations.len()));
+            self.pending.last_mut().unwrap().push((name.to_string(), id));
+          }
+        }
+      }
+      Expr::Binary(_, lhs, rhs) => {
+        self.expression(ast, lhs);
+        self.expression(ast, rhs);
+      }
+      Expr::Unary(_, operand) => self.expression(ast, operand),
+      //function names are not variables, only the arguments are resolved
+      Expr::Call(_, args) => {
+        for &arg in ast.args(args) {
+          self.expression(ast, arg);
+        }
+      }
+    })
+  }
+
+  fn add_declaration(&mut self, name: String, variable: Option<ExprId>, mutable: bool) -> DeclId {
+    let id = DeclId(self.resolution.declarations.len() as u32);
+    let scope = *self.stack.last().unwrap();
+    let previous = self.resolution.scopes[scope.index()].visible.insert(name.clone(), id);
+    if let (Some(previous), Some(expr)) = (previous, variable) {
+      if !self.resolution.declarations[previous.index()].mutable {
+        self.resolution.errors.push(NameError {
+          kind: NameErrorKind::DuplicateDeclaration(previous),
+          name: name.clone(),
+          expr,
+        });
+      }
+    }
+    self.resolution.scopes[scope.index()].declarations.push(id);
+    self.resolution.declarations.push(Declaration {
+      name,
+      variable,
+      scope,
+      mutable,
+      uses: vec![],
+    });
+    id
+  }
+
+  //closes the scopes that are still open, the program scope included
+  pub fn finish(mut self) -> Resolution {
+    while !self.stack.is_empty() {
+      self.exit_scope();
+    }
+    //expressions are allocated in the order they appear in
+    self.resolution.errors.sort_by_key(|err| err.expr);
+    self.resolution
+  }
+}
+
+pub fn resolve(ast: &Ast) -> Resolution {
+  let mut resolver = Resolver::new();
+  for id in ast.body() {
+    resolver.statement(ast, *id);
+  }
+  resolver.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+
+  fn arena(input: &str) -> Ast {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse_arena()
+  }
+
+  fn errors(resolution: &Resolution) -> Vec<(NameErrorKind, &str)> {
+    resolution.errors().iter().map(|err| (err.kind, err.name.as_str())).collect()
+  }
+
+  #[test]
+  fn test_uses_are_linked() {
+    let ast = arena("var a = 1\nvar b = a + a * 2\n(-b)");
+    let resolution = resolve(&ast);
+    assert_eq!(errors(&resolution), vec![]);
+    let (a, b) = (DeclId(0), DeclId(1));
+    assert_eq!(resolution.declaration(a).uses.len(), 2);
+    assert_eq!(resolution.declaration(b).uses.len(), 1);
+    for (id, declaration) in resolution.declarations() {
+      assert_eq!(resolution.declaration_of(declaration.variable.unwrap()), Some(id));
+      for expr in &declaration.uses {
+        assert_eq!(ast.expr(*expr), &Expr::Variable(ast.symbol(&declaration.name).unwrap()));
+        assert_eq!(resolution.declaration_of(*expr), Some(id));
+      }
+    }
+  }
+
+  #[test]
+  fn test_undefined_and_use_before_definition() {
+    let resolution = resolve(&arena("a + c\nvar a = a\n"));
+    assert_eq!(
+      errors(&resolution),
+      vec![
+        (NameErrorKind::UseBeforeDefinition(DeclId(0)), "a"),
+        (NameErrorKind::UndefinedVariable, "c"),
+        (NameErrorKind::UseBeforeDefinition(DeclId(0)), "a"),
+      ]
+    );
+    assert_eq!(resolution.errors()[0].to_string(), "variable `a` is used before it is declared");
+    assert_eq!(resolution.errors()[1].to_string(), "undefined variable `c`");
+    assert!(resolution.declaration_of(resolution.errors()[1].expr).is_none());
+  }
+
+  #[test]
+  fn test_duplicate_declarations() {
+    let ast = arena("var a = 1\nvar a = a + 1\na");
+    let resolution = resolve(&ast);
+    assert_eq!(errors(&resolution), vec![(NameErrorKind::DuplicateDeclaration(DeclId(0)), "a")]);
+    assert_eq!(resolution.errors()[0].expr, resolution.declaration(DeclId(1)).variable.unwrap());
+    assert_eq!(resolution.declaration(DeclId(0)).uses.len(), 1);
+    assert_eq!(resolution.declaration(DeclId(1)).uses.len(), 1);
+
+    //a mutable variable may be declared again
+    let mut ast = Ast::new();
+    for value in [1, 2] {
+      let variable = ast.intern("a");
+      let variable = ast.alloc_expr(Expr::Variable(variable));
+      let value = ast.alloc_expr(Expr::Int(value));
+      ast.push_stmt(Stmt::Assign {
+        variable,
+        value,
+        mutable: true,
+      });
+    }
+    let resolution = resolve(&ast);
+    assert_eq!(
+      errors(&resolution),
+      vec![(NameErrorKind::UnusedVariable, "a"), (NameErrorKind::UnusedVariable, "a")]
+    );
+  }
+
+  #[test]
+  fn test_unused_variables() {
+    let ast = arena("var a = 1\nvar _b = 2\nhost");
+    let mut resolver = Resolver::new();
+    let host = resolver.declare("host");
+    resolver.declare("unused");
+    for id in ast.body() {
+      resolver.statement(&ast, *id);
+    }
+    let resolution = resolver.finish();
+    assert_eq!(errors(&resolution), vec![(NameErrorKind::UnusedVariable, "a")]);
+    assert!(resolution.errors()[0].is_warning());
+    assert_eq!(resolution.errors()[0].to_string(), "unused variable `a`");
+    assert_eq!(resolution.declaration(host).uses.len(), 1);
+  }
+
+  #[test]
+  fn test_nested_scopes() {
+    let ast = arena("var a = 1\nvar b = a\nvar a = 2\nb + a\nc\nvar c = b");
+    let body = ast.body().to_vec();
+    let mut resolver = Resolver::new();
+    resolver.statement(&ast, body[0]);
+    let inner = resolver.enter_scope();
+    //shadowing an outer variable is no duplicate
+    for id in &body[1..5] {
+      resolver.statement(&ast, *id);
+    }
+    resolver.exit_scope();
+    resolver.statement(&ast, body[5]);
+    let resolution = resolver.finish();
+
+    assert_eq!(resolution.scope(inner).parent, Some(ScopeId(0)));
+    assert_eq!(resolution.scope(inner).declarations, vec![DeclId(1), DeclId(2)]);
+    assert_eq!(resolution.declaration(DeclId(0)).uses.len(), 1);
+    assert_eq!(resolution.declaration(DeclId(2)).uses.len(), 1);
+    //`b` of the last statement is not visible outside of the inner scope
+    assert_eq!(
+      errors(&resolution),
+      vec![
+        (NameErrorKind::UseBeforeDefinition(DeclId(3)), "c"),
+        (NameErrorKind::UndefinedVariable, "b"),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_edit_distance() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("abc", ""), 3);
+    assert_eq!(edit_distance("count", "cont"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("flaw", "lawn"), 2);
+    assert_eq!(edit_distance("äb", "ab"), 1);
+  }
+
+  #[test]
+  fn test_similar_declaration() {
+    let ast = arena("var total = 1\nvar totals = 2\ntotl + totalz + x + countr\nvar counter = 3");
+    let mut resolver = Resolver::new();
+    let host = resolver.declare("y");
+    for id in ast.body() {
+      resolver.statement(&ast, *id);
+    }
+    let resolution = resolver.finish();
+    let similar = |name: &str| {
+      let err = resolution.errors().iter().find(|err| err.name == name).unwrap();
+      resolution.similar_declaration(name, err.expr)
+    };
+    assert_eq!(similar("totl"), Some(DeclId(1)));
+    //equally close, the first name wins
+    assert_eq!(similar("totalz"), Some(DeclId(1)));
+    assert_eq!(similar("x"), Some(host));
+    //`counter` is declared after the use
+    assert_eq!(similar("countr"), None);
+  }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::Statement;
+
+use crate::value::{Type, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeError {
+  UndefinedVariable(String),
+  //functions only exist in a host, targets without one never know them
+  UndefinedFunction(String),
+}
+
+impl fmt::Display for TypeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TypeError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+      TypeError::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+    }
+  }
+}
+
+//every expression has a type known before running it: ints stay ints, anything touching a float is a float
+//a variable takes the type of the value it was last declared with
+#[derive(Debug, Clone, Default)]
+pub struct TypeChecker {
+  vars: HashMap<String, Type>,
+}
+
+impl TypeChecker {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn declare(&mut self, name: impl Into<String>, ty: Type) {
+    self.vars.insert(name.into(), ty);
+  }
+
+  pub fn forget(&mut self, name: &str) {
+    self.vars.remove(name);
+  }
+
+  pub fn get(&self, name: &str) -> Option<Type> {
+    self.vars.get(name).copied()
+  }
+
+  pub fn expression(&self, expr: &Expression) -> Result<Type, TypeError> {
+    stack::grow(|| match expr {
+      Expression::ValueExpression(_) => Ok(Value::from_expression(expr).unwrap().ty()),
+      Expression::VariableExpression(variable) => self
+        .get(&variable.name)
+        .ok_or_else(|| TypeError::UndefinedVariable(variable.name.clone())),
+      Expression::Binary { lhs, rhs, .. } => Ok(Type::binary(self.expression(lhs)?, self.expression(rhs)?)),
+      Expression::Unary { operand, .. } => self.expression(operand),
+      Expression::Call(call) => Err(TypeError::UndefinedFunction(call.name.clone())),
+    })
+  }
+
+  //type of the program's result, the last expression statement
+  pub fn check(&mut self, ast: &[Statement]) -> Result<Option<Type>, TypeError> {
+    let mut result = None;
+    for stmt in ast {
+      match stmt {
+        Statement::Expression(expr) => result = Some(self.expression(expr)?),
+        Statement::Assign(stmt) => {
+          let ty = self.expression(&stmt.value)?;
+          if let Expression::VariableExpression(variable) = &stmt.variable {
+            self.declare(variable.name.clone(), ty);
+          }
+        }
+      }
+    }
+    Ok(result)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+
+  fn check(input: &str) -> Result<Option<Type>, TypeError> {
+    TypeChecker::new().check(&Parser::new(Lexer::new().lex(input).unwrap()).parse())
+  }
+
+  #[test]
+  fn test_types() {
+    assert_eq!(check("1 + 2 * -3"), Ok(Some(Type::Int)));
+    assert_eq!(check("var a = 1\nvar b = a / 2.0\nb % a"), Ok(Some(Type::Float)));
+    assert_eq!(check("var a = 1.5\nvar a = 1\na"), Ok(Some(Type::Int)));
+    assert_eq!(check("var a = 1"), Ok(None));
+  }
+
+  #[test]
+  fn test_undefined_variable() {
+    assert_eq!(check("var a = b"), Err(TypeError::UndefinedVariable("b".to_string())));
+    assert_eq!(check("var a = a + 1"), Err(TypeError::UndefinedVariable("a".to_string())));
+    assert_eq!(check("var a = 1\nsqrt(a)"), Err(TypeError::UndefinedFunction("sqrt".to_string())));
+  }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::expression::value_expression::ValueExpression;
+use cliq_parser::expression::Expression;
+
+//runtime semantics of cliq arithmetic, shared by the folder and everything that evaluates code
+//ints wrap on overflow, an int mixed with a float is widened to a float
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+  Int(i32),
+  Float(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+  Int,
+  Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithError {
+  DivisionByZero,
+}
+
+impl fmt::Display for ArithError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ArithError::DivisionByZero => write!(f, "division by zero"),
+    }
+  }
+}
+
+impl Type {
+  //type of `lhs op rhs` for every binary operator
+  pub fn binary(lhs: Type, rhs: Type) -> Type {
+    match (lhs, rhs) {
+      (Type::Int, Type::Int) => Type::Int,
+      _ => Type::Float,
+    }
+  }
+}
+
+impl fmt::Display for Type {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Type::Int => write!(f, "int"),
+      Type::Float => write!(f, "float"),
+    }
+  }
+}
+
+impl Value {
+  pub fn ty(self) -> Type {
+    match self {
+      Value::Int(_) => Type::Int,
+      Value::Float(_) => Type::Float,
+    }
+  }
+
+  pub fn as_float(self) -> f32 {
+    match self {
+      Value::Int(val) => val as f32,
+      Value::Float(val) => val,
+    }
+  }
+
+  pub fn binary(op: BinOp, lhs: Value, rhs: Value) -> Result<Value, ArithError> {
+    match (lhs, rhs) {
+      (Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(match op {
+        BinOp::Add => lhs.wrapping_add(rhs),
+        BinOp::Sub => lhs.wrapping_sub(rhs),
+        BinOp::Mul => lhs.wrapping_mul(rhs),
+        BinOp::Div | BinOp::Rem if rhs == 0 => return Err(ArithError::DivisionByZero),
+        BinOp::Div => lhs.wrapping_div(rhs),
+        BinOp::Rem => lhs.wrapping_rem(rhs),
+      })),
+      _ => {
+        let (lhs, rhs) = (lhs.as_float(), rhs.as_float());
+        Ok(Value::Float(match op {
+          BinOp::Add => lhs + rhs,
+          BinOp::Sub => lhs - rhs,
+          BinOp::Mul => lhs * rhs,
+          BinOp::Div => lhs / rhs,
+          BinOp::Rem => lhs % rhs,
+        }))
+      }
+    }
+  }
+
+  pub fn unary(op: UnOp, operand: Value) -> Value {
+    match (op, operand) {
+      (UnOp::Neg, Value::Int(val)) => Value::Int(val.wrapping_neg()),
+      (UnOp::Neg, Value::Float(val)) => Value::Float(-val),
+    }
+  }
+
+  pub fn from_expression(expr: &Expression) -> Option<Value> {
+    match expr {
+      Expression::ValueExpression(ValueExpression::IntValue(val)) => Some(Value::Int(val.value())),
+      Expression::ValueExpression(ValueExpression::FloatValue(val)) => Some(Value::Float(val.value())),
+      _ => None,
+    }
+  }
+
+  //None for floats that have no literal (inf and nan)
+  pub fn to_expression(self) -> Option<Expression> {
+    match self {
+      Value::Int(val) => Some(ValueExpression::int_value(val)),
+      Value::Float(val) if val.is_finite() => Some(ValueExpression::float_value(val)),
+      Value::Float(_) => None,
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Value::Int(val) => write!(f, "{}", val),
+      Value::Float(val) => write!(f, "{:?}", val),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_int_arithmetic() {
+    assert_eq!(
+      Value::binary(BinOp::Add, Value::Int(i32::MAX), Value::Int(1)),
+      Ok(Value::Int(i32::MIN))
+    );
+    assert_eq!(Value::binary(BinOp::Div, Value::Int(-7), Value::Int(2)), Ok(Value::Int(-3)));
+    assert_eq!(Value::binary(BinOp::Rem, Value::Int(-7), Value::Int(2)), Ok(Value::Int(-1)));
+    assert_eq!(
+      Value::binary(BinOp::Div, Value::Int(i32::MIN), Value::Int(-1)),
+      Ok(Value::Int(i32::MIN))
+    );
+    assert_eq!(
+      Value::binary(BinOp::Rem, Value::Int(1), Value::Int(0)),
+      Err(ArithError::DivisionByZero)
+    );
+  }
+
+  #[test]
+  fn test_mixed_arithmetic() {
+    assert_eq!(Value::binary(BinOp::Mul, Value::Int(3), Value::Float(0.5)), Ok(Value::Float(1.5)));
+    assert_eq!(
+      Value::binary(BinOp::Div, Value::Float(1.0), Value::Int(0)),
+      Ok(Value::Float(f32::INFINITY))
+    );
+    assert_eq!(Value::unary(UnOp::Neg, Value::Int(i32::MIN)), Value::Int(i32::MIN));
+    assert_eq!(Value::Float(f32::NAN).to_expression(), None);
+  }
+}
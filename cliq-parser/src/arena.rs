@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::{Index, Range};
+
+use crate::{
+  cst::{red::SyntaxNode, syntax_kind::SyntaxKind, SyntaxError},
+  expression::{
+    call_expression::CallExpression,
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  stack,
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+
+//index based mirror of the boxed AST, nodes live in one vector per kind and are referenced by id
+//ids are only meaningful for the Ast that created them
+
+pub trait ArenaId: Copy {
+  fn index(self) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StmtId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl ArenaId for ExprId {
+  fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+impl ArenaId for StmtId {
+  fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+impl ArenaId for Symbol {
+  fn index(self) -> usize {
+    self.0 as usize
+  }
+}
+
+//the arguments of a call, a run in the argument list of the Ast, see Ast::args
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Args {
+  start: u32,
+  len: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expr {
+  Int(i32),
+  Float(f32),
+  Variable(Symbol),
+  Binary(BinOp, ExprId, ExprId),
+  Unary(UnOp, ExprId),
+  Call(Symbol, Args),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stmt {
+  Expression(ExprId),
+  Assign { variable: ExprId, value: ExprId, mutable: bool },
+}
+
+fn cst_error(node: &SyntaxNode, msg: &str) -> SyntaxError {
+  SyntaxError::new(msg, node.text_range())
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ast {
+  exprs: Vec<Expr>,
+  stmts: Vec<Stmt>,
+  //statements of the program in source order
+  body: Vec<StmtId>,
+  //the arguments of all calls back to back
+  args: Vec<ExprId>,
+  names: Vec<String>,
+  symbols: HashMap<String, Symbol>,
+}
+
+//extra data per node kept outside of the tree, e.g. types, resolutions or spans
+#[derive(Debug, Clone)]
+pub struct SideTable<I, T> {
+  values: Vec<Option<T>>,
+  _id: PhantomData<I>,
+}
+
+impl<I: ArenaId, T> Default for SideTable<I, T> {
+  fn default() -> Self {
+    Self {
+      values: vec![],
+      _id: PhantomData,
+    }
+  }
+}
+
+impl<I: ArenaId, T> SideTable<I, T> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn insert(&mut self, id: I, value: T) -> Option<T> {
+    let index = id.index();
+    if index >= self.values.len() {
+      self.values.resize_with(index + 1, || None);
+    }
+    self.values[index].replace(value)
+  }
+
+  pub fn get(&self, id: I) -> Option<&T> {
+    self.values.get(id.index()).and_then(|value| value.as_ref())
+  }
+
+  pub fn get_mut(&mut self, id: I) -> Option<&mut T> {
+    self.values.get_mut(id.index()).and_then(|value| value.as_mut())
+  }
+
+  pub fn contains(&self, id: I) -> bool {
+    self.get(id).is_some()
+  }
+
+  pub fn len(&self) -> usize {
+    self.values.iter().filter(|value| value.is_some()).count()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+impl<I: ArenaId, T> Index<I> for SideTable<I, T> {
+  type Output = T;
+
+  fn index(&self, id: I) -> &T {
+    self.get(id).expect("no side table entry for node")
+  }
+}
+
+//byte ranges of the nodes in the source the Ast was lowered from
+#[derive(Debug, Clone, Default)]
+pub struct Spans {
+  pub exprs: SideTable<ExprId, Range<usize>>,
+  pub stmts: SideTable<StmtId, Range<usize>>,
+}
+
+impl Ast {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn intern(&mut self, name: &str) -> Symbol {
+    if let Some(symbol) = self.symbols.get(name) {
+      return *symbol;
+    }
+    let symbol = Symbol(self.names.len() as u32);
+    self.names.push(name.to_string());
+    self.symbols.insert(name.to_string(), symbol);
+    symbol
+  }
+
+  pub fn symbol(&self, name: &str) -> Option<Symbol> {
+    self.symbols.get(name).copied()
+  }
+
+  pub fn name(&self, symbol: Symbol) -> &str {
+    &self.names[symbol.index()]
+  }
+
+  pub fn alloc_expr(&mut self, expr: Expr) -> ExprId {
+    self.exprs.push(expr);
+    ExprId(self.exprs.len() as u32 - 1)
+  }
+
+  pub fn alloc_args(&mut self, args: &[ExprId]) -> Args {
+    let start = self.args.len() as u32;
+    self.args.extend_from_slice(args);
+    Args {
+      start,
+      len: args.len() as u32,
+    }
+  }
+
+  pub fn args(&self, args: Args) -> &[ExprId] {
+    &self.args[args.start as usize..(args.start + args.len) as usize]
+  }
+
+  //allocates the statement and appends it to the program body
+  pub fn push_stmt(&mut self, stmt: Stmt) -> StmtId {
+    self.stmts.push(stmt);
+    let id = StmtId(self.stmts.len() as u32 - 1);
+    self.body.push(id);
+    id
+  }
+
+  pub fn expr(&self, id: ExprId) -> &Expr {
+    &self.exprs[id.index()]
+  }
+
+  pub fn expr_mut(&mut self, id: ExprId) -> &mut Expr {
+    &mut self.exprs[id.index()]
+  }
+
+  pub fn stmt(&self, id: StmtId) -> &Stmt {
+    &self.stmts[id.index()]
+  }
+
+  pub fn body(&self) -> &[StmtId] {
+    &self.body
+  }
+
+  pub fn expr_ids(&self) -> impl Iterator<Item = ExprId> {
+    (0..self.exprs.len() as u32).map(ExprId)
+  }
+
+  pub fn expr_count(&self) -> usize {
+    self.exprs.len()
+  }
+
+  pub fn stmt_count(&self) -> usize {
+    self.stmts.len()
+  }
+
+  pub fn from_statements(ast: &[Statement]) -> Ast {
+    let mut arena = Ast::new();
+    for stmt in ast.iter() {
+      let stmt = match stmt {
+        Statement::Expression(expr) => Stmt::Expression(arena.lower_expression(expr)),
+        Statement::Assign(assign) => Stmt::Assign {
+          variable: arena.lower_expression(&assign.variable),
+          value: arena.lower_expression(&assign.value),
+          mutable: assign.mutable,
+        },
+      };
+      arena.push_stmt(stmt);
+    }
+    arena
+  }
+
+  fn lower_expression(&mut self, expr: &Expression) -> ExprId {
+    stack::grow(|| {
+      let expr = match expr {
+        Expression::ValueExpression(ValueExpression::IntValue(val)) => Expr::Int(val.value()),
+        Expression::ValueExpression(ValueExpression::FloatValue(val)) => Expr::Float(val.value()),
+        Expression::VariableExpression(var) => Expr::Variable(self.intern(&var.name)),
+        Expression::Binary { op, lhs, rhs } => {
+          let lhs = self.lower_expression(lhs);
+          let rhs = self.lower_expression(rhs);
+          Expr::Binary(*op, lhs, rhs)
+        }
+        Expression::Unary { op, operand } => Expr::Unary(*op, self.lower_expression(operand)),
+        Expression::Call(call) => {
+          let args: Vec<ExprId> = call.args.iter().map(|arg| self.lower_expression(arg)).collect();
+          Expr::Call(self.intern(&call.name), self.alloc_args(&args))
+        }
+      };
+      self.alloc_expr(expr)
+    })
+  }
+
+  //like cst::lower::lower but records the source range of every node
+  pub fn from_cst(root: &SyntaxNode) -> Result<(Ast, Spans), SyntaxError> {
+    let mut arena = Ast::new();
+    let mut spans = Spans::default();
+    for stmt in root.children() {
+      let children = stmt.children();
+      let lowered = match (stmt.kind(), children.first()) {
+        (SyntaxKind::EXPR_STMT, Some(expr)) => Stmt::Expression(arena.lower_node(expr, &mut spans)?),
+        (SyntaxKind::ASSIGN_STMT, Some(value)) => {
+          let name = stmt
+            .significant_tokens()
+            .into_iter()
+            .find(|token| token.kind() == SyntaxKind::IDENTIFIER);
+          let name = name.ok_or_else(|| cst_error(&stmt, "Missing IDENTIFIER in ASSIGN_STMT"))?;
+          let symbol = arena.intern(name.text());
+          let variable = arena.alloc_expr(Expr::Variable(symbol));
+          spans.exprs.insert(variable, name.text_range());
+          Stmt::Assign {
+            variable,
+            value: arena.lower_node(value, &mut spans)?,
+            mutable: false,
+          }
+        }
+        (kind, _) => return Err(cst_error(&stmt, &format!("Expected a statement but got: {:?}", kind))),
+      };
+      let id = arena.push_stmt(lowered);
+      spans.stmts.insert(id, stmt.text_range());
+    }
+    Ok((arena, spans))
+  }
+
+  fn lower_node(&mut self, node: &SyntaxNode, spans: &mut Spans) -> Result<ExprId, SyntaxError> {
+    stack::grow(|| {
+      let children = node.children();
+      let expr = match (node.kind(), children.as_slice()) {
+        (SyntaxKind::PAREN_EXPR, [inner]) => return self.lower_node(inner, spans),
+        (SyntaxKind::VARIABLE, _) => {
+          let name = node
+            .significant_tokens()
+            .into_iter()
+            .find(|token| token.kind() == SyntaxKind::IDENTIFIER)
+            .ok_or_else(|| cst_error(node, "Missing IDENTIFIER in VARIABLE"))?;
+          Expr::Variable(self.intern(name.text()))
+        }
+        (SyntaxKind::BINARY_EXPR, [lhs, rhs]) => {
+          let operator = node
+            .significant_tokens()
+            .into_iter()
+            .find(|token| token.kind() == SyntaxKind::OPERATOR);
+          let op = match operator.as_ref().and_then(|token| BinOp::from_symbol(token.text())) {
+            Some(op) => op,
+            None => {
+              return Err(cst_error(
+                node,
+                &format!("Unsupported operator: {:?}", operator.map(|t| t.text().to_string())),
+              ))
+            }
+          };
+          let lhs = self.lower_node(lhs, spans)?;
+          let rhs = self.lower_node(rhs, spans)?;
+          Expr::Binary(op, lhs, rhs)
+        }
+        (SyntaxKind::UNARY_EXPR, [operand]) => {
+          let operator = node
+            .significant_tokens()
+            .into_iter()
+            .find(|token| token.kind() == SyntaxKind::OPERATOR);
+          let op = match operator.as_ref().and_then(|token| UnOp::from_symbol(token.text())) {
+            Some(op) => op,
+            None => {
+              return Err(cst_error(
+                node,
+                &format!("Unsupported operator: {:?}", operator.map(|t| t.text().to_string())),
+              ))
+            }
+          };
+          Expr::Unary(op, self.lower_node(operand, spans)?)
+        }
+        (SyntaxKind::CALL_EXPR, children) => {
+          let name = node
+            .significant_tokens()
+            .into_iter()
+            .find(|token| token.kind() == SyntaxKind::IDENTIFIER)
+            .ok_or_else(|| cst_error(node, "Missing IDENTIFIER in CALL_EXPR"))?;
+          let args = children
+            .iter()
+            .map(|arg| self.lower_node(arg, spans))
+            .collect::<Result<Vec<_>, _>>()?;
+          Expr::Call(self.intern(name.text()), self.alloc_args(&args))
+        }
+        //literals and malformed nodes go through the regular lowering for the same errors
+        _ => match &crate::cst::lower::lower_expression(node)? {
+          Expression::ValueExpression(ValueExpression::IntValue(val)) => Expr::Int(val.value()),
+          Expression::ValueExpression(ValueExpression::FloatValue(val)) => Expr::Float(val.value()),
+          Expression::VariableExpression(var) => Expr::Variable(self.intern(&var.name)),
+          Expression::Binary { .. } | Expression::Unary { .. } | Expression::Call(_) => unreachable!(),
+        },
+      };
+      let id = self.alloc_expr(expr);
+      spans.exprs.insert(id, node.text_range());
+      Ok(id)
+    })
+  }
+
+  pub fn to_statements(&self) -> Vec<Statement> {
+    self
+      .body
+      .iter()
+      .map(|id| match *self.stmt(*id) {
+        Stmt::Expression(expr) => Statement::Expression(self.to_expression(expr)),
+        Stmt::Assign { variable, value, mutable } => {
+          AssignStmt::statement(self.to_expression(variable), self.to_expression(value), mutable)
+        }
+      })
+      .collect()
+  }
+
+  pub fn to_expression(&self, id: ExprId) -> Expression {
+    stack::grow(|| match *self.expr(id) {
+      Expr::Int(val) => ValueExpression::int_value(val),
+      Expr::Float(val) => ValueExpression::float_value(val),
+      Expr::Variable(symbol) => VariableExpression::expression(self.name(symbol).to_string()),
+      Expr::Binary(op, lhs, rhs) => Expression::binary(op, self.to_expression(lhs), self.to_expression(rhs)),
+      Expr::Unary(op, operand) => Expression::unary(op, self.to_expression(operand)),
+      Expr::Call(name, args) => CallExpression::expression(
+        self.name(name).to_string(),
+        self.args(args).iter().map(|arg| self.to_expression(*arg)).collect(),
+      ),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+
+  const INPUT: &str = "var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / ab % -3";
+
+  #[test]
+  fn test_roundtrip() {
+    let ast = Parser::new(Lexer::new().lex(INPUT).unwrap()).parse();
+    let arena = Ast::from_statements(&ast);
+    assert_eq!(arena.stmt_count(), 2);
+    assert_eq!(arena.expr_count(), 20);
+    assert_eq!(arena.to_statements(), ast);
+
+    //every name is stored once
+    let ab = arena.symbol("ab").unwrap();
+    let uses = arena.expr_ids().filter(|id| *arena.expr(*id) == Expr::Variable(ab)).count();
+    assert_eq!(uses, 2);
+  }
+
+  #[test]
+  fn test_parse_arena() {
+    let mut parser = Parser::new(Lexer::new().lex(INPUT).unwrap());
+    let arena = parser.parse_arena();
+    let ast = Parser::new(Lexer::new().lex(INPUT).unwrap()).parse();
+    assert_eq!(arena, Ast::from_statements(&ast));
+    assert!(parser.errors().is_empty());
+
+    let mut parser = Parser::new(Lexer::new().lex("var a = 1\na + (").unwrap());
+    assert_eq!(parser.parse_arena().stmt_count(), 1);
+    assert_eq!(parser.errors().len(), 1);
+  }
+
+  #[test]
+  fn test_side_table() {
+    let arena = Ast::from_statements(&Parser::new(Lexer::new().lex(INPUT).unwrap()).parse());
+    let mut is_int: SideTable<ExprId, bool> = SideTable::new();
+    for id in arena.expr_ids() {
+      is_int.insert(id, matches!(arena.expr(id), Expr::Int(_)));
+    }
+    assert_eq!(is_int.len(), arena.expr_count());
+    assert_eq!(arena.expr_ids().filter(|id| is_int[*id]).count(), 5);
+
+    let empty: SideTable<StmtId, u8> = SideTable::new();
+    assert!(empty.get(arena.body()[0]).is_none());
+  }
+
+  #[test]
+  fn test_spans_from_cst() {
+    let parse = crate::cst::parse(INPUT).unwrap();
+    let (arena, spans) = Ast::from_cst(&parse.syntax()).unwrap();
+    assert_eq!(arena.to_statements(), parse.to_ast().unwrap());
+    for id in arena.expr_ids() {
+      let range = spans.exprs[id].clone();
+      if let Expr::Variable(symbol) = arena.expr(id) {
+        assert_eq!(&INPUT[range], arena.name(*symbol));
+      }
+    }
+    let second = arena.body()[1];
+    assert_eq!(&INPUT[spans.stmts[second].clone()], "hello / ab % -3");
+  }
+}
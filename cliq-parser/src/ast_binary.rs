@@ -0,0 +1,411 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use crate::{
+  expression::{
+    call_expression::CallExpression,
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+
+//layout, all integers are LEB128 varints unless noted:
+//  magic "CLQB", version (u16 little endian)
+//  string table: count, then length + utf8 bytes per string
+//  statements: count, then per statement a tag byte
+//    STMT_EXPR   expression
+//    STMT_ASSIGN mutable byte, expression (variable), expression (value)
+//  expression: node count, then the nodes in postfix order so decoding needs no recursion
+//    EXPR_INT zigzag value, EXPR_FLOAT f32 little endian, EXPR_VAR string index,
+//    EXPR_ADD / EXPR_SUB / EXPR_MUL / EXPR_DIV / EXPR_REM take the two previous operands
+//    EXPR_NEG takes the previous operand
+//    EXPR_CALL string index of the function, argument count, takes that many previous operands
+//version 2 added EXPR_REM and EXPR_NEG, version 3 added EXPR_CALL, files of older versions are still readable
+pub const MAGIC: [u8; 4] = *b"CLQB";
+pub const BINARY_VERSION: u16 = 3;
+
+const STMT_EXPR: u8 = 0;
+const STMT_ASSIGN: u8 = 1;
+
+const EXPR_INT: u8 = 0;
+const EXPR_FLOAT: u8 = 1;
+const EXPR_VAR: u8 = 2;
+const EXPR_ADD: u8 = 3;
+const EXPR_SUB: u8 = 4;
+const EXPR_MUL: u8 = 5;
+const EXPR_DIV: u8 = 6;
+const EXPR_REM: u8 = 7;
+const EXPR_NEG: u8 = 8;
+const EXPR_CALL: u8 = 9;
+
+fn binary_tag(op: BinOp) -> u8 {
+  match op {
+    BinOp::Add => EXPR_ADD,
+    BinOp::Sub => EXPR_SUB,
+    BinOp::Mul => EXPR_MUL,
+    BinOp::Div => EXPR_DIV,
+    BinOp::Rem => EXPR_REM,
+  }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+  Io(io::Error),
+  BadMagic,
+  UnsupportedVersion(u16),
+  UnexpectedEof,
+  InvalidTag(u8),
+  InvalidString(usize),
+  MalformedExpression,
+  TrailingBytes,
+}
+
+impl std::fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DecodeError::Io(err) => write!(f, "{}", err),
+      DecodeError::BadMagic => write!(f, "Not a binary cliq AST"),
+      DecodeError::UnsupportedVersion(version) => {
+        write!(f, "Unsupported binary AST version {} (supported up to {})", version, BINARY_VERSION)
+      }
+      DecodeError::UnexpectedEof => write!(f, "Unexpected end of binary AST"),
+      DecodeError::InvalidTag(tag) => write!(f, "Invalid tag {} in binary AST", tag),
+      DecodeError::InvalidString(index) => write!(f, "Invalid string {} in binary AST", index),
+      DecodeError::MalformedExpression => write!(f, "Malformed expression in binary AST"),
+      DecodeError::TrailingBytes => write!(f, "Trailing bytes after binary AST"),
+    }
+  }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+  fn from(err: io::Error) -> Self {
+    DecodeError::Io(err)
+  }
+}
+
+struct Encoder<'a> {
+  strings: Vec<&'a str>,
+  //index of every string in strings, so interning does not scan the table
+  indices: HashMap<&'a str, usize>,
+  body: Vec<u8>,
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+  loop {
+    let byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      out.push(byte);
+      return;
+    }
+    out.push(byte | 0x80);
+  }
+}
+
+impl<'a> Encoder<'a> {
+  fn intern(&mut self, name: &'a str) -> usize {
+    *self.indices.entry(name).or_insert_with(|| {
+      self.strings.push(name);
+      self.strings.len() - 1
+    })
+  }
+
+  fn expression(&mut self, expr: &'a Expression) {
+    //collect the nodes in prefix order with an explicit stack, reversed that is postfix with swapped operands
+    let mut nodes = vec![];
+    let mut stack = vec![expr];
+    while let Some(expr) = stack.pop() {
+      nodes.push(expr);
+      match expr {
+        Expression::Binary { lhs, rhs, .. } => {
+          stack.push(lhs);
+          stack.push(rhs);
+        }
+        Expression::Unary { operand, .. } => stack.push(operand),
+        Expression::Call(call) => stack.extend(&call.args),
+        _ => {}
+      }
+    }
+
+    write_varint(&mut self.body, nodes.len() as u64);
+    for expr in nodes.into_iter().rev() {
+      match expr {
+        Expression::ValueExpression(ValueExpression::IntValue(val)) => {
+          self.body.push(EXPR_INT);
+          let val = val.value();
+          write_varint(&mut self.body, ((val << 1) ^ (val >> 31)) as u32 as u64);
+        }
+        Expression::ValueExpression(ValueExpression::FloatValue(val)) => {
+          self.body.push(EXPR_FLOAT);
+          self.body.extend_from_slice(&val.value().to_le_bytes());
+        }
+        Expression::VariableExpression(var) => {
+          let index = self.intern(&var.name);
+          self.body.push(EXPR_VAR);
+          write_varint(&mut self.body, index as u64);
+        }
+        Expression::Binary { op, .. } => self.body.push(binary_tag(*op)),
+        Expression::Unary { op, .. } => self.body.push(match op {
+          UnOp::Neg => EXPR_NEG,
+        }),
+        Expression::Call(call) => {
+          let index = self.intern(&call.name);
+          self.body.push(EXPR_CALL);
+          write_varint(&mut self.body, index as u64);
+          write_varint(&mut self.body, call.args.len() as u64);
+        }
+      }
+    }
+  }
+}
+
+pub fn encode(ast: &[Statement]) -> Vec<u8> {
+  let mut encoder = Encoder {
+    strings: vec![],
+    indices: HashMap::new(),
+    body: vec![],
+  };
+  write_varint(&mut encoder.body, ast.len() as u64);
+  for stmt in ast.iter() {
+    match stmt {
+      Statement::Expression(expr) => {
+        encoder.body.push(STMT_EXPR);
+        encoder.expression(expr);
+      }
+      Statement::Assign(assign) => {
+        encoder.body.push(STMT_ASSIGN);
+        encoder.body.push(assign.mutable as u8);
+        encoder.expression(&assign.variable);
+        encoder.expression(&assign.value);
+      }
+    }
+  }
+
+  let mut out = MAGIC.to_vec();
+  out.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+  write_varint(&mut out, encoder.strings.len() as u64);
+  for string in encoder.strings.iter() {
+    write_varint(&mut out, string.len() as u64);
+    out.extend_from_slice(string.as_bytes());
+  }
+  out.extend_from_slice(&encoder.body);
+  out
+}
+
+struct Decoder<'b> {
+  bytes: &'b [u8],
+  pos: usize,
+  strings: Vec<String>,
+}
+
+impl<'b> Decoder<'b> {
+  fn byte(&mut self) -> Result<u8, DecodeError> {
+    let byte = *self.bytes.get(self.pos).ok_or(DecodeError::UnexpectedEof)?;
+    self.pos += 1;
+    Ok(byte)
+  }
+
+  fn take(&mut self, len: usize) -> Result<&'b [u8], DecodeError> {
+    let end = self.pos.checked_add(len).ok_or(DecodeError::UnexpectedEof)?;
+    let slice = self.bytes.get(self.pos..end).ok_or(DecodeError::UnexpectedEof)?;
+    self.pos = end;
+    Ok(slice)
+  }
+
+  fn varint(&mut self) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+      let byte = self.byte()?;
+      value |= ((byte & 0x7f) as u64) << shift;
+      if byte & 0x80 == 0 {
+        return Ok(value);
+      }
+    }
+    Err(DecodeError::MalformedExpression)
+  }
+
+  //lengths are bounded by the remaining input so corrupt counts can not trigger huge allocations
+  fn length(&mut self) -> Result<usize, DecodeError> {
+    let len = self.varint()? as usize;
+    if len > self.bytes.len() - self.pos {
+      return Err(DecodeError::UnexpectedEof);
+    }
+    Ok(len)
+  }
+
+  fn expression(&mut self) -> Result<Expression, DecodeError> {
+    let count = self.length()?;
+    let mut stack: Vec<Expression> = vec![];
+    for _ in 0..count {
+      let tag = self.byte()?;
+      let expr = match tag {
+        EXPR_INT => {
+          let zigzag = self.varint()? as u32;
+          ValueExpression::int_value(((zigzag >> 1) as i32) ^ -((zigzag & 1) as i32))
+        }
+        EXPR_FLOAT => ValueExpression::float_value(f32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+        EXPR_VAR => {
+          let index = self.varint()? as usize;
+          let name = self.strings.get(index).ok_or(DecodeError::InvalidString(index))?;
+          VariableExpression::expression(name.clone())
+        }
+        EXPR_ADD | EXPR_SUB | EXPR_MUL | EXPR_DIV | EXPR_REM => {
+          let rhs = stack.pop().ok_or(DecodeError::MalformedExpression)?;
+          let lhs = stack.pop().ok_or(DecodeError::MalformedExpression)?;
+          let op = BinOp::ALL.into_iter().find(|op| binary_tag(*op) == tag).unwrap();
+          Expression::binary(op, lhs, rhs)
+        }
+        EXPR_NEG => {
+          let operand = stack.pop().ok_or(DecodeError::MalformedExpression)?;
+          Expression::unary(UnOp::Neg, operand)
+        }
+        EXPR_CALL => {
+          let index = self.varint()? as usize;
+          let name = self.strings.get(index).ok_or(DecodeError::InvalidString(index))?.clone();
+          let count = self.varint()?;
+          let start = stack
+            .len()
+            .checked_sub(usize::try_from(count).map_err(|_| DecodeError::MalformedExpression)?)
+            .ok_or(DecodeError::MalformedExpression)?;
+          CallExpression::expression(name, stack.split_off(start))
+        }
+        tag => return Err(DecodeError::InvalidTag(tag)),
+      };
+      stack.push(expr);
+    }
+    match (stack.pop(), stack.is_empty()) {
+      (Some(expr), true) => Ok(expr),
+      _ => Err(DecodeError::MalformedExpression),
+    }
+  }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<Statement>, DecodeError> {
+  if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+    return Err(DecodeError::BadMagic);
+  }
+  let mut decoder = Decoder {
+    bytes,
+    pos: MAGIC.len(),
+    strings: vec![],
+  };
+  let version = u16::from_le_bytes(decoder.take(2)?.try_into().unwrap());
+  if version == 0 || version > BINARY_VERSION {
+    return Err(DecodeError::UnsupportedVersion(version));
+  }
+
+  let string_count = decoder.length()?;
+  for index in 0..string_count {
+    let len = decoder.length()?;
+    let string = std::str::from_utf8(decoder.take(len)?).map_err(|_| DecodeError::InvalidString(index))?;
+    decoder.strings.push(string.to_string());
+  }
+
+  let stmt_count = decoder.length()?;
+  let mut ast = Vec::with_capacity(stmt_count);
+  for _ in 0..stmt_count {
+    match decoder.byte()? {
+      STMT_EXPR => ast.push(Statement::Expression(decoder.expression()?)),
+      STMT_ASSIGN => {
+        let mutable = decoder.byte()? != 0;
+        let variable = decoder.expression()?;
+        let value = decoder.expression()?;
+        ast.push(AssignStmt::statement(variable, value, mutable));
+      }
+      tag => return Err(DecodeError::InvalidTag(tag)),
+    }
+  }
+  if decoder.pos != bytes.len() {
+    return Err(DecodeError::TrailingBytes);
+  }
+  Ok(ast)
+}
+
+pub fn write_file<P: AsRef<Path>>(path: P, ast: &[Statement]) -> io::Result<()> {
+  fs::write(path, encode(ast))
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<Statement>, DecodeError> {
+  decode(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    let tokens = Lexer::new().lex(input).unwrap();
+    Parser::new(tokens).parse()
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let mut ast =
+      parse("var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / 4 - .5\nab * ab % -ab\nmax(min(ab, 1), -hello, f())");
+    ast.push(Statement::Expression(ValueExpression::int_value(i32::MIN)));
+    ast.push(AssignStmt::statement(
+      VariableExpression::expression("m".to_string()),
+      ValueExpression::int_value(-1),
+      true,
+    ));
+    let bytes = encode(&ast);
+    assert_eq!(&bytes[..4], b"CLQB");
+    assert_eq!(decode(&bytes).unwrap(), ast);
+    assert_eq!(decode(&encode(&[])).unwrap(), vec![]);
+  }
+
+  #[test]
+  fn test_deep_expression() {
+    let ast = vec![Statement::Expression(crate::expression::tests::chain(1_000_000))];
+    let bytes = encode(&ast);
+    assert_eq!(encode(&decode(&bytes).unwrap()), bytes);
+  }
+
+  #[test]
+  fn test_string_table() {
+    let ast = parse("var a = b + a\nmax(b, c) * a");
+    let bytes = encode(&ast);
+    //after the header, a, b, c and max are each stored once
+    assert_eq!(bytes[6], 4);
+    assert_eq!(bytes.windows(3).filter(|w| w == b"max").count(), 1);
+    assert_eq!(decode(&bytes).unwrap(), ast);
+  }
+
+  #[test]
+  fn test_smaller_than_json() {
+    let ast = parse("var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2");
+    let bytes = encode(&ast);
+    let json = serde_json::to_string(&ast).unwrap();
+    println!("binary: {} bytes, json: {} bytes", bytes.len(), json.len());
+    assert!(bytes.len() * 10 < json.len());
+  }
+
+  #[test]
+  fn test_invalid_input() {
+    let bytes = encode(&parse("var a = 1 + 2\na * b"));
+    assert!(matches!(decode(b"JSON"), Err(DecodeError::BadMagic)));
+    assert!(matches!(decode(b"CLQB\x09\x00"), Err(DecodeError::UnsupportedVersion(9))));
+    for len in 4..bytes.len() {
+      assert!(decode(&bytes[..len]).is_err(), "truncated to {} bytes", len);
+    }
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(decode(&trailing), Err(DecodeError::TrailingBytes)));
+  }
+
+  #[test]
+  fn test_file() {
+    let path = std::env::temp_dir().join(format!("cliq-ast-{}.bin", std::process::id()));
+    let ast = parse("(4 + 3) * 2");
+    write_file(&path, &ast).unwrap();
+    assert_eq!(read_file(&path).unwrap(), ast);
+    std::fs::remove_file(&path).unwrap();
+  }
+}
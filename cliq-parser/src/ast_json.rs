@@ -0,0 +1,194 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::statement::Statement;
+
+//bump whenever the json shape of any Statement or Expression changes
+//and regenerate schema/ast.schema.json with CLIQ_UPDATE_SCHEMA=1 cargo test
+//version 2 added RemOpr and UnaryExpression, version 3 added CallExpression,
+//documents of an older version are valid documents of every later one
+pub const AST_SCHEMA_VERSION: u32 = 3;
+
+/// A cliq program as exchanged with external tools.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "cliq AST")]
+pub struct AstDocument {
+  /// Version of the AST format, readers reject versions they do not know.
+  pub version: u32,
+  /// Top level statements in source order.
+  pub statements: Vec<Statement>,
+}
+
+impl AstDocument {
+  pub fn new(statements: Vec<Statement>) -> Self {
+    Self {
+      version: AST_SCHEMA_VERSION,
+      statements,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum AstJsonError {
+  Json(serde_json::Error),
+  UnsupportedVersion(u32),
+}
+
+impl std::fmt::Display for AstJsonError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AstJsonError::Json(err) => write!(f, "Invalid AST json: {}", err),
+      AstJsonError::UnsupportedVersion(version) => {
+        write!(f, "Unsupported AST version {} (supported up to {})", version, AST_SCHEMA_VERSION)
+      }
+    }
+  }
+}
+
+impl std::error::Error for AstJsonError {}
+
+impl From<serde_json::Error> for AstJsonError {
+  fn from(err: serde_json::Error) -> Self {
+    AstJsonError::Json(err)
+  }
+}
+
+//an AstDocument that borrows the statements, deep expressions are not cloned to be written
+#[derive(Serialize)]
+struct AstDocumentRef<'a> {
+  version: u32,
+  statements: &'a [Statement],
+}
+
+impl<'a> AstDocumentRef<'a> {
+  fn new(statements: &'a [Statement]) -> Self {
+    Self {
+      version: AST_SCHEMA_VERSION,
+      statements,
+    }
+  }
+}
+
+//the version is read before the statements, so documents of a later version are rejected by their version
+//and not by whatever part of their shape is unknown
+#[derive(Deserialize)]
+struct Header {
+  version: u32,
+}
+
+#[derive(Deserialize)]
+struct Statements {
+  statements: Vec<Statement>,
+}
+
+//a long chain like `a + b + ... + z` nests one json object per operator,
+//reading continues on a new stack instead of stopping at serde_json's limit of 128 levels
+fn deserialize<T: DeserializeOwned>(json: &str) -> Result<T, serde_json::Error> {
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  deserializer.disable_recursion_limit();
+  let value = T::deserialize(serde_stacker::Deserializer::new(&mut deserializer))?;
+  deserializer.end()?;
+  Ok(value)
+}
+
+pub fn to_json(ast: &[Statement]) -> String {
+  serde_json::to_string(&AstDocumentRef::new(ast)).unwrap_or_else(|err| format!("Error serializing AST: {}", err))
+}
+
+pub fn to_json_pretty(ast: &[Statement]) -> String {
+  serde_json::to_string_pretty(&AstDocumentRef::new(ast)).unwrap_or_else(|err| format!("Error serializing AST: {}", err))
+}
+
+pub fn from_json(json: &str) -> Result<Vec<Statement>, AstJsonError> {
+  //the shape Parser::serialize_ast writes, a bare statement array, is read as the unversioned format
+  if json.trim_start().starts_with('[') {
+    return Ok(deserialize(json)?);
+  }
+  let Header { version } = deserialize(json)?;
+  if version == 0 || version > AST_SCHEMA_VERSION {
+    return Err(AstJsonError::UnsupportedVersion(version));
+  }
+  Ok(deserialize::<Statements>(json)?.statements)
+}
+
+pub fn json_schema() -> String {
+  let schema = schemars::schema_for!(AstDocument);
+  serde_json::to_string_pretty(&schema).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+
+  const SCHEMA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/ast.schema.json");
+
+  fn parse(input: &str) -> Vec<Statement> {
+    let tokens = Lexer::new().lex(input).unwrap();
+    Parser::new(tokens).parse()
+  }
+
+  #[test]
+  fn test_roundtrip() {
+    let ast = parse("var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / 4 % -hello");
+    let json = to_json(&ast);
+    assert!(json.starts_with(&format!("{{\"version\":{}", AST_SCHEMA_VERSION)));
+    assert_eq!(from_json(&json).unwrap(), ast);
+    assert_eq!(from_json(&to_json_pretty(&ast)).unwrap(), ast);
+  }
+
+  #[test]
+  fn test_legacy_output() {
+    let json = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/output.json")).unwrap();
+    let ast = from_json(&json).unwrap();
+    assert_eq!(ast, parse("var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2"));
+
+    let mut parser = Parser::new(Lexer::new().lex("(4 + 3) * 2").unwrap());
+    let ast = parser.parse();
+    assert_eq!(from_json(&parser.serialize_ast()).unwrap(), ast);
+  }
+
+  #[test]
+  fn test_unsupported_version() {
+    let json = format!("{{\"version\":{},\"statements\":[]}}", AST_SCHEMA_VERSION + 1);
+    assert!(matches!(from_json(&json), Err(AstJsonError::UnsupportedVersion(_))));
+    assert!(matches!(from_json("{\"statements\":[]}"), Err(AstJsonError::Json(_))));
+  }
+
+  #[test]
+  fn test_deep_expression() {
+    let deep = vec![Statement::Expression(crate::expression::tests::chain(100_000))];
+    assert!(to_json(&deep).starts_with("{\"version\":"));
+    let mut parser = Parser::new(Lexer::new().lex(&vec!["1"; 300].join(" + ")).unwrap());
+    parser.parse();
+    assert!(parser.serialize_ast().starts_with("[{\"Expression\""));
+
+    assert_eq!(from_json(&to_json(&deep)).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_deep_roundtrip() {
+    let ast = parse(&vec!["1"; 1000].join(" + "));
+    assert_eq!(from_json(&to_json(&ast)).unwrap(), ast);
+    assert_eq!(from_json(&to_json_pretty(&ast)).unwrap(), ast);
+    let mut parser = Parser::new(Lexer::new().lex(&vec!["a"; 1000].join(" * ")).unwrap());
+    let ast = parser.parse();
+    assert_eq!(from_json(&parser.serialize_ast()).unwrap(), ast);
+  }
+
+  #[test]
+  fn test_schema_up_to_date() {
+    let schema = json_schema();
+    if std::env::var_os("CLIQ_UPDATE_SCHEMA").is_some() {
+      std::fs::write(SCHEMA_PATH, &schema).unwrap();
+    }
+    let committed = std::fs::read_to_string(SCHEMA_PATH).unwrap_or_default();
+    assert_eq!(
+      committed, schema,
+      "schema changed, rerun with CLIQ_UPDATE_SCHEMA=1 and bump AST_SCHEMA_VERSION"
+    );
+  }
+}
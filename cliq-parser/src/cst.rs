@@ -0,0 +1,208 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use cliq_lexer::{lexer::Lexer, token::Token};
+
+use crate::statement::Statement;
+
+use self::fix::Fix;
+use self::green::GreenNode;
+use self::red::SyntaxNode;
+
+mod grammar;
+pub mod fix;
+pub mod incremental;
+pub mod green;
+pub mod lower;
+pub mod red;
+pub mod syntax_kind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+  pub msg: String,
+  pub range: Range<usize>,
+  pub fix: Option<Fix>,
+}
+
+impl SyntaxError {
+  pub fn new(msg: impl Into<String>, range: Range<usize>) -> Self {
+    Self {
+      msg: msg.into(),
+      range,
+      fix: None,
+    }
+  }
+
+  pub fn with_fix(mut self, fix: Fix) -> Self {
+    self.fix = Some(fix);
+    self
+  }
+
+  pub(crate) fn map_offsets(&self, f: impl Fn(usize) -> usize) -> SyntaxError {
+    SyntaxError {
+      msg: self.msg.clone(),
+      range: f(self.range.start)..f(self.range.end),
+      fix: self.fix.as_ref().map(|fix| fix.map_offsets(&f)),
+    }
+  }
+}
+
+//result of a lossless parse, the tree always covers the whole input even if there are errors
+#[derive(Debug, Clone)]
+pub struct Parse {
+  green: Rc<GreenNode>,
+  errors: Vec<SyntaxError>,
+}
+
+impl Parse {
+  fn new(green: Rc<GreenNode>, errors: Vec<SyntaxError>) -> Self {
+    Self { green, errors }
+  }
+
+  pub fn green(&self) -> &Rc<GreenNode> {
+    &self.green
+  }
+
+  pub fn syntax(&self) -> SyntaxNode {
+    SyntaxNode::new_root(self.green.clone())
+  }
+
+  pub fn errors(&self) -> &[SyntaxError] {
+    &self.errors
+  }
+
+  pub fn to_ast(&self) -> Result<Vec<Statement>, SyntaxError> {
+    if let Some(err) = self.errors.first() {
+      return Err(err.clone());
+    }
+    lower::lower(&self.syntax())
+  }
+}
+
+//tokens must come from lexing src, their spans are used to slice the original text
+pub fn parse_tokens(src: &str, tokens: &[Token]) -> Parse {
+  grammar::Grammar::new(src, tokens).parse()
+}
+
+pub fn parse(src: &str) -> Result<Parse, String> {
+  let tokens = Lexer::new().lex(src)?;
+  Ok(parse_tokens(src, &tokens))
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_lexer::token::Token;
+
+  use super::syntax_kind::SyntaxKind;
+  use crate::parser::Parser;
+
+  #[test]
+  fn test_lossless() {
+    let input = "  var hello = ((ab + 123.30) *\n (ac - (33 + 22) * 2)) + .2 // done\n\n hello / 2 ";
+    let parse = super::parse(input).unwrap();
+    assert!(parse.errors().is_empty());
+    let root = parse.syntax();
+    assert_eq!(root.text(), input);
+    assert_eq!(root.text_range(), 0..input.len());
+    println!("{:#?}", root.green());
+
+    let stmts = root.children();
+    assert_eq!(stmts.len(), 2);
+    assert_eq!(stmts[0].kind(), SyntaxKind::ASSIGN_STMT);
+    assert_eq!(stmts[1].kind(), SyntaxKind::EXPR_STMT);
+    let comment = root.tokens().into_iter().find(|t| t.kind() == SyntaxKind::COMMENT).unwrap();
+    assert_eq!(&input[comment.text_range()], "// done");
+  }
+
+  #[test]
+  fn test_lower_matches_parser() {
+    let input = "var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\n4 + 3 * 2 / 7 - 5\nmax(hello, -f( ), (1))\nf\n(a)";
+    let tokens = Lexer::new().lex(input).unwrap();
+    let expected = Parser::new(tokens).parse();
+    let lowered = super::parse(input).unwrap().to_ast().unwrap();
+    assert_eq!(lowered, expected);
+  }
+
+  #[test]
+  fn test_errors_keep_all_text() {
+    let input = "var = (1 + ) ) var b = 2";
+    let parse = super::parse(input).unwrap();
+    println!("{:#?}", parse.errors());
+    assert!(!parse.errors().is_empty());
+    assert_eq!(parse.syntax().text(), input);
+    assert!(parse.to_ast().is_err());
+  }
+
+  #[test]
+  fn test_fixes() {
+    let fixed = |input: &str| {
+      let parse = super::parse(input).unwrap();
+      let fixes: Vec<_> = parse.errors().iter().filter_map(|err| err.fix.clone()).collect();
+      super::fix::apply(input, &fixes)
+    };
+    assert_eq!(fixed("var x 3"), "var x = 3");
+    assert_eq!(fixed("var x // comment\n  -3"), "var x = // comment\n  -3");
+    assert_eq!(fixed("(1 + 2\nvar a = (3 * (a)"), "(1 + 2)\nvar a = (3 * (a))");
+    assert!(super::parse(&fixed("var x 3\n(1 + 2")).unwrap().errors().is_empty());
+    //nothing to insert that would make sense
+    assert!(super::parse("var 3").unwrap().errors().iter().all(|err| err.fix.is_none()));
+    assert!(super::parse("var x").unwrap().errors().iter().all(|err| err.fix.is_none()));
+  }
+
+  #[test]
+  fn test_max_depth() {
+    //lexing this much is slow, the tokens of one level are repeated with their spans moved
+    let depth = 100_000;
+    let src = format!("{}x{}\nvar a = 1", "-(".repeat(depth), ")".repeat(depth));
+    let unit = Lexer::new().lex("-(x)\nvar a = 1").unwrap();
+    let shifted = |token: &Token, offset: usize| {
+      let mut token = token.clone();
+      token.span.start += offset;
+      token.span.end += offset;
+      token
+    };
+    let mut tokens = vec![];
+    for level in 0..depth {
+      tokens.push(shifted(&unit[0], 2 * level));
+      tokens.push(shifted(&unit[1], 2 * level));
+    }
+    tokens.push(shifted(&unit[2], 2 * depth - 2));
+    tokens.extend((0..depth).map(|level| shifted(&unit[3], 2 * depth - 2 + level)));
+    tokens.extend(unit[4..].iter().map(|token| shifted(token, 3 * depth - 3)));
+
+    let parse = super::parse_tokens(&src, &tokens);
+    let msgs: Vec<_> = parse.errors().iter().map(|err| err.msg.as_str()).collect();
+    assert_eq!(msgs, vec!["Expression is nested deeper than 256 levels"]);
+    assert_eq!(parse.syntax().text(), src);
+    //the statement after it is parsed as usual
+    let stmts = parse.syntax().children();
+    assert_eq!(stmts.len(), 2);
+    assert_eq!(stmts[1].kind(), SyntaxKind::ASSIGN_STMT);
+
+    //a chain builds a tree as deep as its length, what does not fit is kept flat
+    let unit = Lexer::new().lex("1 + 1\nvar a = 1").unwrap();
+    let chain = |terms: usize| {
+      let src = format!("{}1\nvar a = 1", "1 + ".repeat(terms - 1));
+      let mut tokens = vec![unit[0].clone()];
+      for term in 1..terms {
+        tokens.extend(unit[1..5].iter().map(|token| shifted(token, 4 * (term - 1))));
+      }
+      tokens.extend(unit[5..].iter().map(|token| shifted(token, 4 * (terms - 2))));
+      (src, tokens)
+    };
+    let (src, tokens) = chain(20_000);
+    let parse = super::parse_tokens(&src, &tokens);
+    let msgs: Vec<_> = parse.errors().iter().map(|err| err.msg.as_str()).collect();
+    assert_eq!(msgs, vec!["Expression chains more than 4096 binary operators"]);
+    assert_eq!(parse.syntax().text(), src);
+    let stmts = parse.syntax().children();
+    assert_eq!(stmts.len(), 2);
+    assert_eq!(stmts[1].kind(), SyntaxKind::ASSIGN_STMT);
+    assert!(parse.to_ast().is_err());
+    let (src, tokens) = chain(4097);
+    assert_eq!(super::parse_tokens(&src, &tokens).to_ast().unwrap().len(), 2);
+    let (src, tokens) = chain(4098);
+    assert!(super::parse_tokens(&src, &tokens).to_ast().is_err());
+  }
+}
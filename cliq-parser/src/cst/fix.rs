@@ -0,0 +1,91 @@
+use super::incremental::TextEdit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+  //the result is what was meant, tools may apply it without asking
+  MachineApplicable,
+  //a guess, e.g. a similar name, it has to be looked at first
+  MaybeIncorrect,
+}
+
+//edits that together resolve a diagnostic, ranges refer to the diagnosed source and never overlap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+  pub msg: String,
+  pub edits: Vec<TextEdit>,
+  pub applicability: Applicability,
+}
+
+impl Fix {
+  pub fn new(msg: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+    Self {
+      msg: msg.into(),
+      edits,
+      applicability: Applicability::MachineApplicable,
+    }
+  }
+
+  pub fn maybe_incorrect(mut self) -> Self {
+    self.applicability = Applicability::MaybeIncorrect;
+    self
+  }
+
+  pub fn insert(msg: impl Into<String>, offset: usize, text: impl Into<String>) -> Self {
+    Self::new(msg, vec![TextEdit::new(offset..offset, text)])
+  }
+
+  pub fn apply(&self, src: &str) -> String {
+    apply(src, std::slice::from_ref(self))
+  }
+
+  //two insertions at the same offset conflict too, their order would be arbitrary
+  pub fn overlaps(&self, other: &Fix) -> bool {
+    self.edits.iter().any(|edit| {
+      other
+        .edits
+        .iter()
+        .any(|other| edit.range.start < other.range.end && other.range.start < edit.range.end || edit.range.start == other.range.start)
+    })
+  }
+
+  //moves the edits along with the text they were made for
+  pub(crate) fn map_offsets(&self, f: impl Fn(usize) -> usize) -> Fix {
+    Fix {
+      edits: self
+        .edits
+        .iter()
+        .map(|edit| TextEdit::new(f(edit.range.start)..f(edit.range.end), edit.replacement.clone()))
+        .collect(),
+      ..self.clone()
+    }
+  }
+}
+
+//fixes must not overlap each other
+pub fn apply(src: &str, fixes: &[Fix]) -> String {
+  let mut edits: Vec<&TextEdit> = fixes.iter().flat_map(|fix| &fix.edits).collect();
+  edits.sort_by_key(|edit| edit.range.start);
+  //back to front so the ranges of the remaining edits stay valid
+  edits.iter().rev().fold(src.to_string(), |src, edit| edit.apply(&src))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_apply() {
+    let fix = Fix::new("swap", vec![TextEdit::new(4..5, "second"), TextEdit::new(0..1, "first")]);
+    assert_eq!(fix.apply("a + b"), "first + second");
+    assert_eq!(apply("a + b", &[fix, Fix::insert("bang", 5, "!")]), "first + second!");
+  }
+
+  #[test]
+  fn test_overlaps() {
+    let fix = Fix::new("b", vec![TextEdit::new(4..5, "")]);
+    assert!(fix.overlaps(&Fix::new("a", vec![TextEdit::new(2..5, "")])));
+    assert!(fix.overlaps(&Fix::insert("a", 4, "x")));
+    assert!(!fix.overlaps(&Fix::insert("a", 5, "x")));
+    assert!(!fix.overlaps(&Fix::new("a", vec![TextEdit::new(0..4, "")])));
+  }
+}
@@ -0,0 +1,357 @@
+use std::rc::Rc;
+
+use cliq_lexer::{token::Token, token_t::TokenT};
+
+use crate::expression::operator::UnOp;
+use crate::parser::{get_precedence, DEFAULT_MAX_CHAIN, DEFAULT_MAX_DEPTH};
+
+use super::fix::Fix;
+use super::green::{GreenElement, GreenNode, GreenNodeBuilder};
+use super::syntax_kind::SyntaxKind;
+use super::{Parse, SyntaxError};
+
+pub(crate) struct Step {
+  pub(crate) elements: Vec<GreenElement>,
+  pub(crate) errors: Vec<SyntaxError>,
+}
+
+//same grammar as Parser but every token, trivia included, ends up in the tree
+//trivia is attached to the innermost node that is open when the next significant token is consumed
+pub(crate) struct Grammar<'t> {
+  src: &'t str,
+  tokens: &'t [Token],
+  current_token: usize,
+  builder: GreenNodeBuilder,
+  errors: Vec<SyntaxError>,
+  //nesting of next_expression, limited the same way Parser limits it
+  depth: usize,
+  //binary operators stacked in the expression parsed last, limited like in Parser
+  height: usize,
+}
+
+impl<'t> Grammar<'t> {
+  pub(crate) fn new(src: &'t str, tokens: &'t [Token]) -> Self {
+    Self {
+      src,
+      tokens,
+      current_token: 0,
+      builder: GreenNodeBuilder::new(),
+      errors: vec![],
+      depth: 0,
+      height: 0,
+    }
+  }
+
+  fn text(&self, token: &Token) -> &'t str {
+    &self.src[token.span.start..token.span.end]
+  }
+
+  fn peek(&self) -> Option<&'t Token> {
+    self.tokens[self.current_token..]
+      .iter()
+      .find(|token| !SyntaxKind::from(token.token_t).is_trivia())
+  }
+
+  fn peek_is(&self, token_t: TokenT, value: Option<&str>) -> bool {
+    match self.peek() {
+      Some(token) => token.token_t == token_t && value.is_none_or(|value| token.value == value),
+      None => false,
+    }
+  }
+
+  fn eat_trivia(&mut self) {
+    while let Some(token) = self.tokens.get(self.current_token) {
+      let kind = SyntaxKind::from(token.token_t);
+      if !kind.is_trivia() {
+        break;
+      }
+      self.builder.token(kind, self.text(token));
+      self.current_token += 1;
+    }
+  }
+
+  fn bump(&mut self) {
+    self.eat_trivia();
+    if let Some(token) = self.tokens.get(self.current_token) {
+      self.builder.token(SyntaxKind::from(token.token_t), self.text(token));
+      self.current_token += 1;
+    }
+  }
+
+  fn error(&mut self, msg: String, fix: Option<Fix>) {
+    let range = match self.peek() {
+      Some(token) => token.span.start..token.span.end,
+      None => self.src.len()..self.src.len(),
+    };
+    self.errors.push(SyntaxError { msg, range, fix });
+  }
+
+  //where a missing token goes: right behind the last one that is there, not on the line of the next one
+  //the last significant token in front of the current one
+  fn previous(&self) -> Option<&Token> {
+    self.tokens[..self.current_token]
+      .iter()
+      .rev()
+      .find(|token| !SyntaxKind::from(token.token_t).is_trivia())
+  }
+
+  fn previous_end(&self) -> usize {
+    self.previous().map_or(0, |token| token.span.end)
+  }
+
+  fn starts_expression(token: &Token) -> bool {
+    match token.token_t {
+      TokenT::INTEGER | TokenT::FLOAT | TokenT::IDENTIFIER => true,
+      TokenT::OPERATOR => UnOp::from_symbol(&token.value).is_some(),
+      TokenT::BRACKET => token.value == "(",
+      _ => false,
+    }
+  }
+
+  //same rule as Parser: the `(` has to follow the name without trivia in between,
+  //called with the name as the current token
+  fn starts_call(&self) -> bool {
+    self
+      .tokens
+      .get(self.current_token + 1)
+      .is_some_and(|token| token.token_t == TokenT::BRACKET && token.value == "(")
+  }
+
+  fn expect(&mut self, token_t: TokenT, value: Option<&str>) {
+    if self.peek_is(token_t, value) {
+      self.bump();
+      return;
+    }
+    let msg = match (self.peek(), value) {
+      (Some(token), Some(value)) => format!("Expected token value: {:?} but got: {:?}", value, token.value),
+      (Some(token), None) => format!("Expected token type: {:?} but got: {:?}", token_t, token.token_t),
+      (None, _) => "Unexpected end of token stream".to_string(),
+    };
+    let fix = match value {
+      //`var x 3`, but not `var 3` where the name is missing too
+      Some("=")
+        if self.previous().is_some_and(|token| token.token_t == TokenT::IDENTIFIER)
+          && self.peek().is_some_and(Grammar::starts_expression) =>
+      {
+        Some(Fix::insert("insert `=`", self.previous_end(), " ="))
+      }
+      //`(1 + 2`
+      Some(")") => Some(Fix::insert("insert `)`", self.previous_end(), ")")),
+      _ => None,
+    };
+    self.error(msg, fix);
+  }
+
+  pub(crate) fn parse(mut self) -> Parse {
+    let mut children = vec![];
+    let mut errors = vec![];
+    while let Some(step) = self.step() {
+      children.extend(step.elements);
+      errors.extend(step.errors);
+    }
+    Parse::new(Rc::new(GreenNode::new(SyntaxKind::ROOT, children)), errors)
+  }
+
+  //continues the top level loop at another token, the incremental parser skips the statements it reuses
+  pub(crate) fn seek(&mut self, token: usize) {
+    self.current_token = token;
+  }
+
+  pub(crate) fn position(&self) -> usize {
+    self.current_token
+  }
+
+  //one round of the top level loop: the trivia in front of a statement and the statement,
+  //or the trivia at the end of the file
+  pub(crate) fn step(&mut self) -> Option<Step> {
+    if self.current_token >= self.tokens.len() {
+      return None;
+    }
+    self.builder.start_node(SyntaxKind::ROOT);
+    self.eat_trivia();
+    if self.current_token < self.tokens.len() {
+      self.parse_statement();
+    }
+    self.builder.finish_node();
+    let root = std::mem::take(&mut self.builder).finish();
+    Some(Step {
+      elements: root.children().to_vec(),
+      errors: std::mem::take(&mut self.errors),
+    })
+  }
+
+  fn parse_statement(&mut self) {
+    if self.peek_is(TokenT::VAR, None) {
+      self.builder.start_node(SyntaxKind::ASSIGN_STMT);
+      self.bump();
+      self.expect(TokenT::IDENTIFIER, None);
+      self.expect(TokenT::OPERATOR, Some("="));
+      self.parse_expression();
+    } else {
+      self.builder.start_node(SyntaxKind::EXPR_STMT);
+      self.parse_expression();
+    }
+    self.builder.finish_node();
+  }
+
+  fn peek_operator(&self, precedence: i32) -> bool {
+    match self.peek() {
+      Some(token) => token.token_t == TokenT::OPERATOR && get_precedence(&token.value) == precedence,
+      None => false,
+    }
+  }
+
+  fn parse_expression(&mut self) {
+    self.eat_trivia();
+    let checkpoint = self.builder.checkpoint();
+    self.parse_high_precedence_expr();
+    let mut height = self.height;
+    while self.peek_operator(1) {
+      self.builder.start_node_at(checkpoint, SyntaxKind::BINARY_EXPR);
+      self.bump();
+      self.parse_high_precedence_expr();
+      self.builder.finish_node();
+      height = height.max(self.height) + 1;
+      if self.too_long(height) {
+        break;
+      }
+    }
+    self.height = height;
+  }
+
+  fn parse_high_precedence_expr(&mut self) {
+    self.eat_trivia();
+    let checkpoint = self.builder.checkpoint();
+    self.next_expression();
+    let mut height = self.height;
+    while self.peek_operator(2) {
+      self.builder.start_node_at(checkpoint, SyntaxKind::BINARY_EXPR);
+      self.bump();
+      self.next_expression();
+      self.builder.finish_node();
+      height = height.max(self.height) + 1;
+      if self.too_long(height) {
+        break;
+      }
+    }
+    self.height = height;
+  }
+
+  //a chain that grew too long ends in one error, the rest of it is kept flat
+  fn too_long(&mut self, height: usize) -> bool {
+    if height <= DEFAULT_MAX_CHAIN {
+      return false;
+    }
+    self.error(format!("Expression chains more than {} binary operators", DEFAULT_MAX_CHAIN), None);
+    self.builder.start_node(SyntaxKind::ERROR);
+    while self.peek_operator(1) || self.peek_operator(2) {
+      self.bump();
+      self.skip_operand();
+    }
+    self.builder.finish_node();
+    true
+  }
+
+  fn next_expression(&mut self) {
+    if self.depth >= DEFAULT_MAX_DEPTH {
+      self.error(format!("Expression is nested deeper than {} levels", DEFAULT_MAX_DEPTH), None);
+      self.builder.start_node(SyntaxKind::ERROR);
+      self.skip_operand();
+      self.builder.finish_node();
+      self.height = 0;
+      return;
+    }
+    self.depth += 1;
+    self.height = 0;
+    self.nested_expression();
+    self.depth -= 1;
+  }
+
+  //everything up to the end of the operand, without recursing: unary operators and what is inside parentheses
+  //up to the first atom outside of them, a `)` that closes an outer parenthesis or the next statement
+  fn skip_operand(&mut self) {
+    let mut open = 0;
+    loop {
+      //starts_call looks at the token right behind the current one
+      self.eat_trivia();
+      let Some(token) = self.peek() else {
+        break;
+      };
+      let is_bracket = |value: &str| token.token_t == TokenT::BRACKET && token.value == value;
+      if token.token_t == TokenT::VAR || (open == 0 && is_bracket(")")) {
+        break;
+      }
+      let atom = open == 0 && matches!(token.token_t, TokenT::INTEGER | TokenT::FLOAT | TokenT::IDENTIFIER);
+      if is_bracket("(") {
+        open += 1;
+      } else if is_bracket(")") {
+        open -= 1;
+      }
+      let call = token.token_t == TokenT::IDENTIFIER && self.starts_call();
+      self.bump();
+      if (atom && !call) || (open == 0 && is_bracket(")")) {
+        break;
+      }
+    }
+  }
+
+  fn nested_expression(&mut self) {
+    self.eat_trivia();
+    let token = match self.peek() {
+      Some(token) => token,
+      None => {
+        self.error("Unexpected end of token stream".to_string(), None);
+        self.builder.start_node(SyntaxKind::ERROR);
+        self.builder.finish_node();
+        return;
+      }
+    };
+    match token.token_t {
+      TokenT::INTEGER | TokenT::FLOAT => {
+        self.builder.start_node(SyntaxKind::LITERAL);
+        self.bump();
+      }
+      TokenT::IDENTIFIER if self.starts_call() => {
+        self.builder.start_node(SyntaxKind::CALL_EXPR);
+        self.bump();
+        self.bump();
+        let mut height = 0;
+        if !self.peek_is(TokenT::BRACKET, Some(")")) {
+          self.parse_expression();
+          height = self.height;
+          while self.peek_is(TokenT::COMMA, None) {
+            self.bump();
+            self.parse_expression();
+            height = height.max(self.height);
+          }
+        }
+        self.expect(TokenT::BRACKET, Some(")"));
+        self.height = height;
+      }
+      TokenT::IDENTIFIER => {
+        self.builder.start_node(SyntaxKind::VARIABLE);
+        self.bump();
+      }
+      TokenT::OPERATOR if UnOp::from_symbol(&token.value).is_some() => {
+        self.builder.start_node(SyntaxKind::UNARY_EXPR);
+        self.bump();
+        self.next_expression();
+      }
+      TokenT::BRACKET if token.value == "(" => {
+        self.builder.start_node(SyntaxKind::PAREN_EXPR);
+        self.bump();
+        self.parse_expression();
+        self.expect(TokenT::BRACKET, Some(")"));
+      }
+      _ => {
+        self.error(format!("Expected an expression but got: {:?}", token.value), None);
+        self.builder.start_node(SyntaxKind::ERROR);
+        //a keyword starts the next statement so it is left in place for recovery
+        if token.token_t != TokenT::VAR {
+          self.bump();
+        }
+      }
+    }
+    self.builder.finish_node();
+  }
+}
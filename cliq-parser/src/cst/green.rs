@@ -0,0 +1,138 @@
+use std::rc::Rc;
+
+use super::syntax_kind::SyntaxKind;
+
+//green elements are immutable, position independent and can be shared between trees
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+  kind: SyntaxKind,
+  text: String,
+}
+
+impl GreenToken {
+  pub fn new(kind: SyntaxKind, text: &str) -> Self {
+    Self {
+      kind,
+      text: text.to_string(),
+    }
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.kind
+  }
+
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  pub fn width(&self) -> usize {
+    self.text.len()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+  Node(Rc<GreenNode>),
+  Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+  pub fn kind(&self) -> SyntaxKind {
+    match self {
+      GreenElement::Node(node) => node.kind(),
+      GreenElement::Token(token) => token.kind(),
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    match self {
+      GreenElement::Node(node) => node.width(),
+      GreenElement::Token(token) => token.width(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+  kind: SyntaxKind,
+  width: usize,
+  children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+  pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+    let width = children.iter().map(|child| child.width()).sum();
+    Self { kind, width, children }
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.kind
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn children(&self) -> &[GreenElement] {
+    &self.children
+  }
+
+  pub fn write_text(&self, out: &mut String) {
+    crate::stack::grow(|| {
+      for child in self.children.iter() {
+        match child {
+          GreenElement::Node(node) => node.write_text(out),
+          GreenElement::Token(token) => out.push_str(token.text()),
+        }
+      }
+    })
+  }
+}
+
+//builds green trees bottom up, nodes can be started retroactively from a checkpoint
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+  parents: Vec<(SyntaxKind, usize)>,
+  children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint(usize);
+
+impl GreenNodeBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+    self.children.push(GreenElement::Token(Rc::new(GreenToken::new(kind, text))));
+  }
+
+  pub fn start_node(&mut self, kind: SyntaxKind) {
+    self.parents.push((kind, self.children.len()));
+  }
+
+  pub fn checkpoint(&self) -> Checkpoint {
+    Checkpoint(self.children.len())
+  }
+
+  //wraps everything added since the checkpoint into the new node
+  pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+    self.parents.push((kind, checkpoint.0));
+  }
+
+  pub fn finish_node(&mut self) {
+    let (kind, first_child) = self.parents.pop().expect("finish_node without start_node");
+    let children = self.children.split_off(first_child);
+    self.children.push(GreenElement::Node(Rc::new(GreenNode::new(kind, children))));
+  }
+
+  pub fn finish(mut self) -> Rc<GreenNode> {
+    assert!(self.parents.is_empty(), "unfinished nodes left in builder");
+    match self.children.pop() {
+      Some(GreenElement::Node(node)) if self.children.is_empty() => node,
+      _ => panic!("builder must produce exactly one root node"),
+    }
+  }
+}
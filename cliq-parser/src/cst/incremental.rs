@@ -0,0 +1,310 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use cliq_lexer::{lexer::Lexer, token::Token, token_t::TokenT};
+
+use super::grammar::Grammar;
+use super::green::{GreenElement, GreenNode};
+use super::syntax_kind::SyntaxKind;
+use super::{Parse, SyntaxError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+  //byte range of the text before the edit
+  pub range: Range<usize>,
+  pub replacement: String,
+}
+
+impl TextEdit {
+  pub fn new(range: Range<usize>, replacement: impl Into<String>) -> Self {
+    Self {
+      range,
+      replacement: replacement.into(),
+    }
+  }
+
+  //panics like String::replace_range when the range is out of bounds or splits a character
+  pub fn apply(&self, src: &str) -> String {
+    let mut out = src.to_string();
+    out.replace_range(self.range.clone(), &self.replacement);
+    out
+  }
+}
+
+//one round of the grammar's top level loop, a statement and the trivia in front of it
+#[derive(Debug, Clone)]
+struct Step {
+  elements: Vec<GreenElement>,
+  //ranges are relative to the start of the step so a reused step needs no fixing up
+  errors: Vec<SyntaxError>,
+  tokens: usize,
+  width: usize,
+  //counted from the start of the step, the first significant token after it decided where it ended
+  lookahead: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Lexed {
+  tokens: Vec<Token>,
+  steps: Vec<Step>,
+  parse: Parse,
+}
+
+//a parse that is kept up to date with edits instead of being redone from scratch:
+//only the lines around an edit are lexed again and only the statements near it are parsed again,
+//the green nodes of all other statements are shared with the previous tree
+#[derive(Debug, Clone)]
+pub struct IncrementalParse {
+  src: String,
+  lexed: Result<Lexed, String>,
+}
+
+impl IncrementalParse {
+  pub fn new(src: impl Into<String>) -> Self {
+    let src = src.into();
+    let lexed = lex(&src);
+    Self { src, lexed }
+  }
+
+  pub fn src(&self) -> &str {
+    &self.src
+  }
+
+  //the lexer error when the current text does not lex
+  pub fn parse(&self) -> Result<&Parse, &str> {
+    match &self.lexed {
+      Ok(lexed) => Ok(&lexed.parse),
+      Err(msg) => Err(msg),
+    }
+  }
+
+  pub fn tokens(&self) -> Result<&[Token], &str> {
+    match &self.lexed {
+      Ok(lexed) => Ok(&lexed.tokens),
+      Err(msg) => Err(msg),
+    }
+  }
+
+  pub fn edit(&mut self, edit: &TextEdit) {
+    let src = edit.apply(&self.src);
+    self.lexed = match &self.lexed {
+      Ok(lexed) => reparse(lexed, &self.src, &src, edit),
+      //without tokens there is nothing to reuse
+      Err(_) => lex(&src),
+    };
+    self.src = src;
+  }
+}
+
+fn lex(src: &str) -> Result<Lexed, String> {
+  let tokens = Lexer::new().lex(src)?;
+  let mut grammar = Grammar::new(src, &tokens);
+  let mut steps = vec![];
+  while let Some(step) = next_step(&mut grammar, &tokens) {
+    steps.push(step);
+  }
+  let parse = assemble(&steps);
+  Ok(Lexed { tokens, steps, parse })
+}
+
+fn next_step(grammar: &mut Grammar<'_>, tokens: &[Token]) -> Option<Step> {
+  let start = grammar.position();
+  let step = grammar.step()?;
+  let end = grammar.position();
+  let offset = tokens[start].span.start;
+  let lookahead = (end..tokens.len())
+    .find(|&i| !SyntaxKind::from(tokens[i].token_t).is_trivia())
+    .unwrap_or(tokens.len());
+  Some(Step {
+    width: step.elements.iter().map(GreenElement::width).sum(),
+    errors: step.errors.iter().map(|err| err.map_offsets(|i| i - offset)).collect(),
+    elements: step.elements,
+    tokens: end - start,
+    lookahead: lookahead - start,
+  })
+}
+
+fn assemble(steps: &[Step]) -> Parse {
+  let mut children = vec![];
+  let mut errors = vec![];
+  let mut offset = 0;
+  for step in steps {
+    children.extend(step.elements.iter().cloned());
+    errors.extend(step.errors.iter().map(|err| err.map_offsets(|i| i + offset)));
+    offset += step.width;
+  }
+  Parse::new(Rc::new(GreenNode::new(SyntaxKind::ROOT, children)), errors)
+}
+
+//the first token of a line: no token can span the line break in front of it, comments included
+fn starts_line(tokens: &[Token], i: usize) -> bool {
+  i > 0 && tokens[i - 1].token_t == TokenT::WHITESPACE && tokens[i - 1].value.contains('\n')
+}
+
+//the text matches, the position may have moved
+fn same(new: &Token, old: &Token) -> bool {
+  new.token_t == old.token_t && new.value == old.value
+}
+
+fn reparse(old: &Lexed, old_src: &str, src: &str, edit: &TextEdit) -> Result<Lexed, String> {
+  let delta = src.len() as isize - old_src.len() as isize;
+  let shift = |offset: usize| (offset as isize + delta) as usize;
+
+  //relex whole lines, from the last line start in front of the edit to the first one behind it
+  let before = old.tokens.partition_point(|token| token.span.start < edit.range.start);
+  let first = (1..before).rev().find(|&i| starts_line(&old.tokens, i)).unwrap_or(0);
+  let after = old.tokens.partition_point(|token| token.span.start < edit.range.end);
+  let last = (after + 1..old.tokens.len())
+    .find(|&i| starts_line(&old.tokens, i))
+    .unwrap_or(old.tokens.len());
+  let start = old.tokens.get(first).map_or(0, |token| token.span.start);
+  let old_end = old.tokens.get(last).map_or(old_src.len(), |token| token.span.start);
+  let end = shift(old_end);
+
+  let prefix = &src[..start];
+  let line = prefix.matches('\n').count() + 1;
+  let col = prefix[prefix.rfind('\n').map_or(0, |i| i + 1)..].chars().count() + 1;
+  let relexed = Lexer::at(line, col, start).lex(&src[start..end])?;
+  let lines = src[start..end].matches('\n').count() as isize - old_src[start..old_end].matches('\n').count() as isize;
+
+  let mut tokens = old.tokens[..first].to_vec();
+  tokens.extend(relexed);
+  let reused_from = tokens.len();
+  tokens.extend(old.tokens[last..].iter().map(|token| {
+    let mut token = token.clone();
+    token.span.start = shift(token.span.start);
+    token.span.end = shift(token.span.end);
+    token.codepos.line = (token.codepos.line as isize + lines) as usize;
+    token
+  }));
+  let moved = reused_from as isize - last as isize;
+
+  //most relexed tokens are the same as before, only the ones between these two really changed
+  let window = &tokens[first..reused_from];
+  let old_window = &old.tokens[first..last];
+  let changed_start = first + window.iter().zip(old_window).take_while(|(new, old)| same(new, old)).count();
+  let changed_end = reused_from
+    - window
+      .iter()
+      .rev()
+      .zip(old_window.iter().rev())
+      .take_while(|(new, old)| same(new, old))
+      .count();
+
+  //statements whose lookahead lies in front of the first changed token parse the same way again
+  let mut steps = vec![];
+  let mut position = 0;
+  for step in old.steps.iter() {
+    if position + step.lookahead >= changed_start {
+      break;
+    }
+    position += step.tokens;
+    steps.push(step.clone());
+  }
+
+  //token indices at which an old step started, once the new parse lines up with one of them
+  //behind the last changed token, everything from there on parses as before
+  let boundaries: Vec<usize> = old
+    .steps
+    .iter()
+    .scan(0, |position, step| {
+      *position += step.tokens;
+      Some(*position)
+    })
+    .collect();
+  let mut grammar = Grammar::new(src, &tokens);
+  grammar.seek(position);
+  while let Some(step) = next_step(&mut grammar, &tokens) {
+    steps.push(step);
+    let end = grammar.position();
+    if end < changed_end {
+      continue;
+    }
+    let old_end = (end as isize - moved) as usize;
+    if let Ok(i) = boundaries.binary_search(&old_end) {
+      steps.extend(old.steps[i + 1..].iter().cloned());
+      break;
+    }
+  }
+
+  let parse = assemble(&steps);
+  Ok(Lexed { tokens, steps, parse })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cst;
+
+  //the incremental result must be exactly what parsing the new text from scratch gives
+  fn assert_matches_full(incremental: &IncrementalParse) {
+    let src = incremental.src();
+    match (Lexer::new().lex(src), incremental.tokens()) {
+      (Ok(expected), Ok(tokens)) => {
+        assert_eq!(tokens, expected.as_slice(), "tokens of {:?}", src);
+        let full = cst::parse_tokens(src, &expected);
+        let parse = incremental.parse().unwrap();
+        assert_eq!(parse.green(), full.green(), "tree of {:?}", src);
+        assert_eq!(parse.errors(), full.errors(), "errors of {:?}", src);
+      }
+      (Err(expected), Err(msg)) => assert_eq!(msg, expected),
+      (expected, actual) => panic!("{:?}: expected {:?} but got {:?}", src, expected, actual),
+    }
+  }
+
+  fn edit(parse: &mut IncrementalParse, find: &str, replacement: &str) {
+    let start = parse.src().find(find).unwrap();
+    parse.edit(&TextEdit::new(start..start + find.len(), replacement));
+    assert_matches_full(parse);
+  }
+
+  fn statements(parse: &IncrementalParse) -> Vec<Rc<GreenNode>> {
+    let root = parse.parse().unwrap().green().clone();
+    root
+      .children()
+      .iter()
+      .filter_map(|child| match child {
+        GreenElement::Node(node) => Some(node.clone()),
+        GreenElement::Token(_) => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_unchanged_statements_are_shared() {
+    let mut parse = IncrementalParse::new("var a = 1\nvar b = a * 2 // two\nvar c = b\n\nc + a\n");
+    let before = statements(&parse);
+    edit(&mut parse, "2", "(3 + 4)");
+    let after = statements(&parse);
+    assert_eq!(after.len(), 4);
+    assert!(Rc::ptr_eq(&before[0], &after[0]));
+    assert!(!Rc::ptr_eq(&before[1], &after[1]));
+    assert!(Rc::ptr_eq(&before[2], &after[2]));
+    assert!(Rc::ptr_eq(&before[3], &after[3]));
+  }
+
+  #[test]
+  fn test_edits_that_regroup_statements() {
+    let mut parse = IncrementalParse::new("var a = 1\nb\nc\n");
+    //`b\n-c` is a single statement
+    edit(&mut parse, "c", "-c");
+    assert_eq!(statements(&parse).len(), 2);
+    edit(&mut parse, "\nb", "");
+    edit(&mut parse, "var a", "var c");
+    edit(&mut parse, "c = 1", "c = 1 // c\n(");
+    assert!(!parse.parse().unwrap().errors().is_empty());
+    edit(&mut parse, "-c\n", "-c)\n");
+    assert!(parse.parse().unwrap().errors().is_empty());
+  }
+
+  #[test]
+  fn test_lexer_errors() {
+    let mut parse = IncrementalParse::new("var a = 1\na\n");
+    edit(&mut parse, "= 1", "= $1");
+    assert_eq!(parse.parse().unwrap_err(), "Unexpected character at line 1, column 9");
+    edit(&mut parse, "$", "");
+    edit(&mut parse, "a\n", "a // $\n");
+    edit(&mut parse, "1\n", "");
+    edit(&mut parse, "", "\n\n");
+  }
+}
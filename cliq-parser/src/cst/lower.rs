@@ -0,0 +1,114 @@
+use crate::{
+  expression::{
+    call_expression::CallExpression,
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+
+use super::red::{SyntaxNode, SyntaxToken};
+use super::syntax_kind::SyntaxKind;
+use super::SyntaxError;
+
+fn error(node: &SyntaxNode, msg: &str) -> SyntaxError {
+  SyntaxError::new(msg, node.text_range())
+}
+
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Result<SyntaxToken, SyntaxError> {
+  node
+    .significant_tokens()
+    .into_iter()
+    .find(|token| token.kind() == kind)
+    .ok_or_else(|| error(node, &format!("Missing {:?} in {:?}", kind, node.kind())))
+}
+
+//converts a ROOT node into the statements the Parser would have produced
+pub fn lower(root: &SyntaxNode) -> Result<Vec<Statement>, SyntaxError> {
+  root.children().iter().map(lower_statement).collect()
+}
+
+pub fn lower_statement(node: &SyntaxNode) -> Result<Statement, SyntaxError> {
+  match node.kind() {
+    SyntaxKind::ASSIGN_STMT => {
+      let name = token(node, SyntaxKind::IDENTIFIER)?;
+      let value = node
+        .children()
+        .first()
+        .ok_or_else(|| error(node, "Missing assigned value"))?
+        .clone();
+      let variable = VariableExpression::expression(name.text().to_string());
+      Ok(AssignStmt::statement(variable, lower_expression(&value)?, false))
+    }
+    SyntaxKind::EXPR_STMT => {
+      let expr = node.children().first().ok_or_else(|| error(node, "Missing expression"))?.clone();
+      Ok(Statement::Expression(lower_expression(&expr)?))
+    }
+    kind => Err(error(node, &format!("Expected a statement but got: {:?}", kind))),
+  }
+}
+
+pub fn lower_expression(node: &SyntaxNode) -> Result<Expression, SyntaxError> {
+  crate::stack::grow(|| match node.kind() {
+    SyntaxKind::LITERAL => {
+      let literal = node
+        .significant_tokens()
+        .into_iter()
+        .next()
+        .ok_or_else(|| error(node, "Empty literal"))?;
+      match literal.kind() {
+        SyntaxKind::INTEGER => {
+          let val = literal
+            .text()
+            .parse::<i32>()
+            .map_err(|_| error(node, "Integer literal out of range"))?;
+          Ok(ValueExpression::int_value(val))
+        }
+        _ => {
+          let text = literal.text();
+          let text = if text.starts_with('.') {
+            format!("0{}", text)
+          } else {
+            text.to_string()
+          };
+          let val = text.parse::<f32>().map_err(|_| error(node, "Invalid float literal"))?;
+          Ok(ValueExpression::float_value(val))
+        }
+      }
+    }
+    SyntaxKind::VARIABLE => {
+      let name = token(node, SyntaxKind::IDENTIFIER)?;
+      Ok(VariableExpression::expression(name.text().to_string()))
+    }
+    SyntaxKind::CALL_EXPR => {
+      let name = token(node, SyntaxKind::IDENTIFIER)?;
+      let args = node.children().iter().map(lower_expression).collect::<Result<_, _>>()?;
+      Ok(CallExpression::expression(name.text().to_string(), args))
+    }
+    SyntaxKind::PAREN_EXPR => {
+      let inner = node.children().first().ok_or_else(|| error(node, "Empty parentheses"))?.clone();
+      lower_expression(&inner)
+    }
+    SyntaxKind::BINARY_EXPR => {
+      let children = node.children();
+      let (lhs, rhs) = match children.as_slice() {
+        [lhs, rhs] => (lower_expression(lhs)?, lower_expression(rhs)?),
+        _ => return Err(error(node, "Binary expression needs two operands")),
+      };
+      let operator = token(node, SyntaxKind::OPERATOR)?;
+      match BinOp::from_symbol(operator.text()) {
+        Some(op) => Ok(Expression::binary(op, lhs, rhs)),
+        None => Err(error(node, &format!("Unsupported operator: {:?}", operator.text()))),
+      }
+    }
+    SyntaxKind::UNARY_EXPR => {
+      let operator = token(node, SyntaxKind::OPERATOR)?;
+      let op = UnOp::from_symbol(operator.text()).ok_or_else(|| error(node, &format!("Unsupported operator: {:?}", operator.text())))?;
+      let operand = node.children().first().ok_or_else(|| error(node, "Missing operand"))?.clone();
+      Ok(Expression::unary(op, lower_expression(&operand)?))
+    }
+    kind => Err(error(node, &format!("Expected an expression but got: {:?}", kind))),
+  })
+}
@@ -0,0 +1,181 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::green::{GreenElement, GreenNode, GreenToken};
+use super::syntax_kind::SyntaxKind;
+
+//red nodes are cheap cursors over the green tree that know their parent and absolute offset
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Debug)]
+struct NodeData {
+  green: Rc<GreenNode>,
+  parent: Option<SyntaxNode>,
+  offset: usize,
+}
+
+impl PartialEq for SyntaxNode {
+  fn eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+  }
+}
+
+impl Eq for SyntaxNode {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+  green: Rc<GreenToken>,
+  parent: SyntaxNode,
+  offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+  Node(SyntaxNode),
+  Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+  pub fn new_root(green: Rc<GreenNode>) -> Self {
+    SyntaxNode(Rc::new(NodeData {
+      green,
+      parent: None,
+      offset: 0,
+    }))
+  }
+
+  pub fn green(&self) -> &Rc<GreenNode> {
+    &self.0.green
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.0.green.kind()
+  }
+
+  pub fn parent(&self) -> Option<&SyntaxNode> {
+    self.0.parent.as_ref()
+  }
+
+  pub fn text_range(&self) -> Range<usize> {
+    self.0.offset..self.0.offset + self.0.green.width()
+  }
+
+  pub fn text(&self) -> String {
+    let mut out = String::new();
+    self.0.green.write_text(&mut out);
+    out
+  }
+
+  pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+    let mut offset = self.0.offset;
+    let mut elements = vec![];
+    for child in self.0.green.children() {
+      elements.push(match child {
+        GreenElement::Node(node) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+          green: node.clone(),
+          parent: Some(self.clone()),
+          offset,
+        }))),
+        GreenElement::Token(token) => SyntaxElement::Token(SyntaxToken {
+          green: token.clone(),
+          parent: self.clone(),
+          offset,
+        }),
+      });
+      offset += child.width();
+    }
+    elements
+  }
+
+  pub fn children(&self) -> Vec<SyntaxNode> {
+    self
+      .children_with_tokens()
+      .into_iter()
+      .filter_map(|element| match element {
+        SyntaxElement::Node(node) => Some(node),
+        SyntaxElement::Token(_) => None,
+      })
+      .collect()
+  }
+
+  //direct child tokens that are not whitespace or comments
+  pub fn significant_tokens(&self) -> Vec<SyntaxToken> {
+    self
+      .children_with_tokens()
+      .into_iter()
+      .filter_map(|element| match element {
+        SyntaxElement::Token(token) if !token.kind().is_trivia() => Some(token),
+        _ => None,
+      })
+      .collect()
+  }
+
+  //breadth first, starting with the node itself
+  pub fn descendants(&self) -> Vec<SyntaxNode> {
+    let mut nodes = vec![self.clone()];
+    let mut i = 0;
+    while i < nodes.len() {
+      let children = nodes[i].children();
+      nodes.extend(children);
+      i += 1;
+    }
+    nodes
+  }
+
+  //all tokens below this node in source order, without recursing so long chains don't overflow the stack
+  pub fn tokens(&self) -> Vec<SyntaxToken> {
+    let mut tokens = vec![];
+    let mut pending: Vec<_> = self.children_with_tokens().into_iter().rev().collect();
+    while let Some(element) = pending.pop() {
+      match element {
+        SyntaxElement::Node(node) => pending.extend(node.children_with_tokens().into_iter().rev()),
+        SyntaxElement::Token(token) => tokens.push(token),
+      }
+    }
+    tokens
+  }
+
+  pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+    self.tokens().into_iter().find(|token| token.text_range().contains(&offset))
+  }
+}
+
+impl SyntaxToken {
+  pub fn green(&self) -> &Rc<GreenToken> {
+    &self.green
+  }
+
+  pub fn kind(&self) -> SyntaxKind {
+    self.green.kind()
+  }
+
+  pub fn text(&self) -> &str {
+    self.green.text()
+  }
+
+  pub fn parent(&self) -> &SyntaxNode {
+    &self.parent
+  }
+
+  pub fn text_range(&self) -> Range<usize> {
+    self.offset..self.offset + self.green.width()
+  }
+}
+
+impl SyntaxElement {
+  pub fn kind(&self) -> SyntaxKind {
+    match self {
+      SyntaxElement::Node(node) => node.kind(),
+      SyntaxElement::Token(token) => token.kind(),
+    }
+  }
+
+  pub fn text_range(&self) -> Range<usize> {
+    match self {
+      SyntaxElement::Node(node) => node.text_range(),
+      SyntaxElement::Token(token) => token.text_range(),
+    }
+  }
+}
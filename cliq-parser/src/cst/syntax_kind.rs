@@ -0,0 +1,50 @@
+use cliq_lexer::token_t::TokenT;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+  //TOKENS
+  WHITESPACE,
+  COMMENT,
+  INTEGER,
+  FLOAT,
+  IDENTIFIER,
+  OPERATOR,
+  BRACKET,
+  COMMA,
+  VAR_KW,
+
+  //NODES
+  ROOT,
+  ASSIGN_STMT,
+  EXPR_STMT,
+  BINARY_EXPR,
+  UNARY_EXPR,
+  PAREN_EXPR,
+  CALL_EXPR,
+  LITERAL,
+  VARIABLE,
+  ERROR, // tokens the grammar could not place
+}
+
+impl SyntaxKind {
+  pub fn is_trivia(self) -> bool {
+    matches!(self, SyntaxKind::WHITESPACE | SyntaxKind::COMMENT)
+  }
+}
+
+impl From<TokenT> for SyntaxKind {
+  fn from(token_t: TokenT) -> Self {
+    match token_t {
+      TokenT::WHITESPACE => SyntaxKind::WHITESPACE,
+      TokenT::COMMENT => SyntaxKind::COMMENT,
+      TokenT::INTEGER => SyntaxKind::INTEGER,
+      TokenT::FLOAT => SyntaxKind::FLOAT,
+      TokenT::IDENTIFIER => SyntaxKind::IDENTIFIER,
+      TokenT::OPERATOR => SyntaxKind::OPERATOR,
+      TokenT::BRACKET => SyntaxKind::BRACKET,
+      TokenT::COMMA => SyntaxKind::COMMA,
+      TokenT::VAR => SyntaxKind::VAR_KW,
+    }
+  }
+}
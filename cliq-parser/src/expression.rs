@@ -1,12 +1,198 @@
-use serde::Serialize;
+use std::fmt;
 
-pub mod binary_expression;
+use self::operator::{BinOp, UnOp};
+use crate::stack;
+
+pub mod call_expression;
+pub mod operator;
+mod serde_compat;
 pub mod value_expression;
 pub mod variable_expression;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+//serialized in the shape of the former per operator structs, see serde_compat
+#[derive(Debug)]
 pub enum Expression {
   ValueExpression(value_expression::ValueExpression),
-  BinaryExpression(binary_expression::BinaryExpr),
   VariableExpression(variable_expression::VariableExpression),
+  Binary {
+    op: BinOp,
+    lhs: Box<Expression>,
+    rhs: Box<Expression>,
+  },
+  Unary {
+    op: UnOp,
+    operand: Box<Expression>,
+  },
+  Call(call_expression::CallExpression),
+}
+
+impl Expression {
+  pub fn binary(op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+    Expression::Binary {
+      op,
+      lhs: Box::new(lhs),
+      rhs: Box::new(rhs),
+    }
+  }
+
+  pub fn unary(op: UnOp, operand: Expression) -> Expression {
+    Expression::Unary {
+      op,
+      operand: Box::new(operand),
+    }
+  }
+
+  //binding strength used to decide where parentheses are needed when printing
+  pub fn precedence(&self) -> i32 {
+    match self {
+      Expression::Binary { op, .. } => op.precedence(),
+      Expression::Unary { op, .. } => op.precedence(),
+      _ => i32::MAX,
+    }
+  }
+
+  //true when printing starts with a unary operator, which would continue a preceding statement
+  pub fn starts_with_unary(&self) -> bool {
+    match self {
+      Expression::Unary { .. } => true,
+      //constants produced by folding can be negative
+      Expression::ValueExpression(value) => value.to_string().starts_with('-'),
+      Expression::Binary { op, lhs, .. } => {
+        !op.needs_parentheses(lhs.precedence(), false) && stack::grow(|| lhs.starts_with_unary())
+      }
+      _ => false,
+    }
+  }
+
+  pub fn to_source(&self) -> String {
+    self.to_string()
+  }
+
+  //moves the expression out and leaves a literal behind, Expression implements Drop so it cannot be destructured
+  pub fn take(&mut self) -> Expression {
+    std::mem::replace(self, value_expression::ValueExpression::int_value(0))
+  }
+}
+
+//clone, compare and print on a growing stack, see stack::grow
+impl Clone for Expression {
+  fn clone(&self) -> Self {
+    stack::grow(|| match self {
+      Expression::ValueExpression(expr) => Expression::ValueExpression(expr.clone()),
+      Expression::VariableExpression(expr) => Expression::VariableExpression(expr.clone()),
+      Expression::Binary { op, lhs, rhs } => Expression::Binary {
+        op: *op,
+        lhs: lhs.clone(),
+        rhs: rhs.clone(),
+      },
+      Expression::Unary { op, operand } => Expression::Unary {
+        op: *op,
+        operand: operand.clone(),
+      },
+      Expression::Call(expr) => Expression::Call(expr.clone()),
+    })
+  }
+}
+
+impl PartialEq for Expression {
+  fn eq(&self, other: &Self) -> bool {
+    stack::grow(|| match (self, other) {
+      (Expression::ValueExpression(a), Expression::ValueExpression(b)) => a == b,
+      (Expression::VariableExpression(a), Expression::VariableExpression(b)) => a == b,
+      (Expression::Binary { op, lhs, rhs }, Expression::Binary { op: other_op, lhs: other_lhs, rhs: other_rhs }) => {
+        op == other_op && lhs == other_lhs && rhs == other_rhs
+      }
+      (Expression::Unary { op, operand }, Expression::Unary { op: other_op, operand: other_operand }) => {
+        op == other_op && operand == other_operand
+      }
+      (Expression::Call(a), Expression::Call(b)) => a == b,
+      _ => false,
+    })
+  }
+}
+
+impl fmt::Display for Expression {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    stack::grow(|| match self {
+      Expression::ValueExpression(expr) => write!(f, "{}", expr),
+      Expression::VariableExpression(expr) => write!(f, "{}", expr),
+      Expression::Call(expr) => write!(f, "{}", expr),
+      Expression::Binary { op, lhs, rhs } => {
+        if op.needs_parentheses(lhs.precedence(), false) {
+          write!(f, "({})", lhs)?;
+        } else {
+          write!(f, "{}", lhs)?;
+        }
+        write!(f, " {} ", op.symbol())?;
+        if op.needs_parentheses(rhs.precedence(), true) {
+          write!(f, "({})", rhs)
+        } else {
+          write!(f, "{}", rhs)
+        }
+      }
+      Expression::Unary { op, operand } => {
+        if operand.precedence() < op.precedence() {
+          write!(f, "{}({})", op.symbol(), operand)
+        } else {
+          write!(f, "{}{}", op.symbol(), operand)
+        }
+      }
+    })
+  }
+}
+
+//long chains like `a + b + ... + z` nest on the left, dropping them recursively takes one stack frame per operator
+//so the children are moved to a list instead; leaves have nothing to drop recursively and stay where they are
+impl Drop for Expression {
+  fn drop(&mut self) {
+    let mut pending = vec![];
+    take_nested(self, &mut pending);
+    while let Some(mut expr) = pending.pop() {
+      take_nested(&mut expr, &mut pending);
+    }
+  }
+}
+
+fn take_nested(expr: &mut Expression, pending: &mut Vec<Expression>) {
+  let mut take = |child: &mut Expression| {
+    if matches!(child, Expression::Binary { .. } | Expression::Unary { .. } | Expression::Call(_)) {
+      pending.push(child.take());
+    }
+  };
+  match expr {
+    Expression::Binary { lhs, rhs, .. } => {
+      take(lhs);
+      take(rhs);
+    }
+    Expression::Unary { operand, .. } => take(operand),
+    Expression::Call(call) => call.args.iter_mut().for_each(take),
+    Expression::ValueExpression(_) | Expression::VariableExpression(_) => {}
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::variable_expression::VariableExpression;
+  use super::*;
+
+  //a chain as deep as a few megabytes of source, built without the parser
+  pub(crate) fn chain(depth: usize) -> Expression {
+    let mut expr = VariableExpression::expression("a".to_string());
+    for i in 0..depth {
+      expr = if i % 2 == 0 {
+        Expression::binary(BinOp::Add, expr, value_expression::ValueExpression::int_value(1))
+      } else {
+        Expression::unary(UnOp::Neg, expr)
+      };
+    }
+    expr
+  }
+
+  #[test]
+  fn test_drop_deep() {
+    drop(chain(1_000_000));
+    let mut expr = chain(3);
+    assert_eq!(expr.take().to_string(), "-(a + 1) + 1");
+    assert_eq!(expr, value_expression::ValueExpression::int_value(0));
+  }
 }
@@ -0,0 +1,36 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::Expression;
+
+//a function the host provides, called with the arguments in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CallExpression {
+  pub name: String,
+  pub args: Vec<Expression>,
+}
+
+impl CallExpression {
+  pub fn new(name: String, args: Vec<Expression>) -> Self {
+    Self { name, args }
+  }
+
+  pub fn expression(name: String, args: Vec<Expression>) -> Expression {
+    Expression::Call(Self::new(name, args))
+  }
+}
+
+impl fmt::Display for CallExpression {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}(", self.name)?;
+    for (i, arg) in self.args.iter().enumerate() {
+      if i > 0 {
+        write!(f, ", ")?;
+      }
+      write!(f, "{}", arg)?;
+    }
+    write!(f, ")")
+  }
+}
@@ -0,0 +1,86 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Associativity {
+  Left,
+  Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum BinOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum UnOp {
+  Neg,
+}
+
+impl BinOp {
+  pub const ALL: [BinOp; 5] = [BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Rem];
+
+  pub fn from_symbol(symbol: &str) -> Option<BinOp> {
+    BinOp::ALL.into_iter().find(|op| op.symbol() == symbol)
+  }
+
+  pub fn symbol(self) -> &'static str {
+    match self {
+      BinOp::Add => "+",
+      BinOp::Sub => "-",
+      BinOp::Mul => "*",
+      BinOp::Div => "/",
+      BinOp::Rem => "%",
+    }
+  }
+
+  //higher binds tighter, unary operators bind tighter than every binary operator
+  pub fn precedence(self) -> i32 {
+    match self {
+      BinOp::Add | BinOp::Sub => 1,
+      BinOp::Mul | BinOp::Div | BinOp::Rem => 2,
+    }
+  }
+
+  pub fn associativity(self) -> Associativity {
+    Associativity::Left
+  }
+
+  //whether an operand with the given precedence needs parentheses on that side of this operator
+  pub fn needs_parentheses(self, operand_precedence: i32, is_rhs: bool) -> bool {
+    let precedence = self.precedence();
+    if operand_precedence != precedence {
+      return operand_precedence < precedence;
+    }
+    match self.associativity() {
+      Associativity::Left => is_rhs,
+      Associativity::Right => !is_rhs,
+    }
+  }
+}
+
+impl UnOp {
+  pub const ALL: [UnOp; 1] = [UnOp::Neg];
+
+  pub fn from_symbol(symbol: &str) -> Option<UnOp> {
+    UnOp::ALL.into_iter().find(|op| op.symbol() == symbol)
+  }
+
+  pub fn symbol(self) -> &'static str {
+    match self {
+      UnOp::Neg => "-",
+    }
+  }
+
+  pub fn precedence(self) -> i32 {
+    3
+  }
+
+  pub fn associativity(self) -> Associativity {
+    Associativity::Right
+  }
+}
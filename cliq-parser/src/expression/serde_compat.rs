@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::call_expression::CallExpression;
+use super::operator::{BinOp, UnOp};
+use super::value_expression::ValueExpression;
+use super::variable_expression::VariableExpression;
+use super::Expression;
+
+//keeps the json written before operators were collapsed into BinOp:
+//  {"BinaryExpression": {"AddOpr": {"lhs": ..., "rhs": ...}}}
+//unary operators follow the same pattern:
+//  {"UnaryExpression": {"NegOpr": {"operand": ...}}}
+//calls are new in version 3 and have no former shape:
+//  {"CallExpression": {"name": "max", "args": [...]}}
+//serialization borrows the tree so deep expressions are not cloned
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize)]
+enum ExpressionRef<'a> {
+  ValueExpression(&'a ValueExpression),
+  BinaryExpression(BinaryRef<'a>),
+  VariableExpression(&'a VariableExpression),
+  UnaryExpression(UnaryRef<'a>),
+  CallExpression(&'a CallExpression),
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize)]
+enum BinaryRef<'a> {
+  AddOpr(OperandsRef<'a>),
+  SubOpr(OperandsRef<'a>),
+  MulOpr(OperandsRef<'a>),
+  DivOpr(OperandsRef<'a>),
+  RemOpr(OperandsRef<'a>),
+}
+
+#[derive(Serialize)]
+struct OperandsRef<'a> {
+  lhs: &'a Expression,
+  rhs: &'a Expression,
+}
+
+#[derive(Serialize)]
+enum UnaryRef<'a> {
+  NegOpr(OperandRef<'a>),
+}
+
+#[derive(Serialize)]
+struct OperandRef<'a> {
+  operand: &'a Expression,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "Expression")]
+enum ExpressionDe {
+  ValueExpression(ValueExpression),
+  BinaryExpression(BinaryDe),
+  VariableExpression(VariableExpression),
+  UnaryExpression(UnaryDe),
+  CallExpression(CallExpression),
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "BinaryExpr")]
+enum BinaryDe {
+  AddOpr(Operands),
+  SubOpr(Operands),
+  MulOpr(Operands),
+  DivOpr(Operands),
+  RemOpr(Operands),
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct Operands {
+  lhs: Box<Expression>,
+  rhs: Box<Expression>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename = "UnaryExpr")]
+enum UnaryDe {
+  NegOpr(Operand),
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct Operand {
+  operand: Box<Expression>,
+}
+
+impl Serialize for Expression {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let compat = match self {
+      Expression::ValueExpression(expr) => ExpressionRef::ValueExpression(expr),
+      Expression::VariableExpression(expr) => ExpressionRef::VariableExpression(expr),
+      Expression::Call(expr) => ExpressionRef::CallExpression(expr),
+      Expression::Binary { op, lhs, rhs } => {
+        let operands = OperandsRef { lhs, rhs };
+        ExpressionRef::BinaryExpression(match op {
+          BinOp::Add => BinaryRef::AddOpr(operands),
+          BinOp::Sub => BinaryRef::SubOpr(operands),
+          BinOp::Mul => BinaryRef::MulOpr(operands),
+          BinOp::Div => BinaryRef::DivOpr(operands),
+          BinOp::Rem => BinaryRef::RemOpr(operands),
+        })
+      }
+      Expression::Unary { op, operand } => ExpressionRef::UnaryExpression(match op {
+        UnOp::Neg => UnaryRef::NegOpr(OperandRef { operand }),
+      }),
+    };
+    //a long chain nests one level per operator, see stack::grow
+    crate::stack::grow(|| compat.serialize(serializer))
+  }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Ok(match ExpressionDe::deserialize(deserializer)? {
+      ExpressionDe::ValueExpression(expr) => Expression::ValueExpression(expr),
+      ExpressionDe::VariableExpression(expr) => Expression::VariableExpression(expr),
+      ExpressionDe::CallExpression(expr) => Expression::Call(expr),
+      ExpressionDe::BinaryExpression(binary) => {
+        let (op, Operands { lhs, rhs }) = match binary {
+          BinaryDe::AddOpr(operands) => (BinOp::Add, operands),
+          BinaryDe::SubOpr(operands) => (BinOp::Sub, operands),
+          BinaryDe::MulOpr(operands) => (BinOp::Mul, operands),
+          BinaryDe::DivOpr(operands) => (BinOp::Div, operands),
+          BinaryDe::RemOpr(operands) => (BinOp::Rem, operands),
+        };
+        Expression::Binary { op, lhs, rhs }
+      }
+      ExpressionDe::UnaryExpression(UnaryDe::NegOpr(Operand { operand })) => Expression::Unary { op: UnOp::Neg, operand },
+    })
+  }
+}
+
+impl JsonSchema for Expression {
+  fn schema_name() -> Cow<'static, str> {
+    "Expression".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    ExpressionDe::json_schema(generator)
+  }
+}
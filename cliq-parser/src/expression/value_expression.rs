@@ -1,4 +1,7 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use self::float_value::FloatValue;
 use self::int_value::IntValue;
@@ -8,7 +11,7 @@ use super::Expression;
 pub mod float_value;
 pub mod int_value;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum ValueExpression {
   IntValue(int_value::IntValue),
   FloatValue(float_value::FloatValue),
@@ -23,3 +26,12 @@ impl ValueExpression {
     FloatValue::expression(val)
   }
 }
+
+impl fmt::Display for ValueExpression {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValueExpression::IntValue(val) => write!(f, "{}", val),
+      ValueExpression::FloatValue(val) => write!(f, "{}", val),
+    }
+  }
+}
@@ -1,17 +1,20 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use crate::expression::Expression;
 
 use super::ValueExpression;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct FloatValue {
   value: f32,
 }
 
 impl FloatValue {
   pub fn new(val: f32) -> FloatValue {
-    return FloatValue { value: val };
+    FloatValue { value: val }
   }
 
   pub fn expression(val: f32) -> Expression {
@@ -22,3 +25,15 @@ impl FloatValue {
     self.value
   }
 }
+
+//always keeps a fraction so the literal is lexed as a float again
+impl fmt::Display for FloatValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let text = self.value.to_string();
+    if text.contains('.') {
+      write!(f, "{}", text)
+    } else {
+      write!(f, "{}.0", text)
+    }
+  }
+}
@@ -1,17 +1,20 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use crate::expression::Expression;
 
 use super::ValueExpression;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct IntValue {
   value: i32,
 }
 
 impl IntValue {
   pub fn new(val: i32) -> IntValue {
-    return IntValue { value: val };
+    IntValue { value: val }
   }
 
   pub fn expression(val: i32) -> Expression {
@@ -22,3 +25,14 @@ impl IntValue {
     self.value
   }
 }
+
+//cliq has no negative literals, negative values print as a negation that folds back into them
+//except i32::MIN, whose magnitude does not fit an int and is printed as a subtraction instead
+impl fmt::Display for IntValue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.value == i32::MIN {
+      return write!(f, "(-2147483647 - 1)");
+    }
+    write!(f, "{}", self.value)
+  }
+}
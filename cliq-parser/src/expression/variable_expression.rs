@@ -1,8 +1,11 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use super::Expression;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct VariableExpression {
   pub name: String,
 }
@@ -15,4 +18,10 @@ impl VariableExpression {
   pub fn expression(name:String) -> Expression{
     Expression::VariableExpression(Self::new(name))
   }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for VariableExpression {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name)
+  }
+}
@@ -1,3 +1,9 @@
 pub mod statement;
 pub mod expression;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod cst;
+pub mod ast_json;
+pub mod ast_binary;
+pub mod arena;
+pub mod visitor;
+pub mod stack;
\ No newline at end of file
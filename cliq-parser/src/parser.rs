@@ -1,14 +1,25 @@
 use cliq_lexer::{token::Token, token_t::TokenT};
 
 use crate::{
+  arena::{Ast, Expr, ExprId, Stmt},
+  cst::fix::Fix,
   expression::{
-    binary_expression::{add_opr::AddOpr, div_opr::DivOpr, mul_opr::MulOpr, sub_opr::SubOpr},
+    call_expression::CallExpression,
+    operator::{BinOp, UnOp},
     value_expression::ValueExpression,
     Expression, variable_expression::VariableExpression,
   },
   statement::{assign_stmt::AssignStmt, Statement},
 };
 
+//how many parentheses, unary operators and calls an expression may be nested in,
+//every level is a few recursive calls here and in every pass that walks the tree afterwards
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+//how many binary operators may be stacked on top of each other, `1 + 2 + 3` is two;
+//a chain is parsed in a loop but the tree it builds is not flat, the passes walking it grow their stack instead
+pub const DEFAULT_MAX_CHAIN: usize = 4096;
+
 pub struct Parser {
   //module
   //stmts: Vec<Statement>,
@@ -16,26 +27,36 @@ pub struct Parser {
   current_token: usize,
   stream_size: usize,
   ast: Vec<Statement>,
+  errors: Vec<LangError>,
+  depth: usize,
+  max_depth: usize,
+  max_chain: usize,
+  //binary operators stacked in the expression parsed last, so the ones on top of it can check the tree they build
+  height: usize,
 }
 
-fn get_precedence(opr: &str) -> i32 {
-  match opr {
-    "+" => 1,
-    "-" => 1,
-    "*" => 2,
-    "/" => 2,
-    "%" => 2,
-    _ => 0,
-  }
+pub fn get_precedence(opr: &str) -> i32 {
+  BinOp::from_symbol(opr).map_or(0, |op| op.precedence())
 }
 
 struct LangError {
   msg: String,
+  //same edits the lossless parser suggests for the error, offsets are bytes of the lexed source;
+  //boxed so the results every level of an expression passes up stay small
+  fix: Option<Box<Fix>>,
 }
 
 impl LangError {
   fn new(msg: &str) -> LangError {
-    LangError { msg: msg.to_string() }
+    LangError {
+      msg: msg.to_string(),
+      fix: None,
+    }
+  }
+
+  fn with_fix(mut self, fix: Fix) -> LangError {
+    self.fix = Some(Box::new(fix));
+    self
   }
 }
 
@@ -45,6 +66,85 @@ impl std::fmt::Debug for LangError {
   }
 }
 
+//what the parser builds from the tokens, the boxed Expression tree or the nodes of an arena::Ast
+trait Builder {
+  type Expr;
+
+  fn int(&mut self, val: i32) -> Self::Expr;
+  fn float(&mut self, val: f32) -> Self::Expr;
+  fn variable(&mut self, name: &str) -> Self::Expr;
+  fn binary(&mut self, op: BinOp, lhs: Self::Expr, rhs: Self::Expr) -> Self::Expr;
+  fn unary(&mut self, op: UnOp, operand: Self::Expr) -> Self::Expr;
+  fn call(&mut self, name: &str, args: Vec<Self::Expr>) -> Self::Expr;
+}
+
+struct Boxed;
+
+impl Builder for Boxed {
+  type Expr = Expression;
+
+  fn int(&mut self, val: i32) -> Expression {
+    ValueExpression::int_value(val)
+  }
+
+  fn float(&mut self, val: f32) -> Expression {
+    ValueExpression::float_value(val)
+  }
+
+  fn variable(&mut self, name: &str) -> Expression {
+    VariableExpression::expression(name.to_string())
+  }
+
+  fn binary(&mut self, op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+    Expression::binary(op, lhs, rhs)
+  }
+
+  fn unary(&mut self, op: UnOp, operand: Expression) -> Expression {
+    Expression::unary(op, operand)
+  }
+
+  fn call(&mut self, name: &str, args: Vec<Expression>) -> Expression {
+    CallExpression::expression(name.to_string(), args)
+  }
+}
+
+impl Builder for Ast {
+  type Expr = ExprId;
+
+  fn int(&mut self, val: i32) -> ExprId {
+    self.alloc_expr(Expr::Int(val))
+  }
+
+  fn float(&mut self, val: f32) -> ExprId {
+    self.alloc_expr(Expr::Float(val))
+  }
+
+  fn variable(&mut self, name: &str) -> ExprId {
+    let symbol = self.intern(name);
+    self.alloc_expr(Expr::Variable(symbol))
+  }
+
+  fn binary(&mut self, op: BinOp, lhs: ExprId, rhs: ExprId) -> ExprId {
+    self.alloc_expr(Expr::Binary(op, lhs, rhs))
+  }
+
+  fn unary(&mut self, op: UnOp, operand: ExprId) -> ExprId {
+    self.alloc_expr(Expr::Unary(op, operand))
+  }
+
+  fn call(&mut self, name: &str, args: Vec<ExprId>) -> ExprId {
+    let symbol = self.intern(name);
+    let args = self.alloc_args(&args);
+    self.alloc_expr(Expr::Call(symbol, args))
+  }
+}
+
+//a statement whose expressions are built
+enum Parsed<E> {
+  Expression(E),
+  Assign { variable: E, value: E },
+}
+
 //we want to strictly use nom to parse the tokens
 impl Parser {
   pub fn new(input_tokens: Vec<Token>) -> Parser {
@@ -53,13 +153,42 @@ impl Parser {
       token_stream: input_tokens,
       current_token: 0,
       ast: vec![],
+      errors: vec![],
+      depth: 0,
+      max_depth: DEFAULT_MAX_DEPTH,
+      max_chain: DEFAULT_MAX_CHAIN,
+      height: 0,
     }
   }
 
+  pub fn with_max_depth(mut self, max_depth: usize) -> Parser {
+    self.max_depth = max_depth;
+    self
+  }
+
+  pub fn with_max_chain(mut self, max_chain: usize) -> Parser {
+    self.max_chain = max_chain;
+    self
+  }
+
+  //messages of the errors the last parse stopped at
+  pub fn errors(&self) -> Vec<&str> {
+    self.errors.iter().map(|err| err.msg.as_str()).collect()
+  }
+
+  //suggested edits of the errors that have one
+  pub fn fixes(&self) -> Vec<&Fix> {
+    self.errors.iter().filter_map(|err| err.fix.as_deref()).collect()
+  }
+
+  fn previous_end(&self) -> usize {
+    self.current_token.checked_sub(1).map_or(0, |index| self.token_stream[index].span.end)
+  }
+
   fn clear_whitespaces(&mut self) {
     let mut new_tokens = vec![];
     for token in self.token_stream.iter() {
-      if token.token_t != TokenT::WHITESPACE {
+      if token.token_t != TokenT::WHITESPACE && token.token_t != TokenT::COMMENT {
         new_tokens.push(token.clone());
       }
     }
@@ -91,9 +220,9 @@ impl Parser {
         if let Some(values) = token_has_value {
           if values.contains(&token.value) {
             self.current_token += 1;
-            return Ok(token.clone());
+            Ok(token.clone())
           } else {
-            return Err(LangError::new(
+            Err(LangError::new(
               format!(
                 "Expected token value: {:?} but got: {:?}\nHere: {:#?}",
                 values,
@@ -101,14 +230,14 @@ impl Parser {
                 self.get_token_stream(token)
               )
               .as_str(),
-            ));
+            ))
           }
         } else {
           self.current_token += 1;
-          return Ok(token.clone());
+          Ok(token.clone())
         }
       } else {
-        return Err(LangError::new(
+        Err(LangError::new(
           format!(
             "Expected token type: {:?} but got: {:?}\nHere: {:#?}",
             expected_token,
@@ -116,10 +245,10 @@ impl Parser {
             self.get_token_stream(token)
           )
           .as_str(),
-        ));
+        ))
       }
     } else {
-      return Err(LangError::new("Unexpected end of token stream"));
+      Err(LangError::new("Unexpected end of token stream"))
     }
   }
 
@@ -128,65 +257,150 @@ impl Parser {
       let token = &self.token_stream[self.current_token];
       if expected_token.contains(&token.token_t) {
         if let Some(values) = token_has_value {
-          if values.contains(&token.value) {
-            return Ok(true);
-          } else {
-            return Ok(false);
-          }
+          Ok(values.contains(&token.value))
         } else {
-          return Ok(true);
+          Ok(true)
         }
       } else {
-        return Ok(false);
+        Ok(false)
       }
     } else {
-      return Ok(false);
+      Ok(false)
     }
   }
 
-  fn parse_value(&mut self, token: Token) -> Result<Expression, LangError> {
+  fn parse_value<B: Builder>(&mut self, token: Token, builder: &mut B) -> Result<B::Expr, LangError> {
     match token.token_t {
       TokenT::INTEGER => {
-        let val = token.value.parse::<i32>().unwrap();
-        Ok(ValueExpression::int_value(val))
+        let val = token.value.parse::<i32>().map_err(|_| LangError::new("Integer literal out of range"))?;
+        Ok(builder.int(val))
       }
       TokenT::FLOAT => {
-        let val = token.value.parse::<f32>().unwrap();
-        Ok(ValueExpression::float_value(val))
+        let val = token.value.parse::<f32>().map_err(|_| LangError::new("Invalid float literal"))?;
+        Ok(builder.float(val))
       }
       _ => unreachable!(),
     }
   }
 
-  fn next_expression<'a>(&mut self) -> Result<Expression, LangError> {
-    let token = self.next(vec![TokenT::INTEGER, TokenT::FLOAT, TokenT::BRACKET, TokenT::IDENTIFIER], None)?;
+  //the depth is checked before anything is consumed, so input nested too deeply never recurses further
+  fn next_expression<B: Builder>(&mut self, builder: &mut B) -> Result<B::Expr, LangError> {
+    if self.depth >= self.max_depth {
+      return Err(self.too_deep());
+    }
+    self.depth += 1;
+    self.height = 0;
+    let expr = self.nested_expression(builder);
+    self.depth -= 1;
+    expr
+  }
+
+  //the node on top of an operand of lhs binary operators and the operand parsed last
+  fn binary_height(&self, lhs: usize) -> Result<usize, LangError> {
+    let height = lhs.max(self.height) + 1;
+    if height > self.max_chain {
+      let at = self.token_stream.get(self.current_token).map_or(0, |token| token.span.start);
+      return Err(LangError::new(
+        format!("Expression chains more than {} binary operators\nAt byte: {}", self.max_chain, at).as_str(),
+      ));
+    }
+    Ok(height)
+  }
+
+  fn too_deep(&self) -> LangError {
+    let at = self.token_stream.get(self.current_token).map_or(0, |token| token.span.start);
+    LangError::new(format!("Expression is nested deeper than {} levels\nAt byte: {}", self.max_depth, at).as_str())
+  }
+
+  fn nested_expression<B: Builder>(&mut self, builder: &mut B) -> Result<B::Expr, LangError> {
+    let token = self.next(
+      vec![
+        TokenT::INTEGER,
+        TokenT::FLOAT,
+        TokenT::BRACKET,
+        TokenT::IDENTIFIER,
+        TokenT::OPERATOR,
+      ],
+      None,
+    )?;
     Ok(match token.token_t {
-      TokenT::INTEGER | TokenT::FLOAT => self.parse_value(token)?,
+      TokenT::INTEGER | TokenT::FLOAT => self.parse_value(token, builder)?,
+      TokenT::OPERATOR => match UnOp::from_symbol(&token.value) {
+        Some(op) => {
+          let operand = self.next_expression(builder)?;
+          builder.unary(op, operand)
+        }
+        None => return Err(self.expected_expression(&token)),
+      },
       TokenT::BRACKET => {
         let value = token.value.clone();
         return match value.as_str() {
           "(" => {
-            let expr = self.parse_expression()?;
-            self.next(vec![TokenT::BRACKET], Some(vec![")".to_string()]))?;
+            let expr = self.parse_expression(builder)?;
+            self.close_bracket()?;
             Ok(expr)
           }
 
-          "{" => {
-            unimplemented!();
-          }
-          _ => unreachable!(),
+          "{" => Err(LangError::new("Blocks are not supported")),
+          _ => Err(self.expected_expression(&token)),
         };
       }
-      TokenT::IDENTIFIER => {
-        VariableExpression::expression(token.value.clone())
-      }
+      TokenT::IDENTIFIER if self.starts_call(&token) => self.parse_call(token, builder)?,
+      TokenT::IDENTIFIER => builder.variable(&token.value),
 
       _ => unreachable!(),
     })
   }
 
-  fn parse_high_precedence_expr<'a>(&mut self) -> Result<Expression, LangError> {
-    let mut expr = self.next_expression()?;
+  fn expected_expression(&self, token: &Token) -> LangError {
+    LangError::new(
+      format!(
+        "Expected an expression but got: {:?}\nHere: {:#?}",
+        token.value,
+        self.get_token_stream(token)
+      )
+      .as_str(),
+    )
+  }
+
+  //only `name(` is a call, with a space in between it would be a variable followed by a new statement
+  fn starts_call(&self, name: &Token) -> bool {
+    self
+      .token_stream
+      .get(self.current_token)
+      .is_some_and(|token| token.token_t == TokenT::BRACKET && token.value == "(" && token.span.start == name.span.end)
+  }
+
+  //`(1 + 2`
+  fn close_bracket(&mut self) -> Result<(), LangError> {
+    let end = self.previous_end();
+    match self.next(vec![TokenT::BRACKET], Some(vec![")".to_string()])) {
+      Ok(_) => Ok(()),
+      Err(err) => Err(err.with_fix(Fix::insert("insert `)`", end, ")"))),
+    }
+  }
+
+  fn parse_call<B: Builder>(&mut self, name: Token, builder: &mut B) -> Result<B::Expr, LangError> {
+    self.next(vec![TokenT::BRACKET], Some(vec!["(".to_string()]))?;
+    let mut args = vec![];
+    let mut height = 0;
+    if !self.peek(vec![TokenT::BRACKET], Some(vec![")".to_string()]))? {
+      args.push(self.parse_expression(builder)?);
+      height = self.height;
+      while self.peek(vec![TokenT::COMMA], None)? {
+        self.next(vec![TokenT::COMMA], None)?;
+        args.push(self.parse_expression(builder)?);
+        height = height.max(self.height);
+      }
+    }
+    self.next(vec![TokenT::BRACKET], Some(vec![")".to_string()]))?;
+    self.height = height;
+    Ok(builder.call(&name.value, args))
+  }
+
+  fn parse_high_precedence_expr<B: Builder>(&mut self, builder: &mut B) -> Result<B::Expr, LangError> {
+    let mut expr = self.next_expression(builder)?;
+    let mut height = self.height;
 
     while self.current_token < self.stream_size {
       match self.peek(vec![TokenT::OPERATOR], None)? {
@@ -199,21 +413,21 @@ impl Parser {
             break;
           }
 
-          let next_expr = self.next_expression()?;
-          expr = match operator_value.as_str() {
-            "*" => MulOpr::expression(expr, next_expr),
-            "/" => DivOpr::expression(expr, next_expr),
-            _ => unreachable!(),
-          };
+          let next_expr = self.next_expression(builder)?;
+          height = self.binary_height(height)?;
+          let op = BinOp::from_symbol(&operator_value).unwrap();
+          expr = builder.binary(op, expr, next_expr);
         }
         _ => break,
       }
     }
+    self.height = height;
     Ok(expr)
   }
 
-  fn parse_expression<'a>(&mut self) -> Result<Expression, LangError> {
-    let mut expr: Expression = self.parse_high_precedence_expr()?;
+  fn parse_expression<B: Builder>(&mut self, builder: &mut B) -> Result<B::Expr, LangError> {
+    let mut expr = self.parse_high_precedence_expr(builder)?;
+    let mut height = self.height;
 
     while self.current_token < self.stream_size {
       match self.peek(vec![TokenT::OPERATOR], None)? {
@@ -222,59 +436,102 @@ impl Parser {
           let operator_value = operator.value.clone();
 
           if get_precedence(&operator_value) == 1 {
-            let next_expr = self.parse_high_precedence_expr()?;
-            expr = match operator_value.as_str() {
-              "+" => AddOpr::expression(expr, next_expr),
-              "-" => SubOpr::expression(expr, next_expr),
-              _ => unreachable!(),
-            };
+            let next_expr = self.parse_high_precedence_expr(builder)?;
+            height = self.binary_height(height)?;
+            let op = BinOp::from_symbol(&operator_value).unwrap();
+            expr = builder.binary(op, expr, next_expr);
           }
         }
         _ => break,
       }
     }
+    self.height = height;
     Ok(expr)
   }
 
-  fn parse_statement(&mut self) -> Result<Statement, LangError> {
+  fn parse_statement<B: Builder>(&mut self, builder: &mut B) -> Result<Parsed<B::Expr>, LangError> {
     let token = self.next(vec![TokenT::VAR], None);
-    if token.is_ok() {
-      let token = token.unwrap();
+    if let Ok(token) = token {
       match token.token_t {
-        TokenT::VAR => self.parse_variable_assignment(),
+        TokenT::VAR => self.parse_variable_assignment(builder),
         _ => unreachable!(),
       }
     } else {
-      let expr = self.parse_expression().unwrap();
-      Ok(Statement::Expression(expr))
+      let expr = self.parse_expression(builder)?;
+      Ok(Parsed::Expression(expr))
     }
   }
 
-  fn parse_variable_assignment(&mut self) -> Result<Statement, LangError> {
-    let variable = self.next(vec![TokenT::IDENTIFIER], None).unwrap();
-    let variable = VariableExpression::expression(variable.value.clone());  
-    self.next(vec![TokenT::OPERATOR], Some(vec!["=".to_string()])).unwrap();
-    let expression = self.parse_expression().unwrap();
-    Ok(AssignStmt::statement(variable, expression, false))
+  fn parse_variable_assignment<B: Builder>(&mut self, builder: &mut B) -> Result<Parsed<B::Expr>, LangError> {
+    let name = self.next(vec![TokenT::IDENTIFIER], None)?;
+    let variable = builder.variable(&name.value);
+    if let Err(err) = self.next(vec![TokenT::OPERATOR], Some(vec!["=".to_string()])) {
+      //`var x 3`
+      if self.token_stream.get(self.current_token).is_some_and(starts_expression) {
+        return Err(err.with_fix(Fix::insert("insert `=`", name.span.end, " =")));
+      }
+      return Err(err);
+    }
+    let value = self.parse_expression(builder)?;
+    Ok(Parsed::Assign { variable, value })
   }
 
   pub fn serialize_ast(&self) -> String {
     serde_json::to_string(&self.ast).unwrap_or_else(|err| format!("Error serializing AST: {}", err))
   }
 
-  pub fn parse(&mut self) -> Vec<Statement> {
+  fn parse_statements<B: Builder>(&mut self, builder: &mut B, mut push: impl FnMut(&mut B, Parsed<B::Expr>)) {
     self.clear_whitespaces();
-    let mut errors: Vec<LangError> = vec![];
-    while self.token_stream.get(self.current_token) != None {
-      let stmt = self.parse_statement();
-      if stmt.is_err() {
-        errors.push(stmt.err().unwrap());
-      } else {
-        self.ast.push(stmt.unwrap());
+    while self.token_stream.get(self.current_token).is_some() {
+      match self.parse_statement(builder) {
+        Ok(stmt) => push(builder, stmt),
+        Err(err) => {
+          //there is no telling where the next statement starts
+          self.errors.push(err);
+          break;
+        }
       }
     }
+  }
+
+  //the statements in front of the first error, the error itself is in errors()
+  pub fn parse(&mut self) -> Vec<Statement> {
+    let mut ast = vec![];
+    self.parse_statements(&mut Boxed, |_, stmt| {
+      ast.push(match stmt {
+        Parsed::Expression(expr) => Statement::Expression(expr),
+        Parsed::Assign { variable, value } => AssignStmt::statement(variable, value, false),
+      })
+    });
+    self.ast.extend(ast);
     self.ast.clone()
   }
+
+  //like parse, but the nodes are allocated in an arena::Ast without building the boxed tree first
+  pub fn parse_arena(&mut self) -> Ast {
+    let mut arena = Ast::new();
+    self.parse_statements(&mut arena, |arena, stmt| {
+      arena.push_stmt(match stmt {
+        Parsed::Expression(expr) => Stmt::Expression(expr),
+        Parsed::Assign { variable, value } => Stmt::Assign {
+          variable,
+          value,
+          mutable: false,
+        },
+      });
+    });
+    arena
+  }
+}
+
+//the tokens the lossless grammar accepts at the start of an expression
+fn starts_expression(token: &Token) -> bool {
+  match token.token_t {
+    TokenT::INTEGER | TokenT::FLOAT | TokenT::IDENTIFIER => true,
+    TokenT::OPERATOR => UnOp::from_symbol(&token.value).is_some(),
+    TokenT::BRACKET => token.value == "(",
+    _ => false,
+  }
 }
 
 #[cfg(test)]
@@ -301,10 +558,10 @@ mod tests {
     let tokens = lexer.lex(input).unwrap();
     let mut parser = super::Parser::new(tokens);
     parser.clear_whitespaces();
-    let expr = parser.next_expression();
+    let expr = parser.next_expression(&mut super::Boxed);
     println!("{:#?}", expr);
 
-    assert_eq!(expr.is_ok(), true);
+    assert!(expr.is_ok());
     let expr = expr.unwrap();
     assert_eq!(expr, ValueExpression::int_value(123));
   }
@@ -351,6 +608,27 @@ mod tests {
     println!("Serialized AST for '{}':\n{}", input, serialized_ast);
   }
 
+  #[test]
+  fn test_call_expression() {
+    use crate::expression::call_expression::CallExpression;
+    use crate::expression::variable_expression::VariableExpression;
+    use crate::statement::Statement;
+
+    let tokens = Lexer::new().lex("max(a, 2 * b) + f()\nf\n(a)").unwrap();
+    let ast = super::Parser::new(tokens).parse();
+    let var = |name: &str| VariableExpression::expression(name.to_string());
+    assert_eq!(ast.len(), 3);
+    assert_eq!(ast[0].to_string(), "max(a, 2 * b) + f()");
+    assert_eq!(
+      ast[1..],
+      [Statement::Expression(var("f")), Statement::Expression(var("a"))]
+    );
+    let Statement::Expression(crate::expression::Expression::Binary { rhs, .. }) = &ast[0] else {
+      panic!("expected a binary expression");
+    };
+    assert_eq!(**rhs, CallExpression::expression("f".to_string(), vec![]));
+  }
+
   #[test]
   fn test_parse_variable_def() {
     let mut lexer = Lexer::new();
@@ -362,4 +640,86 @@ mod tests {
     let serialized_ast = parser.serialize_ast();
     println!("Serialized AST for '{}':\n{}", input, serialized_ast);
   }
+
+  #[test]
+  fn test_max_depth() {
+    let parse = |input: &str, max_depth: usize| {
+      let mut parser = super::Parser::new(Lexer::new().lex(input).unwrap()).with_max_depth(max_depth);
+      let ast = parser.parse();
+      (ast.len(), parser.errors().len())
+    };
+    //the call, its argument, the operand of `-` and the parenthesized expression are one level each
+    assert_eq!(parse("var a = 1\nf(-(a))", 4), (2, 0));
+    assert_eq!(parse("var a = 1\nf(-(a))", 3), (1, 1));
+    //parentheses around a chain are one level each, however long it is
+    assert_eq!(parse(&format!("var a = 1\n(({}))", vec!["a"; 100].join(" + ")), 3), (2, 0));
+
+    //far more than the stack would take, the tokens are copied because lexing that much is slow
+    let tokens = Lexer::new().lex("(1)").unwrap();
+    let mut deep = vec![tokens[0].clone(); 100_000];
+    deep.push(tokens[1].clone());
+    deep.extend(vec![tokens[2].clone(); 100_000]);
+    let mut parser = super::Parser::new(deep);
+    assert!(parser.parse().is_empty());
+    assert_eq!(parser.errors(), vec!["Expression is nested deeper than 256 levels\nAt byte: 0"]);
+  }
+
+  #[test]
+  fn test_max_chain() {
+    let parse = |input: &str, max_chain: usize| {
+      let mut parser = super::Parser::new(Lexer::new().lex(input).unwrap()).with_max_chain(max_chain);
+      let ast = parser.parse();
+      (ast.len(), parser.errors().len())
+    };
+    //every binary operator of a chain is stacked on the ones before it, its first operand is the deepest node
+    let chain = |terms: usize| vec!["a"; terms].join(" + ");
+    assert_eq!(parse(&format!("var a = 1\n{}", chain(21)), 20), (2, 0));
+    assert_eq!(parse(&format!("var a = 1\n{}", chain(22)), 20), (1, 1));
+    assert_eq!(parse("var a = 1\n(a * a) + (a)", 2), (2, 0));
+    assert_eq!(parse("var a = 1\n(a * a) + (a)", 1), (1, 1));
+    assert_eq!(parse("var a = 1\nf(a * a * a)", 2), (2, 0));
+    assert_eq!(parse("var a = 1\nf(a * a * a)", 1), (1, 1));
+    //a chain inside parentheses counts on top of the operators around them, the parentheses themselves don't
+    assert_eq!(parse("var a = 1\n(a + a) * -((a + a + a))", 3), (2, 0));
+    assert_eq!(parse("var a = 1\n(a + a) * -((a + a + a))", 2), (1, 1));
+
+    //a chain is parsed in a loop, a long one stops at the limit just the same
+    let tokens = Lexer::new().lex("1+1").unwrap();
+    let mut chain = vec![tokens[0].clone()];
+    for _ in 0..100_000 {
+      chain.extend_from_slice(&tokens[1..]);
+    }
+    let mut parser = super::Parser::new(chain);
+    assert!(parser.parse().is_empty());
+    assert_eq!(parser.errors(), vec!["Expression chains more than 4096 binary operators\nAt byte: 1"]);
+  }
+
+  #[test]
+  fn test_invalid_values() {
+    let errors = |input: &str| {
+      let mut parser = super::Parser::new(Lexer::new().lex(input).unwrap());
+      assert!(parser.parse().is_empty());
+      parser.errors().iter().map(|err| err.lines().next().unwrap().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(errors("99999999999"), vec!["Integer literal out of range"]);
+    assert_eq!(errors("var x = 99999999999"), vec!["Integer literal out of range"]);
+    assert_eq!(errors("1 + { 1 }"), vec!["Blocks are not supported"]);
+    assert_eq!(errors("[1]"), vec!["Expected an expression but got: \"[\""]);
+    assert_eq!(errors("1 + }"), vec!["Expected an expression but got: \"}\""]);
+  }
+
+  #[test]
+  fn test_fixes() {
+    let fixed = |input: &str| {
+      let mut parser = super::Parser::new(Lexer::new().lex(input).unwrap());
+      assert!(parser.parse().is_empty());
+      assert_eq!(parser.errors().len(), 1);
+      parser.fixes().first().map(|fix| fix.apply(input))
+    };
+    assert_eq!(fixed("var x 3"), Some("var x = 3".to_string()));
+    assert_eq!(fixed("var x -y"), Some("var x = -y".to_string()));
+    assert_eq!(fixed("(1 + 2"), Some("(1 + 2)".to_string()));
+    assert_eq!(fixed("var x"), None);
+    assert_eq!(fixed("var 3 = 1"), None);
+  }
 }
@@ -0,0 +1,10 @@
+//a long chain like `a + b + ... + z` nests one level per operator, the passes that walk a tree recursively
+//continue on a new stack when this little is left instead of overflowing
+const RED_ZONE: usize = 64 * 1024;
+const STACK_SIZE: usize = 1024 * 1024;
+
+//runs f on the current stack, or on a fresh one when the current one is close to its end;
+//call it on every level of a recursive walk over expressions or syntax nodes
+pub fn grow<R>(f: impl FnOnce() -> R) -> R {
+  stacker::maybe_grow(RED_ZONE, STACK_SIZE, f)
+}
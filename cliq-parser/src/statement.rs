@@ -1,10 +1,42 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use crate::expression::Expression;
 
 pub mod assign_stmt;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Statement{
   Expression(Expression),
   Assign(assign_stmt::AssignStmt),
-}
\ No newline at end of file
+}
+
+impl Statement {
+  pub fn to_source(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl fmt::Display for Statement {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Statement::Expression(expr) => write!(f, "{}", expr),
+      Statement::Assign(stmt) => write!(f, "{}", stmt),
+    }
+  }
+}
+
+//one statement per line, parses back into the same statements
+//statements are not terminated, so `a\n-b` reads as `a - b`; such statements get wrapped
+pub fn to_source(ast: &[Statement]) -> String {
+  let mut out = String::new();
+  for (i, stmt) in ast.iter().enumerate() {
+    match stmt {
+      Statement::Expression(expr) if i > 0 && expr.starts_with_unary() => out.push_str(&format!("({})", expr)),
+      _ => out.push_str(&stmt.to_string()),
+    }
+    out.push('\n');
+  }
+  out
+}
@@ -1,10 +1,13 @@
-use serde::Serialize;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use crate::expression::Expression;
 
 use super::Statement;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct AssignStmt{
   pub variable: Expression,
   pub value: Expression,
@@ -23,4 +26,11 @@ impl AssignStmt{
   pub fn statement(variable: Expression, value: Expression, mutable: bool) -> Statement{
     Statement::Assign(Self::new(variable, value, mutable))
   }
-}
\ No newline at end of file
+}
+
+//the grammar only has immutable `var` declarations, so mutable is not printed
+impl fmt::Display for AssignStmt {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "var {} = {}", self.variable, self.value)
+  }
+}
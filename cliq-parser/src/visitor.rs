@@ -0,0 +1,7 @@
+pub mod fold;
+pub mod visit;
+pub mod visit_mut;
+
+pub use self::fold::Fold;
+pub use self::visit::Visitor;
+pub use self::visit_mut::VisitorMut;
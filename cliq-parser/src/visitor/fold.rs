@@ -0,0 +1,138 @@
+use crate::expression::call_expression::CallExpression;
+use crate::expression::operator::{BinOp, UnOp};
+use crate::expression::value_expression::ValueExpression;
+use crate::expression::variable_expression::VariableExpression;
+use crate::expression::Expression;
+use crate::statement::assign_stmt::AssignStmt;
+use crate::statement::Statement;
+
+//consumes the tree and rebuilds it, each expression method may return a different kind of node
+pub trait Fold {
+  fn fold_statements(&mut self, stmts: Vec<Statement>) -> Vec<Statement> {
+    walk_statements(self, stmts)
+  }
+
+  fn fold_statement(&mut self, stmt: Statement) -> Statement {
+    walk_statement(self, stmt)
+  }
+
+  fn fold_assign_stmt(&mut self, stmt: AssignStmt) -> AssignStmt {
+    walk_assign_stmt(self, stmt)
+  }
+
+  fn fold_expression(&mut self, expr: Expression) -> Expression {
+    walk_expression(self, expr)
+  }
+
+  fn fold_value(&mut self, value: ValueExpression) -> Expression {
+    Expression::ValueExpression(value)
+  }
+
+  fn fold_variable(&mut self, variable: VariableExpression) -> Expression {
+    Expression::VariableExpression(variable)
+  }
+
+  fn fold_binary(&mut self, op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+    walk_binary(self, op, lhs, rhs)
+  }
+
+  fn fold_unary(&mut self, op: UnOp, operand: Expression) -> Expression {
+    walk_unary(self, op, operand)
+  }
+
+  fn fold_call(&mut self, call: CallExpression) -> Expression {
+    walk_call(self, call)
+  }
+}
+
+pub fn walk_statements<F: Fold + ?Sized>(folder: &mut F, stmts: Vec<Statement>) -> Vec<Statement> {
+  stmts.into_iter().map(|stmt| folder.fold_statement(stmt)).collect()
+}
+
+pub fn walk_statement<F: Fold + ?Sized>(folder: &mut F, stmt: Statement) -> Statement {
+  match stmt {
+    Statement::Expression(expr) => Statement::Expression(folder.fold_expression(expr)),
+    Statement::Assign(stmt) => Statement::Assign(folder.fold_assign_stmt(stmt)),
+  }
+}
+
+pub fn walk_assign_stmt<F: Fold + ?Sized>(folder: &mut F, stmt: AssignStmt) -> AssignStmt {
+  let value = folder.fold_expression(stmt.value);
+  let variable = folder.fold_expression(stmt.variable);
+  AssignStmt::new(variable, value, stmt.mutable)
+}
+
+//Expression implements Drop, so the children are taken out of it rather than moved
+pub fn walk_expression<F: Fold + ?Sized>(folder: &mut F, mut expr: Expression) -> Expression {
+  crate::stack::grow(|| match &mut expr {
+    Expression::ValueExpression(value) => folder.fold_value(value.clone()),
+    Expression::VariableExpression(variable) => folder.fold_variable(variable.clone()),
+    Expression::Binary { op, lhs, rhs } => folder.fold_binary(*op, lhs.take(), rhs.take()),
+    Expression::Unary { op, operand } => folder.fold_unary(*op, operand.take()),
+    Expression::Call(call) => folder.fold_call(CallExpression::new(std::mem::take(&mut call.name), std::mem::take(&mut call.args))),
+  })
+}
+
+pub fn walk_binary<F: Fold + ?Sized>(folder: &mut F, op: BinOp, lhs: Expression, rhs: Expression) -> Expression {
+  let lhs = folder.fold_expression(lhs);
+  let rhs = folder.fold_expression(rhs);
+  Expression::binary(op, lhs, rhs)
+}
+
+pub fn walk_unary<F: Fold + ?Sized>(folder: &mut F, op: UnOp, operand: Expression) -> Expression {
+  let operand = folder.fold_expression(operand);
+  Expression::unary(op, operand)
+}
+
+pub fn walk_call<F: Fold + ?Sized>(folder: &mut F, call: CallExpression) -> Expression {
+  let args = call.args.into_iter().map(|arg| folder.fold_expression(arg)).collect();
+  CallExpression::expression(call.name, args)
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+  use crate::statement::to_source;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  #[test]
+  fn test_identity_fold() {
+    struct Identity;
+    impl Fold for Identity {}
+
+    let ast = parse("var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / ab % -3");
+    assert_eq!(Identity.fold_statements(ast.clone()), ast);
+  }
+
+  #[test]
+  fn test_replace_node_kind() {
+    //substitutes variables with the literal they are bound to
+    struct Inline(Vec<(String, Expression)>);
+    impl Fold for Inline {
+      fn fold_assign_stmt(&mut self, stmt: AssignStmt) -> AssignStmt {
+        let value = self.fold_expression(stmt.value);
+        if let Expression::VariableExpression(variable) = &stmt.variable {
+          self.0.push((variable.name.clone(), value.clone()));
+        }
+        AssignStmt::new(stmt.variable, value, stmt.mutable)
+      }
+
+      fn fold_variable(&mut self, variable: VariableExpression) -> Expression {
+        match self.0.iter().rev().find(|(name, _)| *name == variable.name) {
+          Some((_, value)) => value.clone(),
+          None => Expression::VariableExpression(variable),
+        }
+      }
+    }
+
+    let ast = parse("var a = 2\nvar b = a + 1.5\nb * -c");
+    let folded = Inline(Vec::new()).fold_statements(ast);
+    assert_eq!(to_source(&folded), "var a = 2\nvar b = 2 + 1.5\n(2 + 1.5) * -c\n");
+  }
+}
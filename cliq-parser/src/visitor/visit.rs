@@ -0,0 +1,154 @@
+use crate::expression::call_expression::CallExpression;
+use crate::expression::operator::{BinOp, UnOp};
+use crate::expression::value_expression::ValueExpression;
+use crate::expression::variable_expression::VariableExpression;
+use crate::expression::Expression;
+use crate::statement::assign_stmt::AssignStmt;
+use crate::statement::Statement;
+
+//read only traversal, every method defaults to walking into the children
+pub trait Visitor {
+  fn visit_statements(&mut self, stmts: &[Statement]) {
+    walk_statements(self, stmts)
+  }
+
+  fn visit_statement(&mut self, stmt: &Statement) {
+    walk_statement(self, stmt)
+  }
+
+  fn visit_assign_stmt(&mut self, stmt: &AssignStmt) {
+    walk_assign_stmt(self, stmt)
+  }
+
+  fn visit_expression(&mut self, expr: &Expression) {
+    walk_expression(self, expr)
+  }
+
+  fn visit_value(&mut self, _value: &ValueExpression) {}
+
+  fn visit_variable(&mut self, _variable: &VariableExpression) {}
+
+  fn visit_binary(&mut self, op: BinOp, lhs: &Expression, rhs: &Expression) {
+    walk_binary(self, op, lhs, rhs)
+  }
+
+  fn visit_unary(&mut self, op: UnOp, operand: &Expression) {
+    walk_unary(self, op, operand)
+  }
+
+  fn visit_call(&mut self, call: &CallExpression) {
+    walk_call(self, call)
+  }
+}
+
+pub fn walk_statements<V: Visitor + ?Sized>(visitor: &mut V, stmts: &[Statement]) {
+  for stmt in stmts {
+    visitor.visit_statement(stmt);
+  }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Statement) {
+  match stmt {
+    Statement::Expression(expr) => visitor.visit_expression(expr),
+    Statement::Assign(stmt) => visitor.visit_assign_stmt(stmt),
+  }
+}
+
+//the value is visited before the variable, in the order they are evaluated
+pub fn walk_assign_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &AssignStmt) {
+  visitor.visit_expression(&stmt.value);
+  visitor.visit_expression(&stmt.variable);
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expression) {
+  //every level of every walk goes through here, so this is where the stack grows for visitors that recurse
+  crate::stack::grow(|| match expr {
+    Expression::ValueExpression(value) => visitor.visit_value(value),
+    Expression::VariableExpression(variable) => visitor.visit_variable(variable),
+    Expression::Binary { op, lhs, rhs } => visitor.visit_binary(*op, lhs, rhs),
+    Expression::Unary { op, operand } => visitor.visit_unary(*op, operand),
+    Expression::Call(call) => visitor.visit_call(call),
+  })
+}
+
+pub fn walk_binary<V: Visitor + ?Sized>(visitor: &mut V, _op: BinOp, lhs: &Expression, rhs: &Expression) {
+  visitor.visit_expression(lhs);
+  visitor.visit_expression(rhs);
+}
+
+pub fn walk_unary<V: Visitor + ?Sized>(visitor: &mut V, _op: UnOp, operand: &Expression) {
+  visitor.visit_expression(operand);
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(visitor: &mut V, call: &CallExpression) {
+  for arg in &call.args {
+    visitor.visit_expression(arg);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  #[derive(Default)]
+  struct Collect {
+    variables: Vec<String>,
+    operators: Vec<&'static str>,
+    values: usize,
+  }
+
+  impl Visitor for Collect {
+    fn visit_value(&mut self, _value: &ValueExpression) {
+      self.values += 1;
+    }
+
+    fn visit_variable(&mut self, variable: &VariableExpression) {
+      self.variables.push(variable.name.clone());
+    }
+
+    fn visit_binary(&mut self, op: BinOp, lhs: &Expression, rhs: &Expression) {
+      self.operators.push(op.symbol());
+      walk_binary(self, op, lhs, rhs);
+    }
+
+    fn visit_unary(&mut self, op: UnOp, operand: &Expression) {
+      self.operators.push(op.symbol());
+      walk_unary(self, op, operand);
+    }
+  }
+
+  #[test]
+  fn test_visit_every_node() {
+    let ast = parse("var x = (a + 2) * -b\nx % max(3.0, c)");
+    let mut collect = Collect::default();
+    collect.visit_statements(&ast);
+    assert_eq!(collect.variables, vec!["a", "b", "x", "x", "c"]);
+    assert_eq!(collect.operators, vec!["*", "+", "-", "%"]);
+    assert_eq!(collect.values, 2);
+  }
+
+  #[test]
+  fn test_override_stops_walk() {
+    struct Statements(usize);
+    impl Visitor for Statements {
+      fn visit_statement(&mut self, _stmt: &Statement) {
+        self.0 += 1;
+      }
+
+      fn visit_expression(&mut self, _expr: &Expression) {
+        panic!("statements are not walked into");
+      }
+    }
+
+    let mut count = Statements(0);
+    count.visit_statements(&parse("var a = 1\na + 1\n2"));
+    assert_eq!(count.0, 3);
+  }
+}
@@ -0,0 +1,141 @@
+use crate::expression::call_expression::CallExpression;
+use crate::expression::operator::{BinOp, UnOp};
+use crate::expression::value_expression::ValueExpression;
+use crate::expression::variable_expression::VariableExpression;
+use crate::expression::Expression;
+use crate::statement::assign_stmt::AssignStmt;
+use crate::statement::Statement;
+
+//in place traversal, for rewrites that keep the shape of the tree
+//a node can still be replaced wholesale from visit_expression
+pub trait VisitorMut {
+  fn visit_statements_mut(&mut self, stmts: &mut [Statement]) {
+    walk_statements_mut(self, stmts)
+  }
+
+  fn visit_statement_mut(&mut self, stmt: &mut Statement) {
+    walk_statement_mut(self, stmt)
+  }
+
+  fn visit_assign_stmt_mut(&mut self, stmt: &mut AssignStmt) {
+    walk_assign_stmt_mut(self, stmt)
+  }
+
+  fn visit_expression_mut(&mut self, expr: &mut Expression) {
+    walk_expression_mut(self, expr)
+  }
+
+  fn visit_value_mut(&mut self, _value: &mut ValueExpression) {}
+
+  fn visit_variable_mut(&mut self, _variable: &mut VariableExpression) {}
+
+  fn visit_binary_mut(&mut self, op: &mut BinOp, lhs: &mut Expression, rhs: &mut Expression) {
+    walk_binary_mut(self, op, lhs, rhs)
+  }
+
+  fn visit_unary_mut(&mut self, op: &mut UnOp, operand: &mut Expression) {
+    walk_unary_mut(self, op, operand)
+  }
+
+  fn visit_call_mut(&mut self, call: &mut CallExpression) {
+    walk_call_mut(self, call)
+  }
+}
+
+pub fn walk_statements_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmts: &mut [Statement]) {
+  for stmt in stmts {
+    visitor.visit_statement_mut(stmt);
+  }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Statement) {
+  match stmt {
+    Statement::Expression(expr) => visitor.visit_expression_mut(expr),
+    Statement::Assign(stmt) => visitor.visit_assign_stmt_mut(stmt),
+  }
+}
+
+pub fn walk_assign_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut AssignStmt) {
+  visitor.visit_expression_mut(&mut stmt.value);
+  visitor.visit_expression_mut(&mut stmt.variable);
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expression) {
+  crate::stack::grow(|| match expr {
+    Expression::ValueExpression(value) => visitor.visit_value_mut(value),
+    Expression::VariableExpression(variable) => visitor.visit_variable_mut(variable),
+    Expression::Binary { op, lhs, rhs } => visitor.visit_binary_mut(op, lhs, rhs),
+    Expression::Unary { op, operand } => visitor.visit_unary_mut(op, operand),
+    Expression::Call(call) => visitor.visit_call_mut(call),
+  })
+}
+
+pub fn walk_binary_mut<V: VisitorMut + ?Sized>(visitor: &mut V, _op: &mut BinOp, lhs: &mut Expression, rhs: &mut Expression) {
+  visitor.visit_expression_mut(lhs);
+  visitor.visit_expression_mut(rhs);
+}
+
+pub fn walk_unary_mut<V: VisitorMut + ?Sized>(visitor: &mut V, _op: &mut UnOp, operand: &mut Expression) {
+  visitor.visit_expression_mut(operand);
+}
+
+pub fn walk_call_mut<V: VisitorMut + ?Sized>(visitor: &mut V, call: &mut CallExpression) {
+  for arg in &mut call.args {
+    visitor.visit_expression_mut(arg);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+
+  use super::*;
+  use crate::parser::Parser;
+  use crate::statement::to_source;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  #[test]
+  fn test_rename_variables() {
+    struct Rename;
+    impl VisitorMut for Rename {
+      fn visit_variable_mut(&mut self, variable: &mut VariableExpression) {
+        variable.name = variable.name.to_uppercase();
+      }
+    }
+
+    let mut ast = parse("var x = a * (b - 1)\nx");
+    Rename.visit_statements_mut(&mut ast);
+    assert_eq!(to_source(&ast), "var X = A * (B - 1)\nX\n");
+  }
+
+  #[test]
+  fn test_replace_expression() {
+    //swaps operands of commutative operators and drops double negation
+    struct Rewrite;
+    impl VisitorMut for Rewrite {
+      fn visit_expression_mut(&mut self, expr: &mut Expression) {
+        walk_expression_mut(self, expr);
+        if let Expression::Unary { operand, .. } = expr {
+          if let Expression::Unary { operand: inner, .. } = operand.as_mut() {
+            let inner = std::mem::replace(inner.as_mut(), ValueExpression::int_value(0));
+            *expr = inner;
+          }
+        }
+      }
+
+      fn visit_binary_mut(&mut self, op: &mut BinOp, lhs: &mut Expression, rhs: &mut Expression) {
+        walk_binary_mut(self, op, lhs, rhs);
+        if matches!(op, BinOp::Add | BinOp::Mul) {
+          std::mem::swap(lhs, rhs);
+        }
+      }
+    }
+
+    let mut ast = parse("--a + b * 2");
+    Rewrite.visit_statements_mut(&mut ast);
+    assert_eq!(to_source(&ast), "2 * b + a\n");
+  }
+}
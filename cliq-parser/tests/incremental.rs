@@ -0,0 +1,47 @@
+use cliq_lexer::lexer::Lexer;
+use cliq_parser::cst::{
+  self,
+  incremental::{IncrementalParse, TextEdit},
+};
+use proptest::prelude::*;
+
+//small pieces so random edits split and join tokens, lines and statements
+fn text() -> impl Strategy<Value = String> {
+  let piece = prop::sample::select(vec![
+    "var ", "a", "b1", "1", "2.5", ".5", " + ", "-", "*", " = ", "(", ")", " ", "\n", "// ü", "$",
+  ]);
+  prop::collection::vec(piece, 0..24).prop_map(|pieces| pieces.concat())
+}
+
+fn boundary(src: &str, fraction: f64) -> usize {
+  let mut offset = (src.len() as f64 * fraction) as usize;
+  while !src.is_char_boundary(offset) {
+    offset -= 1;
+  }
+  offset
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(128))]
+
+  #[test]
+  fn edits_match_full_reparse(src in text(), edits in prop::collection::vec((0.0..=1.0f64, 0.0..=1.0f64, text()), 1..6)) {
+    let mut parse = IncrementalParse::new(src);
+    for (a, b, replacement) in edits {
+      let (a, b) = (boundary(parse.src(), a), boundary(parse.src(), b));
+      parse.edit(&TextEdit::new(a.min(b)..a.max(b), replacement));
+
+      let src = parse.src();
+      match Lexer::new().lex(src) {
+        Ok(tokens) => {
+          prop_assert_eq!(parse.tokens().unwrap(), tokens.as_slice(), "tokens of {:?}", src);
+          let full = cst::parse_tokens(src, &tokens);
+          let incremental = parse.parse().unwrap();
+          prop_assert_eq!(incremental.green(), full.green(), "tree of {:?}", src);
+          prop_assert_eq!(incremental.errors(), full.errors(), "errors of {:?}", src);
+        }
+        Err(msg) => prop_assert_eq!(parse.parse().unwrap_err(), msg),
+      }
+    }
+  }
+}
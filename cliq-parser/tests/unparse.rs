@@ -0,0 +1,65 @@
+use cliq_lexer::lexer::Lexer;
+use cliq_parser::{
+  expression::{
+    call_expression::CallExpression,
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  parser::Parser,
+  statement::{assign_stmt::AssignStmt, to_source, Statement},
+};
+use proptest::prelude::*;
+
+fn parse(input: &str) -> Vec<Statement> {
+  let tokens = Lexer::new().lex(input).unwrap();
+  Parser::new(tokens).parse()
+}
+
+fn name() -> impl Strategy<Value = String> {
+  "[a-z_][a-z0-9_]{0,6}".prop_filter("keyword", |name| name != "var")
+}
+
+//literals are never negative, the grammar has no way to write them
+fn expression() -> impl Strategy<Value = Expression> {
+  let leaf = prop_oneof![
+    (0..=i32::MAX).prop_map(ValueExpression::int_value),
+    (0.0f32..1.0e9).prop_map(ValueExpression::float_value),
+    name().prop_map(VariableExpression::expression),
+  ];
+  leaf.prop_recursive(6, 64, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), inner.clone(), prop::sample::select(BinOp::ALL.to_vec())).prop_map(|(lhs, rhs, op)| Expression::binary(op, lhs, rhs)),
+      inner.clone().prop_map(|operand| Expression::unary(UnOp::Neg, operand)),
+      (name(), prop::collection::vec(inner, 0..3)).prop_map(|(name, args)| CallExpression::expression(name, args)),
+    ]
+  })
+}
+
+fn statement() -> impl Strategy<Value = Statement> {
+  prop_oneof![
+    expression().prop_map(Statement::Expression),
+    (name(), expression()).prop_map(|(name, value)| AssignStmt::statement(VariableExpression::expression(name), value, false)),
+  ]
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(64))]
+
+  #[test]
+  fn parse_print_roundtrip(ast in prop::collection::vec(statement(), 0..8)) {
+    let source = to_source(&ast);
+    prop_assert_eq!(parse(&source), ast, "printed as:\n{}", source);
+  }
+}
+
+#[test]
+fn prints_minimal_parentheses() {
+  let input = "var hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\n(4 - 3) - (2 - 1) / (7 % 5)\n2.0 * x\n(-(-a) * -(b + 1))";
+  let printed = to_source(&parse(input));
+  assert_eq!(
+    printed,
+    "var hello = (ab + 123.3) * (ac - (33 + 22) * 2) + 2\n4 - 3 - (2 - 1) / (7 % 5)\n2.0 * x\n(--a * -(b + 1))\n"
+  );
+}
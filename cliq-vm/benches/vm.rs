@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cliq_interp::Interpreter;
+use cliq_lexer::lexer::Lexer;
+use cliq_parser::parser::Parser;
+use cliq_parser::statement::Statement;
+use cliq_vm::{compile, Vm};
+
+//a chain of declarations where every variable depends on the two before it
+fn program(len: usize) -> Vec<Statement> {
+  let mut src = String::from("var v0 = 3\nvar v1 = 7.5\n");
+  for i in 2..len {
+    src.push_str(&format!(
+      "var v{i} = (v{a} * 3 + {i}) % 1000 - v{b} / 7 + -(v{a} - {i}.25) * 2\n",
+      a = i - 1,
+      b = i - 2
+    ));
+  }
+  src.push_str(&format!("v{} * 2", len - 1));
+  Parser::new(Lexer::new().lex(&src).unwrap()).parse()
+}
+
+fn bench_backends(c: &mut Criterion) {
+  let ast = program(200);
+  let chunk = compile(&ast).unwrap();
+
+  let mut group = c.benchmark_group("run");
+  group.bench_function("interpreter", |b| b.iter(|| Interpreter::new().run(black_box(&ast)).unwrap()));
+  group.bench_function("vm", |b| b.iter(|| Vm::new(black_box(&chunk)).run().unwrap()));
+  #[cfg(feature = "jit")]
+  {
+    let program = cliq_vm::jit::JitProgram::compile(&ast).unwrap();
+    group.bench_function("jit", |b| b.iter(|| black_box(&program).run().unwrap()));
+  }
+  group.finish();
+
+  c.bench_function("compile", |b| b.iter(|| compile(black_box(&ast)).unwrap()));
+}
+
+criterion_group!(benches, bench_backends);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use cliq_opt::value::Value;
+
+use crate::opcode::Op;
+
+//compiled program: bytecode, the constants it loads, the variable name of every local slot
+//and the name of every host function it calls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chunk {
+  code: Vec<u8>,
+  constants: Vec<Value>,
+  slots: Vec<String>,
+  functions: Vec<String>,
+  //indices of the entries above, floats are keyed bitwise so 0.0 and -0.0 stay apart
+  constant_indices: HashMap<(u8, u32), u16>,
+  slot_indices: HashMap<String, u16>,
+  function_indices: HashMap<String, u16>,
+}
+
+impl Chunk {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn code(&self) -> &[u8] {
+    &self.code
+  }
+
+  pub fn constants(&self) -> &[Value] {
+    &self.constants
+  }
+
+  pub fn slots(&self) -> &[String] {
+    &self.slots
+  }
+
+  pub fn slot(&self, name: &str) -> Option<u16> {
+    self.slot_indices.get(name).copied()
+  }
+
+  pub fn functions(&self) -> &[String] {
+    &self.functions
+  }
+
+  pub fn function(&self, name: &str) -> Option<u16> {
+    self.function_indices.get(name).copied()
+  }
+
+  pub(crate) fn emit(&mut self, op: Op) {
+    debug_assert_eq!(op.operand_len(), 0);
+    self.code.push(op as u8);
+  }
+
+  pub(crate) fn emit_with(&mut self, op: Op, operand: u16) {
+    debug_assert_eq!(op.operand_len(), 2);
+    self.code.push(op as u8);
+    self.code.extend_from_slice(&operand.to_le_bytes());
+  }
+
+  pub(crate) fn emit_call(&mut self, function: u16, argc: u8) {
+    self.code.push(Op::Call as u8);
+    self.code.extend_from_slice(&function.to_le_bytes());
+    self.code.push(argc);
+  }
+
+  //equal constants share one entry
+  pub(crate) fn add_constant(&mut self, value: Value) -> Option<u16> {
+    let key = match value {
+      Value::Int(val) => (0, val as u32),
+      Value::Float(val) => (1, val.to_bits()),
+    };
+    if let Some(&index) = self.constant_indices.get(&key) {
+      return Some(index);
+    }
+    let index = u16::try_from(self.constants.len()).ok()?;
+    self.constants.push(value);
+    self.constant_indices.insert(key, index);
+    Some(index)
+  }
+
+  pub(crate) fn add_slot(&mut self, name: &str) -> Option<u16> {
+    let index = u16::try_from(self.slots.len()).ok()?;
+    self.slots.push(name.to_string());
+    self.slot_indices.entry(name.to_string()).or_insert(index);
+    Some(index)
+  }
+
+  pub(crate) fn add_function(&mut self, name: &str) -> Option<u16> {
+    let index = u16::try_from(self.functions.len()).ok()?;
+    self.functions.push(name.to_string());
+    self.function_indices.entry(name.to_string()).or_insert(index);
+    Some(index)
+  }
+}
+
+pub(crate) fn read_u16(code: &[u8], at: usize) -> u16 {
+  u16::from_le_bytes([code[at], code[at + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_indices() {
+    let mut chunk = Chunk::new();
+    for i in 0..1000 {
+      assert_eq!(chunk.add_constant(Value::Int(i)), Some(i as u16));
+    }
+    assert_eq!(chunk.add_constant(Value::Int(999)), Some(999));
+    assert_eq!(chunk.add_constant(Value::Float(0.0)), Some(1000));
+    assert_eq!(chunk.add_constant(Value::Float(-0.0)), Some(1001));
+    assert_eq!(chunk.add_constant(Value::Float(0.0)), Some(1000));
+    assert_eq!(chunk.constants().len(), 1002);
+
+    assert_eq!(chunk.add_slot("a"), Some(0));
+    assert_eq!(chunk.add_slot("b"), Some(1));
+    assert_eq!(chunk.add_function("f"), Some(0));
+    assert_eq!(chunk.slot("b"), Some(1));
+    assert_eq!(chunk.slot("f"), None);
+    assert_eq!(chunk.function("f"), Some(0));
+    assert_eq!(chunk.function("a"), None);
+  }
+}
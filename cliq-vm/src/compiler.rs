@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use cliq_opt::value::Value;
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::Statement;
+
+use crate::chunk::Chunk;
+use crate::opcode::Op;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+  //read before any `var` declared it, the program would fail at runtime anyway
+  UndefinedVariable(String),
+  //called without the host declaring it, see Compiler::declare_function
+  UndefinedFunction(String),
+  WrongArgumentCount { name: String, expected: u8, found: usize },
+  TooManyConstants,
+  TooManySlots,
+  TooManyFunctions,
+}
+
+impl fmt::Display for CompileError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CompileError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+      CompileError::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+      CompileError::WrongArgumentCount { name, expected, found } => {
+        write!(f, "`{}` takes {} arguments but {} were given", name, expected, found)
+      }
+      CompileError::TooManyConstants => write!(f, "more than {} constants", u16::MAX as usize + 1),
+      CompileError::TooManySlots => write!(f, "more than {} variables", u16::MAX as usize + 1),
+      CompileError::TooManyFunctions => write!(f, "more than {} functions", u16::MAX as usize + 1),
+    }
+  }
+}
+
+//every variable gets one local slot, declaring it again reuses the slot
+#[derive(Debug, Default)]
+pub struct Compiler {
+  chunk: Chunk,
+  slots: HashMap<String, u16>,
+  //index into the chunk's functions and the number of arguments
+  functions: HashMap<String, (u16, u8)>,
+}
+
+impl Compiler {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  //a variable the host sets before the chunk runs, see Vm::set
+  pub fn declare(&mut self, name: &str) -> Result<u16, CompileError> {
+    self.slot(name)
+  }
+
+  //a function the host sets before the chunk runs, see Vm::set_function
+  //calls are checked against the arity here so the function always gets as many arguments as it takes
+  pub fn declare_function(&mut self, name: &str, arity: u8) -> Result<u16, CompileError> {
+    if let Some((index, _)) = self.functions.get(name) {
+      let index = *index;
+      self.functions.insert(name.to_string(), (index, arity));
+      return Ok(index);
+    }
+    let index = self.chunk.add_function(name).ok_or(CompileError::TooManyFunctions)?;
+    self.functions.insert(name.to_string(), (index, arity));
+    Ok(index)
+  }
+
+  fn slot(&mut self, name: &str) -> Result<u16, CompileError> {
+    if let Some(slot) = self.slots.get(name) {
+      return Ok(*slot);
+    }
+    let slot = self.chunk.add_slot(name).ok_or(CompileError::TooManySlots)?;
+    self.slots.insert(name.to_string(), slot);
+    Ok(slot)
+  }
+
+  pub fn compile(mut self, ast: &[Statement]) -> Result<Chunk, CompileError> {
+    for stmt in ast {
+      self.statement(stmt)?;
+    }
+    Ok(self.chunk)
+  }
+
+  fn statement(&mut self, stmt: &Statement) -> Result<(), CompileError> {
+    match stmt {
+      Statement::Expression(expr) => {
+        self.expression(expr)?;
+        self.chunk.emit(Op::Pop);
+      }
+      Statement::Assign(stmt) => {
+        //the value is compiled first, `var a = a` must not see the new slot
+        self.expression(&stmt.value)?;
+        if let Expression::VariableExpression(variable) = &stmt.variable {
+          let slot = self.slot(&variable.name)?;
+          self.chunk.emit_with(Op::Store, slot);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn expression(&mut self, expr: &Expression) -> Result<(), CompileError> {
+    stack::grow(|| {
+      match expr {
+        Expression::ValueExpression(_) => {
+          let index = self
+            .chunk
+            .add_constant(Value::from_expression(expr).unwrap())
+            .ok_or(CompileError::TooManyConstants)?;
+          self.chunk.emit_with(Op::Const, index);
+        }
+        Expression::VariableExpression(variable) => {
+          let slot = self
+            .slots
+            .get(&variable.name)
+            .ok_or_else(|| CompileError::UndefinedVariable(variable.name.clone()))?;
+          self.chunk.emit_with(Op::Load, *slot);
+        }
+        Expression::Binary { op, lhs, rhs } => {
+          self.expression(lhs)?;
+          self.expression(rhs)?;
+          self.chunk.emit(Op::binary(*op));
+        }
+        Expression::Unary { op, operand } => {
+          self.expression(operand)?;
+          self.chunk.emit(Op::unary(*op));
+        }
+        Expression::Call(call) => {
+          let (index, arity) = *self
+            .functions
+            .get(&call.name)
+            .ok_or_else(|| CompileError::UndefinedFunction(call.name.clone()))?;
+          if call.args.len() != arity as usize {
+            return Err(CompileError::WrongArgumentCount {
+              name: call.name.clone(),
+              expected: arity,
+              found: call.args.len(),
+            });
+          }
+          for arg in &call.args {
+            self.expression(arg)?;
+          }
+          self.chunk.emit_call(index, arity);
+        }
+      }
+      Ok(())
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+
+  fn compile(input: &str) -> Result<Chunk, CompileError> {
+    Compiler::new().compile(&Parser::new(Lexer::new().lex(input).unwrap()).parse())
+  }
+
+  #[test]
+  fn test_constant_pool() {
+    let chunk = compile("var a = 2 + 2.0\nvar b = 2 * a\n-0.0 + 0.0 + 2.0").unwrap();
+    assert_eq!(chunk.constants(), &[Value::Int(2), Value::Float(2.0), Value::Float(0.0)]);
+    assert_eq!(chunk.slots(), &["a".to_string(), "b".to_string()]);
+  }
+
+  #[test]
+  fn test_redeclared_variable_reuses_slot() {
+    let chunk = compile("var a = 1\nvar a = a + 1").unwrap();
+    assert_eq!(chunk.slots().len(), 1);
+    assert_eq!(chunk.slot("a"), Some(0));
+  }
+
+  #[test]
+  fn test_undefined_variable() {
+    assert_eq!(compile("var a = a"), Err(CompileError::UndefinedVariable("a".to_string())));
+    assert_eq!(compile("1 + b"), Err(CompileError::UndefinedVariable("b".to_string())));
+  }
+
+  #[test]
+  fn test_function_arity() {
+    let ast = Parser::new(
+      Lexer::new()
+        .lex(
+          "pow(2, 3)
+abs(1, 2)",
+        )
+        .unwrap(),
+    )
+    .parse();
+    let mut compiler = Compiler::new();
+    compiler.declare_function("pow", 2).unwrap();
+    compiler.declare_function("abs", 1).unwrap();
+    assert_eq!(
+      compiler.compile(&ast),
+      Err(CompileError::WrongArgumentCount {
+        name: "abs".to_string(),
+        expected: 1,
+        found: 2
+      })
+    );
+    assert_eq!(compile("sqrt(4)"), Err(CompileError::UndefinedFunction("sqrt".to_string())));
+    assert_eq!(
+      CompileError::WrongArgumentCount {
+        name: "abs".to_string(),
+        expected: 1,
+        found: 2
+      }
+      .to_string(),
+      "`abs` takes 1 arguments but 2 were given"
+    );
+  }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::chunk::{read_u16, Chunk};
+use crate::opcode::Op;
+
+//one instruction per line: byte offset, opcode, operand and what the operand refers to
+pub fn disassemble(chunk: &Chunk) -> String {
+  let code = chunk.code();
+  let mut out = String::new();
+  let mut pc = 0;
+  while pc < code.len() {
+    let op = match Op::from_byte(code[pc]) {
+      Some(op) => op,
+      None => {
+        writeln!(out, "{:04} <invalid {:#04x}>", pc, code[pc]).unwrap();
+        pc += 1;
+        continue;
+      }
+    };
+    match op {
+      Op::Const => {
+        let index = read_u16(code, pc + 1);
+        writeln!(
+          out,
+          "{:04} {:<5} {:>4} ; {}",
+          pc,
+          op.name(),
+          index,
+          chunk.constants()[index as usize]
+        )
+        .unwrap();
+      }
+      Op::Load | Op::Store => {
+        let slot = read_u16(code, pc + 1);
+        writeln!(out, "{:04} {:<5} {:>4} ; {}", pc, op.name(), slot, chunk.slots()[slot as usize]).unwrap();
+      }
+      Op::Call => {
+        let index = read_u16(code, pc + 1);
+        let argc = code[pc + 3];
+        writeln!(
+          out,
+          "{:04} {:<5} {:>4} ; {}/{}",
+          pc,
+          op.name(),
+          index,
+          chunk.functions()[index as usize],
+          argc
+        )
+        .unwrap();
+      }
+      _ => writeln!(out, "{:04} {}", pc, op.name()).unwrap(),
+    }
+    pc += 1 + op.operand_len();
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+  use crate::compiler::Compiler;
+
+  #[test]
+  fn test_disassemble() {
+    let ast = Parser::new(Lexer::new().lex("var hello = (ab + 1.5) * 2\nhello % -3").unwrap()).parse();
+    let mut compiler = Compiler::new();
+    compiler.declare("ab").unwrap();
+    let chunk = compiler.compile(&ast).unwrap();
+    assert_eq!(
+      disassemble(&chunk),
+      "0000 LOAD     0 ; ab\n\
+       0003 CONST    0 ; 1.5\n\
+       0006 ADD\n\
+       0007 CONST    1 ; 2\n\
+       0010 MUL\n\
+       0011 STORE    1 ; hello\n\
+       0014 LOAD     1 ; hello\n\
+       0017 CONST    2 ; 3\n\
+       0020 NEG\n\
+       0021 REM\n\
+       0022 POP\n"
+    );
+  }
+
+  #[test]
+  fn test_disassemble_call() {
+    let ast = Parser::new(Lexer::new().lex("pow(2, 0.5)").unwrap()).parse();
+    let mut compiler = Compiler::new();
+    compiler.declare_function("pow", 2).unwrap();
+    let chunk = compiler.compile(&ast).unwrap();
+    assert_eq!(
+      disassemble(&chunk),
+      "0000 CONST    0 ; 2\n\
+       0003 CONST    1 ; 0.5\n\
+       0006 CALL     0 ; pow/2\n\
+       0010 POP\n"
+    );
+  }
+}
@@ -0,0 +1,450 @@
+use std::fmt;
+
+use cliq_interp::{Interpreter, RuntimeError};
+use cliq_ir::lower::Vars;
+use cliq_ir::{Function, Inst, InstKind, Terminator, ValueId};
+use cliq_opt::typeck::TypeError;
+use cliq_opt::value::{ArithError, Type, Value};
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::statement::Statement;
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, Block, FuncRef, InstBuilder, MemFlags, Signature, UserFuncName};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JitError {
+  Type(TypeError),
+  //valid ir that has no native lowering yet
+  Unsupported(&'static str),
+  Codegen(String),
+}
+
+impl fmt::Display for JitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      JitError::Type(err) => write!(f, "{}", err),
+      JitError::Unsupported(what) => write!(f, "{} is not supported by the jit", what),
+      JitError::Codegen(msg) => write!(f, "code generation failed: {}", msg),
+    }
+  }
+}
+
+impl From<TypeError> for JitError {
+  fn from(err: TypeError) -> Self {
+    JitError::Type(err)
+  }
+}
+
+const STATUS_OK: u32 = 0;
+const STATUS_DIVISION_BY_ZERO: u32 = 1;
+
+//the compiled function reads input i from inputs[i] and writes the result to out[0] and variable i to out[1 + i],
+//one 8 byte cell each; variables are numbered like the slots of Compiler, the inputs first in the order they are declared
+type Entry = unsafe extern "C" fn(inputs: *const u64, out: *mut u64) -> u32;
+
+extern "C" {
+  //float remainder has no instruction, it is the same libm call rust uses for `%` on f32
+  fn fmodf(lhs: f32, rhs: f32) -> f32;
+}
+
+//natively compiled program, owns the executable memory its code lives in
+pub struct JitProgram {
+  module: Option<JITModule>,
+  entry: Entry,
+  result: Option<Type>,
+  inputs: Vec<Type>,
+  vars: Vec<(String, Type)>,
+}
+
+impl JitProgram {
+  pub fn compile(ast: &[Statement]) -> Result<JitProgram, JitError> {
+    Self::compile_with_inputs(ast, &[])
+  }
+
+  //the program is specialized for the types of its inputs, so it can run again and again with new values, see run_with
+  pub fn compile_with_inputs(ast: &[Statement], inputs: &[(&str, Type)]) -> Result<JitProgram, JitError> {
+    let (function, vars) = cliq_ir::lower_with_inputs(ast, inputs)?;
+
+    let mut flags = settings::builder();
+    flags.set("use_colocated_libcalls", "false").unwrap();
+    flags.set("is_pic", "false").unwrap();
+    flags.set("opt_level", "speed").unwrap();
+    let isa = cranelift_native::builder()
+      .map_err(|msg| JitError::Codegen(msg.to_string()))?
+      .finish(settings::Flags::new(flags))
+      .map_err(|err| JitError::Codegen(err.to_string()))?;
+    let mut builder = JITBuilder::with_isa(isa, default_libcall_names());
+    builder.symbol("fmodf", fmodf as *const u8);
+    let mut module = JITModule::new(builder);
+
+    let mut ctx = module.make_context();
+    let pointer = module.target_config().pointer_type();
+    ctx.func.signature.params.push(AbiParam::new(pointer));
+    ctx.func.signature.params.push(AbiParam::new(pointer));
+    ctx.func.signature.returns.push(AbiParam::new(types::I32));
+    let id = module
+      .declare_function("main", Linkage::Local, &ctx.func.signature)
+      .map_err(|err| JitError::Codegen(err.to_string()))?;
+    ctx.func.name = UserFuncName::user(0, id.as_u32());
+
+    let mut signature = Signature::new(module.isa().default_call_conv());
+    signature.params.push(AbiParam::new(types::F32));
+    signature.params.push(AbiParam::new(types::F32));
+    signature.returns.push(AbiParam::new(types::F32));
+    let fmodf = module
+      .declare_function("fmodf", Linkage::Import, &signature)
+      .map_err(|err| JitError::Codegen(err.to_string()))?;
+    let fmodf = module.declare_func_in_func(fmodf, &mut ctx.func);
+
+    let mut func_ctx = FunctionBuilderContext::new();
+    let lowering = Lowering::new(FunctionBuilder::new(&mut ctx.func, &mut func_ctx), fmodf, function.value_count());
+    lowering.lower(&function, &vars)?;
+
+    module
+      .define_function(id, &mut ctx)
+      .map_err(|err| JitError::Codegen(err.to_string()))?;
+    module.clear_context(&mut ctx);
+    module.finalize_definitions().map_err(|err| JitError::Codegen(err.to_string()))?;
+    let code = module.get_finalized_function(id);
+    //the signature is the one declared above
+    let entry = unsafe { std::mem::transmute::<*const u8, Entry>(code) };
+
+    Ok(JitProgram {
+      module: Some(module),
+      entry,
+      result: function.ret,
+      inputs: inputs.iter().map(|(_, ty)| *ty).collect(),
+      vars: vars.into_iter().map(|(name, _, ty)| (name, ty)).collect(),
+    })
+  }
+
+  pub fn run(&self) -> Result<JitRun<'_>, ArithError> {
+    self.run_with(&[])
+  }
+
+  //one value per input in the order they were declared in; ints are converted for float inputs
+  //panics when the count is wrong or a float is given for an int input, the code was compiled for ints
+  pub fn run_with(&self, inputs: &[Value]) -> Result<JitRun<'_>, ArithError> {
+    assert_eq!(inputs.len(), self.inputs.len(), "the program takes {} inputs", self.inputs.len());
+    let cells: Vec<u64> = inputs
+      .iter()
+      .zip(self.inputs.iter())
+      .map(|(value, ty)| match (value, ty) {
+        (Value::Int(val), Type::Int) => *val as u32 as u64,
+        (_, Type::Float) => value.as_float().to_bits() as u64,
+        (Value::Float(_), Type::Int) => panic!("float given for an int input"),
+      })
+      .collect();
+    let mut out = vec![0u64; 1 + self.vars.len()];
+    let status = unsafe { (self.entry)(cells.as_ptr(), out.as_mut_ptr()) };
+    if status == STATUS_DIVISION_BY_ZERO {
+      return Err(ArithError::DivisionByZero);
+    }
+    debug_assert_eq!(status, STATUS_OK);
+    Ok(JitRun { program: self, out })
+  }
+}
+
+//output cells of one run, decoded on demand
+pub struct JitRun<'p> {
+  program: &'p JitProgram,
+  out: Vec<u64>,
+}
+
+impl JitRun<'_> {
+  pub fn result(&self) -> Option<Value> {
+    self.program.result.map(|ty| self.read(0, ty))
+  }
+
+  pub fn get(&self, name: &str) -> Option<Value> {
+    let index = self.program.vars.iter().position(|(var, _)| var == name)?;
+    Some(self.read(index + 1, self.program.vars[index].1))
+  }
+
+  pub fn vars(&self) -> impl Iterator<Item = (&str, Value)> + '_ {
+    self
+      .program
+      .vars
+      .iter()
+      .enumerate()
+      .map(|(i, (name, ty))| (name.as_str(), self.read(i + 1, *ty)))
+  }
+
+  //the cell was written with a 4 byte store of the value's own type
+  fn read(&self, cell: usize, ty: Type) -> Value {
+    let cell = &self.out[cell] as *const u64;
+    unsafe {
+      match ty {
+        Type::Int => Value::Int(*(cell as *const i32)),
+        Type::Float => Value::Float(*(cell as *const f32)),
+      }
+    }
+  }
+}
+
+impl Drop for JitProgram {
+  fn drop(&mut self) {
+    if let Some(module) = self.module.take() {
+      //entry is never called again once the program is dropped
+      unsafe { module.free_memory() };
+    }
+  }
+}
+
+struct Lowering<'f> {
+  builder: FunctionBuilder<'f>,
+  inputs: cranelift_codegen::ir::Value,
+  out: cranelift_codegen::ir::Value,
+  division_by_zero: Block,
+  fmodf: FuncRef,
+  //cranelift value of every ir value, indexed by its id
+  values: Vec<Option<cranelift_codegen::ir::Value>>,
+}
+
+impl<'f> Lowering<'f> {
+  fn new(mut builder: FunctionBuilder<'f>, fmodf: FuncRef, value_count: usize) -> Self {
+    let entry = builder.create_block();
+    builder.append_block_params_for_function_params(entry);
+    builder.switch_to_block(entry);
+    builder.seal_block(entry);
+    let (inputs, out) = (builder.block_params(entry)[0], builder.block_params(entry)[1]);
+    let division_by_zero = builder.create_block();
+    Self {
+      builder,
+      inputs,
+      out,
+      division_by_zero,
+      fmodf,
+      values: vec![None; value_count],
+    }
+  }
+
+  fn get(&self, id: ValueId) -> cranelift_codegen::ir::Value {
+    self.values[id.index()].expect("verified ir defines values before use")
+  }
+
+  //lower only produces straight line code, its one block is emitted into the entry block
+  //that only branches off to report a division by zero
+  fn lower(mut self, function: &Function, vars: &Vars) -> Result<(), JitError> {
+    let [block] = &function.blocks[..] else {
+      return Err(JitError::Unsupported("control flow"));
+    };
+    //the parameters of the entry block are the inputs
+    for (cell, (param, ty)) in block.params.iter().enumerate() {
+      let value = self
+        .builder
+        .ins()
+        .load(cl_type(*ty), MemFlags::trusted(), self.inputs, 8 * cell as i32);
+      self.values[param.index()] = Some(value);
+    }
+    for inst in &block.insts {
+      let value = self.inst(inst);
+      self.values[inst.result.index()] = Some(value);
+    }
+    let Terminator::Return(result) = &block.terminator else {
+      return Err(JitError::Unsupported("control flow"));
+    };
+
+    if let Some(result) = result {
+      let value = self.get(*result);
+      self.builder.ins().store(MemFlags::trusted(), value, self.out, 0);
+    }
+    for (i, (_, id, _)) in vars.iter().enumerate() {
+      let value = self.get(*id);
+      self.builder.ins().store(MemFlags::trusted(), value, self.out, 8 * (i as i32 + 1));
+    }
+    let ok = self.builder.ins().iconst(types::I32, STATUS_OK as i64);
+    self.builder.ins().return_(&[ok]);
+
+    self.builder.switch_to_block(self.division_by_zero);
+    self.builder.seal_block(self.division_by_zero);
+    let status = self.builder.ins().iconst(types::I32, STATUS_DIVISION_BY_ZERO as i64);
+    self.builder.ins().return_(&[status]);
+    self.builder.finalize();
+    Ok(())
+  }
+
+  fn inst(&mut self, inst: &Inst) -> cranelift_codegen::ir::Value {
+    match (&inst.kind, inst.ty) {
+      (InstKind::Const(Value::Int(val)), _) => self.builder.ins().iconst(types::I32, *val as i64),
+      (InstKind::Const(Value::Float(val)), _) => self.builder.ins().f32const(*val),
+      (InstKind::Binary(op, lhs, rhs), ty) => {
+        let (lhs, rhs) = (self.get(*lhs), self.get(*rhs));
+        self.binary(*op, ty, lhs, rhs)
+      }
+      (InstKind::Unary(UnOp::Neg, operand), Type::Int) => {
+        let operand = self.get(*operand);
+        self.builder.ins().ineg(operand)
+      }
+      (InstKind::Unary(UnOp::Neg, operand), Type::Float) => {
+        let operand = self.get(*operand);
+        self.builder.ins().fneg(operand)
+      }
+      (InstKind::IntToFloat(operand), _) => {
+        let operand = self.get(*operand);
+        self.builder.ins().fcvt_from_sint(types::F32, operand)
+      }
+    }
+  }
+
+  //both operands have the instruction's type, the ir converts ints explicitly
+  fn binary(
+    &mut self,
+    op: BinOp,
+    ty: Type,
+    lhs: cranelift_codegen::ir::Value,
+    rhs: cranelift_codegen::ir::Value,
+  ) -> cranelift_codegen::ir::Value {
+    let ins = self.builder.ins();
+    match (ty, op) {
+      (Type::Int, BinOp::Add) => ins.iadd(lhs, rhs),
+      (Type::Int, BinOp::Sub) => ins.isub(lhs, rhs),
+      (Type::Int, BinOp::Mul) => ins.imul(lhs, rhs),
+      (Type::Int, BinOp::Div | BinOp::Rem) => self.division(op, lhs, rhs),
+      (Type::Float, BinOp::Add) => ins.fadd(lhs, rhs),
+      (Type::Float, BinOp::Sub) => ins.fsub(lhs, rhs),
+      (Type::Float, BinOp::Mul) => ins.fmul(lhs, rhs),
+      (Type::Float, BinOp::Div) => ins.fdiv(lhs, rhs),
+      (Type::Float, BinOp::Rem) => {
+        let call = ins.call(self.fmodf, &[lhs, rhs]);
+        self.builder.inst_results(call)[0]
+      }
+    }
+  }
+
+  //sdiv and srem trap on a zero divisor and on i32::MIN / -1, where cliq reports an error and wraps
+  fn division(&mut self, op: BinOp, lhs: cranelift_codegen::ir::Value, rhs: cranelift_codegen::ir::Value) -> cranelift_codegen::ir::Value {
+    let is_zero = self.builder.ins().icmp_imm(IntCC::Equal, rhs, 0);
+    let next = self.builder.create_block();
+    self.builder.ins().brif(is_zero, self.division_by_zero, &[], next, &[]);
+    self.builder.switch_to_block(next);
+    self.builder.seal_block(next);
+
+    let is_minus_one = self.builder.ins().icmp_imm(IntCC::Equal, rhs, -1);
+    let one = self.builder.ins().iconst(types::I32, 1);
+    let divisor = self.builder.ins().select(is_minus_one, one, rhs);
+    match op {
+      BinOp::Div => {
+        let quotient = self.builder.ins().sdiv(lhs, divisor);
+        let negated = self.builder.ins().ineg(lhs);
+        self.builder.ins().select(is_minus_one, negated, quotient)
+      }
+      //x % 1 and x % -1 are both 0
+      _ => self.builder.ins().srem(lhs, divisor),
+    }
+  }
+}
+
+fn cl_type(ty: Type) -> types::Type {
+  match ty {
+    Type::Int => types::I32,
+    Type::Float => types::F32,
+  }
+}
+
+//runs natively when the whole program can be compiled, on the interpreter otherwise
+pub enum Executable {
+  Native(Box<JitProgram>),
+  Interpreted(Vec<Statement>),
+}
+
+impl Executable {
+  pub fn new(ast: Vec<Statement>) -> Executable {
+    match JitProgram::compile(&ast) {
+      Ok(program) => Executable::Native(Box::new(program)),
+      Err(_) => Executable::Interpreted(ast),
+    }
+  }
+
+  pub fn is_native(&self) -> bool {
+    matches!(self, Executable::Native(_))
+  }
+
+  pub fn run(&self) -> Result<Option<Value>, RuntimeError> {
+    match self {
+      Executable::Native(program) => Ok(program.run()?.result()),
+      Executable::Interpreted(ast) => Interpreter::new().run(ast),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  fn run(input: &str) -> Result<Option<Value>, ArithError> {
+    Ok(JitProgram::compile(&parse(input)).unwrap().run()?.result())
+  }
+
+  #[test]
+  fn test_run() {
+    let program = JitProgram::compile(&parse("var a = 7\nvar b = a / 2\nb * 2.5 - -a\nvar a = 0.5")).unwrap();
+    let output = program.run().unwrap();
+    assert_eq!(output.result(), Some(Value::Float(14.5)));
+    assert_eq!(output.get("a"), Some(Value::Float(0.5)));
+    assert_eq!(
+      output.vars().collect::<Vec<_>>(),
+      vec![("a", Value::Float(0.5)), ("b", Value::Int(3))]
+    );
+    assert_eq!(output.get("c"), None);
+    assert_eq!(run("var a = 1"), Ok(None));
+  }
+
+  #[test]
+  fn test_int_division() {
+    assert_eq!(run("-7 / 2"), Ok(Some(Value::Int(-3))));
+    assert_eq!(run("-7 % 2"), Ok(Some(Value::Int(-1))));
+    assert_eq!(run("var m = -2147483647 - 1\nm / -1"), Ok(Some(Value::Int(i32::MIN))));
+    assert_eq!(run("var m = -2147483647 - 1\nm % -1"), Ok(Some(Value::Int(0))));
+    assert_eq!(run("var z = 0\n1 + 1\n5 % z"), Err(ArithError::DivisionByZero));
+    assert_eq!(run("1.0 / 0"), Ok(Some(Value::Float(f32::INFINITY))));
+  }
+
+  #[test]
+  fn test_float_remainder() {
+    assert_eq!(run("2.5 % 2"), Ok(Some(Value::Float(0.5))));
+    assert_eq!(run("-7.5 % 2"), Ok(Some(Value::Float(-1.5))));
+    assert_eq!(run("var a = 7\na % 2.5"), Ok(Some(Value::Float(2.0))));
+    assert!(matches!(run("1 % 0.0"), Ok(Some(Value::Float(val))) if val.is_nan()));
+  }
+
+  #[test]
+  fn test_inputs() {
+    let ast = parse("var y = x * x + n % 3\nvar n = n - 1\ny / 2");
+    let program = JitProgram::compile_with_inputs(&ast, &[("x", Type::Float), ("n", Type::Int)]).unwrap();
+    for (x, n) in [(0.5, 7), (3.0, 3), (-2.0, -1)] {
+      let run = program.run_with(&[Value::Float(x), Value::Int(n)]).unwrap();
+      assert_eq!(run.result(), Some(Value::Float((x * x + (n % 3) as f32) / 2.0)));
+      assert_eq!(run.get("n"), Some(Value::Int(n - 1)));
+    }
+    //the inputs come first, like the slots Compiler::declare hands out
+    let run = program.run_with(&[Value::Int(2), Value::Int(0)]).unwrap();
+    assert_eq!(
+      run.vars().collect::<Vec<_>>(),
+      vec![("x", Value::Float(2.0)), ("n", Value::Int(-1)), ("y", Value::Float(4.0))]
+    );
+    assert_eq!(
+      JitProgram::compile_with_inputs(&ast, &[("x", Type::Float)]).err(),
+      Some(JitError::Type(TypeError::UndefinedVariable("n".to_string())))
+    );
+  }
+
+  #[test]
+  fn test_fallback() {
+    let executable = Executable::new(parse("1 / 0\nb"));
+    assert!(!executable.is_native());
+    assert_eq!(executable.run(), Err(RuntimeError::Arith(ArithError::DivisionByZero)));
+
+    assert!(Executable::new(parse("var a = 2\na * a")).is_native());
+  }
+}
@@ -0,0 +1,18 @@
+use cliq_parser::statement::Statement;
+
+pub use self::chunk::Chunk;
+pub use self::compiler::{CompileError, Compiler};
+pub use self::disasm::disassemble;
+pub use self::vm::{Function, Limits, Vm, VmError};
+
+pub mod chunk;
+pub mod compiler;
+pub mod disasm;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod opcode;
+pub mod vm;
+
+pub fn compile(ast: &[Statement]) -> Result<Chunk, CompileError> {
+  Compiler::new().compile(ast)
+}
@@ -0,0 +1,109 @@
+use cliq_parser::expression::operator::{BinOp, UnOp};
+
+//one byte per opcode, Const/Load/Store are followed by a little endian u16 operand,
+//Call by the u16 index of the function and a u8 argument count
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+  Const,
+  Load,
+  Store,
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+  Neg,
+  //pops the arguments and pushes what the host function returns
+  Call,
+  //pops the value of an expression statement, the last one popped is the result of the chunk
+  Pop,
+}
+
+impl Op {
+  pub const ALL: [Op; 11] = [
+    Op::Const,
+    Op::Load,
+    Op::Store,
+    Op::Add,
+    Op::Sub,
+    Op::Mul,
+    Op::Div,
+    Op::Rem,
+    Op::Neg,
+    Op::Call,
+    Op::Pop,
+  ];
+
+  pub fn from_byte(byte: u8) -> Option<Op> {
+    Op::ALL.get(byte as usize).copied()
+  }
+
+  pub fn binary(op: BinOp) -> Op {
+    match op {
+      BinOp::Add => Op::Add,
+      BinOp::Sub => Op::Sub,
+      BinOp::Mul => Op::Mul,
+      BinOp::Div => Op::Div,
+      BinOp::Rem => Op::Rem,
+    }
+  }
+
+  pub fn as_binary(self) -> Option<BinOp> {
+    match self {
+      Op::Add => Some(BinOp::Add),
+      Op::Sub => Some(BinOp::Sub),
+      Op::Mul => Some(BinOp::Mul),
+      Op::Div => Some(BinOp::Div),
+      Op::Rem => Some(BinOp::Rem),
+      _ => None,
+    }
+  }
+
+  pub fn unary(op: UnOp) -> Op {
+    match op {
+      UnOp::Neg => Op::Neg,
+    }
+  }
+
+  //bytes following the opcode
+  pub fn operand_len(self) -> usize {
+    match self {
+      Op::Const | Op::Load | Op::Store => 2,
+      Op::Call => 3,
+      _ => 0,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Op::Const => "CONST",
+      Op::Load => "LOAD",
+      Op::Store => "STORE",
+      Op::Add => "ADD",
+      Op::Sub => "SUB",
+      Op::Mul => "MUL",
+      Op::Div => "DIV",
+      Op::Rem => "REM",
+      Op::Neg => "NEG",
+      Op::Call => "CALL",
+      Op::Pop => "POP",
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_byte_roundtrip() {
+    for op in Op::ALL {
+      assert_eq!(Op::from_byte(op as u8), Some(op));
+    }
+    assert_eq!(Op::from_byte(Op::ALL.len() as u8), None);
+    for op in BinOp::ALL {
+      assert_eq!(Op::binary(op).as_binary(), Some(op));
+    }
+  }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::time::Instant;
+
+use cliq_opt::value::{ArithError, Value};
+use cliq_parser::expression::operator::UnOp;
+
+use crate::chunk::{read_u16, Chunk};
+use crate::opcode::Op;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError {
+  //a slot declared for the host that was never set
+  UndefinedVariable(String),
+  //a function declared for the host that was never set
+  UndefinedFunction(String),
+  //the message of a host function that failed
+  Function { name: String, msg: String },
+  Arith(ArithError),
+  //one of the Limits was reached, the run stops where it was
+  OutOfFuel,
+  StackOverflow,
+  DeadlineExceeded,
+}
+
+impl fmt::Display for VmError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VmError::UndefinedVariable(name) => write!(f, "undefined variable `{}`", name),
+      VmError::UndefinedFunction(name) => write!(f, "undefined function `{}`", name),
+      VmError::Function { name, msg } => write!(f, "in call to `{}`: {}", name, msg),
+      VmError::Arith(err) => write!(f, "{}", err),
+      VmError::OutOfFuel => write!(f, "the program ran out of fuel"),
+      VmError::StackOverflow => write!(f, "the expression is nested too deeply"),
+      VmError::DeadlineExceeded => write!(f, "the program ran past its deadline"),
+    }
+  }
+}
+
+impl From<ArithError> for VmError {
+  fn from(err: ArithError) -> Self {
+    VmError::Arith(err)
+  }
+}
+
+//a host function, it gets exactly as many arguments as it was declared with
+pub type Function<'c> = &'c dyn Fn(&[Value]) -> Result<Value, String>;
+
+//the clock is only read every this many instructions, and after every host function
+const DEADLINE_INTERVAL: u64 = 256;
+
+//bounds for running untrusted chunks, None is no bound
+//cliq has no loops or functions of its own, so a run is as long as its code and calls never nest at runtime,
+//the stack is what deeply nested operators and calls use up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+  //instructions a run may execute
+  pub fuel: Option<u64>,
+  //values on the stack at once
+  pub max_stack: Option<usize>,
+  //checked between instructions, a host function that blocks is not interrupted
+  pub deadline: Option<Instant>,
+}
+
+pub struct Vm<'c> {
+  chunk: &'c Chunk,
+  stack: Vec<Value>,
+  slots: Vec<Option<Value>>,
+  functions: Vec<Option<Function<'c>>>,
+  limits: Limits,
+}
+
+impl<'c> Vm<'c> {
+  pub fn new(chunk: &'c Chunk) -> Self {
+    Self {
+      chunk,
+      stack: Vec::new(),
+      slots: vec![None; chunk.slots().len()],
+      functions: vec![None; chunk.functions().len()],
+      limits: Limits::default(),
+    }
+  }
+
+  //applies to every later run, fuel is not carried over from one run to the next
+  pub fn set_limits(&mut self, limits: Limits) {
+    self.limits = limits;
+  }
+
+  //false if the chunk has no variable with that name
+  pub fn set(&mut self, name: &str, value: Value) -> bool {
+    match self.chunk.slot(name) {
+      Some(slot) => {
+        self.slots[slot as usize] = Some(value);
+        true
+      }
+      None => false,
+    }
+  }
+
+  //false if the chunk never calls a function with that name
+  pub fn set_function(&mut self, name: &str, function: Function<'c>) -> bool {
+    match self.chunk.function(name) {
+      Some(index) => {
+        self.functions[index as usize] = Some(function);
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn get(&self, name: &str) -> Option<Value> {
+    self.slots[self.chunk.slot(name)? as usize]
+  }
+
+  //value popped by the last expression statement
+  pub fn run(&mut self) -> Result<Option<Value>, VmError> {
+    let code = self.chunk.code();
+    let mut result = None;
+    let mut pc = 0;
+    let mut steps = 0;
+    self.stack.clear();
+    while pc < code.len() {
+      if self.limits.fuel.is_some_and(|fuel| steps >= fuel) {
+        return Err(VmError::OutOfFuel);
+      }
+      if steps % DEADLINE_INTERVAL == 0 {
+        self.check_deadline()?;
+      }
+      steps += 1;
+      let op = Op::from_byte(code[pc]).expect("chunks are only built by the compiler");
+      pc += 1;
+      match op {
+        Op::Const => {
+          self.push(self.chunk.constants()[read_u16(code, pc) as usize])?;
+          pc += 2;
+        }
+        Op::Load => {
+          let slot = read_u16(code, pc) as usize;
+          let value = self.slots[slot].ok_or_else(|| VmError::UndefinedVariable(self.chunk.slots()[slot].clone()))?;
+          self.push(value)?;
+          pc += 2;
+        }
+        Op::Store => {
+          self.slots[read_u16(code, pc) as usize] = Some(self.pop());
+          pc += 2;
+        }
+        Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Rem => {
+          let rhs = self.pop();
+          let lhs = self.pop();
+          self.stack.push(Value::binary(op.as_binary().unwrap(), lhs, rhs)?);
+        }
+        Op::Neg => {
+          let operand = self.pop();
+          self.stack.push(Value::unary(UnOp::Neg, operand));
+        }
+        Op::Call => {
+          let index = read_u16(code, pc) as usize;
+          let argc = code[pc + 2] as usize;
+          let name = &self.chunk.functions()[index];
+          let function = self.functions[index].ok_or_else(|| VmError::UndefinedFunction(name.clone()))?;
+          let args = self.stack.split_off(self.stack.len() - argc);
+          let value = function(&args).map_err(|msg| VmError::Function { name: name.clone(), msg })?;
+          self.stack.push(value);
+          self.check_deadline()?;
+          pc += 3;
+        }
+        Op::Pop => result = Some(self.pop()),
+      }
+    }
+    Ok(result)
+  }
+
+  //only loads grow the stack, every other instruction pops at least as many values as it pushes
+  fn push(&mut self, value: Value) -> Result<(), VmError> {
+    if self.limits.max_stack.is_some_and(|max| self.stack.len() >= max) {
+      return Err(VmError::StackOverflow);
+    }
+    self.stack.push(value);
+    Ok(())
+  }
+
+  fn check_deadline(&self) -> Result<(), VmError> {
+    match self.limits.deadline {
+      Some(deadline) if Instant::now() >= deadline => Err(VmError::DeadlineExceeded),
+      _ => Ok(()),
+    }
+  }
+
+  fn pop(&mut self) -> Value {
+    self.stack.pop().expect("compiled code never pops an empty stack")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use super::*;
+  use crate::compiler::Compiler;
+
+  fn chunk(input: &str, host: &[&str]) -> Chunk {
+    let ast = Parser::new(Lexer::new().lex(input).unwrap()).parse();
+    let mut compiler = Compiler::new();
+    for name in host {
+      compiler.declare(name).unwrap();
+    }
+    compiler.compile(&ast).unwrap()
+  }
+
+  #[test]
+  fn test_run() {
+    let chunk = chunk("var a = 7\nvar b = a / 2\nb * 2.5 - -a\nvar c = 1", &[]);
+    let mut vm = Vm::new(&chunk);
+    assert_eq!(vm.run(), Ok(Some(Value::Float(14.5))));
+    assert_eq!(vm.get("b"), Some(Value::Int(3)));
+    assert_eq!(vm.get("c"), Some(Value::Int(1)));
+  }
+
+  #[test]
+  fn test_host_variables() {
+    let chunk = chunk("x * 2", &["x"]);
+    let mut vm = Vm::new(&chunk);
+    assert_eq!(vm.run(), Err(VmError::UndefinedVariable("x".to_string())));
+    assert!(vm.set("x", Value::Float(1.25)));
+    assert!(!vm.set("y", Value::Int(1)));
+    assert_eq!(vm.run(), Ok(Some(Value::Float(2.5))));
+  }
+
+  #[test]
+  fn test_host_functions() {
+    let ast = Parser::new(Lexer::new().lex("var a = 2\nhalf(a + 3) * a").unwrap()).parse();
+    let mut compiler = Compiler::new();
+    compiler.declare_function("half", 1).unwrap();
+    let chunk = compiler.compile(&ast).unwrap();
+    let half = |args: &[Value]| match args {
+      [Value::Int(0)] => Err("nothing to halve".to_string()),
+      [value] => Ok(Value::Float(value.as_float() / 2.0)),
+      _ => unreachable!(),
+    };
+    let mut vm = Vm::new(&chunk);
+    assert_eq!(vm.run(), Err(VmError::UndefinedFunction("half".to_string())));
+    assert!(vm.set_function("half", &half));
+    assert!(!vm.set_function("double", &half));
+    assert_eq!(vm.run(), Ok(Some(Value::Float(5.0))));
+
+    let ast = Parser::new(Lexer::new().lex("half(0)").unwrap()).parse();
+    let mut compiler = Compiler::new();
+    compiler.declare_function("half", 1).unwrap();
+    let chunk = compiler.compile(&ast).unwrap();
+    let mut vm = Vm::new(&chunk);
+    vm.set_function("half", &half);
+    let err = vm.run().unwrap_err();
+    assert_eq!(err.to_string(), "in call to `half`: nothing to halve");
+  }
+
+  #[test]
+  fn test_fuel() {
+    //CONST CONST ADD POP
+    let chunk = chunk("1 + 2", &[]);
+    let mut vm = Vm::new(&chunk);
+    vm.set_limits(Limits {
+      fuel: Some(4),
+      ..Limits::default()
+    });
+    assert_eq!(vm.run(), Ok(Some(Value::Int(3))));
+    //every run starts with the full amount
+    assert_eq!(vm.run(), Ok(Some(Value::Int(3))));
+    vm.set_limits(Limits {
+      fuel: Some(3),
+      ..Limits::default()
+    });
+    assert_eq!(vm.run(), Err(VmError::OutOfFuel));
+  }
+
+  #[test]
+  fn test_stack_limit() {
+    let nested = |depth: usize| format!("{}x{}", "x + (".repeat(depth), ")".repeat(depth));
+    let limits = Limits {
+      max_stack: Some(8),
+      ..Limits::default()
+    };
+    let run = |depth: usize| {
+      let chunk = chunk(&nested(depth), &["x"]);
+      let mut vm = Vm::new(&chunk);
+      vm.set("x", Value::Int(1));
+      vm.set_limits(limits);
+      vm.run()
+    };
+    assert_eq!(run(7), Ok(Some(Value::Int(8))));
+    assert_eq!(run(8), Err(VmError::StackOverflow));
+  }
+
+  #[test]
+  fn test_deadline() {
+    let chunk = chunk("1", &[]);
+    let mut vm = Vm::new(&chunk);
+    vm.set_limits(Limits {
+      deadline: Some(Instant::now()),
+      ..Limits::default()
+    });
+    assert_eq!(vm.run(), Err(VmError::DeadlineExceeded));
+
+    //the deadline is noticed right after a host function returns
+    let ast = Parser::new(Lexer::new().lex("slow() + 1").unwrap()).parse();
+    let mut compiler = Compiler::new();
+    compiler.declare_function("slow", 0).unwrap();
+    let chunk = compiler.compile(&ast).unwrap();
+    let slow = |_: &[Value]| {
+      std::thread::sleep(std::time::Duration::from_millis(20));
+      Ok(Value::Int(0))
+    };
+    let mut vm = Vm::new(&chunk);
+    vm.set_function("slow", &slow);
+    vm.set_limits(Limits {
+      deadline: Some(Instant::now() + std::time::Duration::from_millis(5)),
+      ..Limits::default()
+    });
+    assert_eq!(vm.run(), Err(VmError::DeadlineExceeded));
+  }
+
+  #[test]
+  fn test_division_by_zero() {
+    let chunk = chunk("var z = 0\n1 % z", &[]);
+    assert_eq!(Vm::new(&chunk).run(), Err(VmError::Arith(ArithError::DivisionByZero)));
+  }
+}
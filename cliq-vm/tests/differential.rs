@@ -0,0 +1,114 @@
+use cliq_interp::{Interpreter, RuntimeError};
+use cliq_opt::optimize;
+use cliq_parser::{
+  expression::{
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  statement::{assign_stmt::AssignStmt, Statement},
+};
+use cliq_vm::{compile, Vm, VmError};
+use proptest::prelude::*;
+
+const NAMES: [&str; 4] = ["a", "b", "c", "d"];
+
+fn name() -> impl Strategy<Value = String> {
+  prop::sample::select(NAMES.to_vec()).prop_map(str::to_string)
+}
+
+//small ints so division by zero and exact results come up often
+fn expression() -> impl Strategy<Value = Expression> {
+  let leaf = prop_oneof![
+    (0..4).prop_map(ValueExpression::int_value),
+    (0..=i32::MAX).prop_map(ValueExpression::int_value),
+    prop::sample::select(vec![0.0f32, 0.5, 1.0, 3.25, 1.0e30]).prop_map(ValueExpression::float_value),
+    name().prop_map(VariableExpression::expression),
+  ];
+  leaf.prop_recursive(5, 32, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), inner.clone(), prop::sample::select(BinOp::ALL.to_vec())).prop_map(|(lhs, rhs, op)| Expression::binary(op, lhs, rhs)),
+      inner.prop_map(|operand| Expression::unary(UnOp::Neg, operand)),
+    ]
+  })
+}
+
+fn statement() -> impl Strategy<Value = Statement> {
+  prop_oneof![
+    expression().prop_map(Statement::Expression),
+    (name(), expression()).prop_map(|(name, value)| AssignStmt::statement(VariableExpression::expression(name), value, false)),
+    (name(), expression()).prop_map(|(name, value)| AssignStmt::statement(VariableExpression::expression(name), value, false)),
+  ]
+}
+
+//debug output compares floats bitwise enough to tell nan, inf and -0.0 apart
+fn interpret(ast: &[Statement]) -> (String, Vec<String>) {
+  let mut interp = Interpreter::new();
+  let result = interp.run(ast);
+  let vars = NAMES.iter().map(|name| format!("{:?}", interp.get(name))).collect();
+  (format!("{:?}", result), vars)
+}
+
+fn execute(ast: &[Statement]) -> Option<(String, Vec<String>)> {
+  let chunk = compile(ast).ok()?;
+  let mut vm = Vm::new(&chunk);
+  let result = vm.run().map_err(|err| match err {
+    VmError::Arith(err) => RuntimeError::Arith(err),
+    VmError::UndefinedVariable(name) => RuntimeError::UndefinedVariable(name),
+    VmError::UndefinedFunction(name) => RuntimeError::UndefinedFunction(name),
+    VmError::Function { .. } | VmError::OutOfFuel | VmError::StackOverflow | VmError::DeadlineExceeded => {
+      unreachable!("no functions or limits are set")
+    }
+  });
+  let vars = NAMES.iter().map(|name| format!("{:?}", vm.get(name))).collect();
+  Some((format!("{:?}", result), vars))
+}
+
+//variables are only compared when the program ran to the end
+#[cfg(feature = "jit")]
+fn jit(ast: &[Statement]) -> Option<(String, Option<Vec<String>>)> {
+  let program = cliq_vm::jit::JitProgram::compile(ast).ok()?;
+  Some(match program.run() {
+    Ok(run) => (
+      format!("{:?}", Ok::<_, RuntimeError>(run.result())),
+      Some(NAMES.iter().map(|name| format!("{:?}", run.get(name))).collect()),
+    ),
+    Err(err) => (
+      format!("{:?}", Err::<Option<cliq_opt::value::Value>, _>(RuntimeError::Arith(err))),
+      None,
+    ),
+  })
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(512))]
+
+  #[test]
+  fn vm_matches_interpreter(ast in prop::collection::vec(statement(), 1..8)) {
+    let expected = interpret(&ast);
+    match execute(&ast) {
+      Some(actual) => prop_assert_eq!(actual, expected),
+      //rejected programs read an undeclared variable, interpreting them fails there or at an earlier error
+      None => prop_assert!(expected.0.starts_with("Err("), "{:?}", expected),
+    }
+  }
+
+  #[test]
+  fn optimized_program_behaves_the_same(ast in prop::collection::vec(statement(), 1..8)) {
+    let (optimized, _) = optimize(ast.clone());
+    prop_assert_eq!(interpret(&optimized), interpret(&ast));
+  }
+
+  #[cfg(feature = "jit")]
+  #[test]
+  fn jit_matches_interpreter(ast in prop::collection::vec(statement(), 1..8)) {
+    let (result, vars) = interpret(&ast);
+    if let Some((jit_result, jit_vars)) = jit(&ast) {
+      prop_assert_eq!(jit_result, result);
+      if let Some(jit_vars) = jit_vars {
+        prop_assert_eq!(jit_vars, vars);
+      }
+    }
+  }
+}
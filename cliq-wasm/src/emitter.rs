@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use cliq_opt::typeck::TypeChecker;
+use cliq_opt::value::{Type, Value};
+use cliq_parser::expression::operator::{BinOp, UnOp};
+use cliq_parser::expression::Expression;
+use cliq_parser::stack;
+use cliq_parser::statement::Statement;
+
+use crate::{Export, WasmError, WasmModule};
+
+fn val_type(ty: Type) -> &'static str {
+  match ty {
+    Type::Int => "i64",
+    Type::Float => "f64",
+  }
+}
+
+//wasm values are i64 and f64, every operation is narrowed again so results match
+//the interpreter's i32 and f32 exactly; f64 is wide enough that rounding twice is harmless
+fn wrap_int(code: String) -> String {
+  format!("(i64.extend32_s {})", code)
+}
+
+fn round_float(code: String) -> String {
+  format!("(f64.promote_f32 (f32.demote_f64 {}))", code)
+}
+
+enum Lowered {
+  Declare { local: String, ty: Type, code: String },
+  Expression { code: String, ty: Type },
+}
+
+//f32 remainder computed on f64 as x - trunc(x / y) * y: below a quotient of 2^24 the product and the difference
+//are exact and nan or inf quotients give nan, above it the divisor is doubled up to the dividend and halved back
+//down, every subtraction is exact then
+const FMOD: &str = "  (func $fmod (param $x f64) (param $y f64) (result f64)
+    (local $q f64) (local $r f64) (local $d f64)
+    (if (f64.lt (f64.abs (local.get $x)) (f64.abs (local.get $y)))
+      (then (return (local.get $x))))
+    (local.set $q (f64.trunc (f64.div (local.get $x) (local.get $y))))
+    (if (i32.eqz (i32.and (f64.ge (f64.abs (local.get $q)) (f64.const 16777216)) (f64.ne (f64.abs (local.get $q)) (f64.const inf))))
+      (then (return (f64.copysign (f64.sub (local.get $x) (f64.mul (local.get $q) (local.get $y))) (local.get $x)))))
+    (local.set $r (f64.abs (local.get $x)))
+    (local.set $d (f64.abs (local.get $y)))
+    (loop $grow
+      (if (f64.le (f64.mul (local.get $d) (f64.const 2)) (local.get $r))
+        (then (local.set $d (f64.mul (local.get $d) (f64.const 2))) (br $grow))))
+    (loop $shrink
+      (if (f64.ge (local.get $r) (local.get $d))
+        (then (local.set $r (f64.sub (local.get $r) (local.get $d)))))
+      (if (f64.gt (local.get $d) (f64.abs (local.get $y)))
+        (then (local.set $d (f64.mul (local.get $d) (f64.const 0.5))) (br $shrink))))
+    (f64.copysign (local.get $r) (local.get $x)))
+";
+
+#[derive(Default)]
+pub struct Emitter {
+  types: TypeChecker,
+  //wat name of the current declaration of every variable
+  names: HashMap<String, String>,
+  declarations: HashMap<String, usize>,
+  uses_fmod: bool,
+}
+
+impl Emitter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  //every `var` becomes a global that $init sets, it runs the declarations in order up to a count and remembers
+  //how many it ran; the n-th top level expression becomes the export `exprN`, which runs the declarations
+  //in front of it and then only its own expression, so a trap only fails the exports that need what trapped
+  pub fn emit(mut self, ast: &[Statement]) -> Result<WasmModule, WasmError> {
+    let mut lowered = Vec::new();
+    for stmt in ast {
+      lowered.push(self.statement(stmt)?);
+    }
+
+    let mut wat = String::from("(module\n");
+    for stmt in &lowered {
+      if let Lowered::Declare { local, ty, .. } = stmt {
+        let ty = val_type(*ty);
+        writeln!(wat, "  (global {} (mut {}) ({}.const 0))", local, ty, ty).unwrap();
+      }
+    }
+    wat.push_str("  (global $declared (mut i32) (i32.const 0))\n");
+    wat.push_str("  (func $init (param $upto i32)");
+    let mut declared = 0;
+    for stmt in &lowered {
+      if let Lowered::Declare { local, code, .. } = stmt {
+        write!(
+          wat,
+          "\n    (if (i32.and (i32.eq (global.get $declared) (i32.const {n})) (i32.gt_u (local.get $upto) (i32.const {n})))",
+          n = declared
+        )
+        .unwrap();
+        declared += 1;
+        write!(
+          wat,
+          "\n      (then (global.set {} {}) (global.set $declared (i32.const {}))))",
+          local, code, declared
+        )
+        .unwrap();
+      }
+    }
+    wat.push_str(")\n");
+    if self.uses_fmod {
+      wat.push_str(FMOD);
+    }
+
+    let mut exports = Vec::new();
+    let mut declared = 0;
+    for (i, stmt) in lowered.iter().enumerate() {
+      let (code, ty) = match stmt {
+        Lowered::Expression { code, ty } => (code, *ty),
+        Lowered::Declare { .. } => {
+          declared += 1;
+          continue;
+        }
+      };
+      let name = format!("expr{}", exports.len());
+      writeln!(wat, "  (func (export \"{}\") (result {})", name, val_type(ty)).unwrap();
+      writeln!(wat, "    (call $init (i32.const {}))", declared).unwrap();
+      writeln!(wat, "    {})", code).unwrap();
+      exports.push(Export { name, statement: i, ty });
+    }
+    wat.push_str(")\n");
+    Ok(WasmModule { wat, exports })
+  }
+
+  fn statement(&mut self, stmt: &Statement) -> Result<Lowered, WasmError> {
+    Ok(match stmt {
+      Statement::Expression(expr) => {
+        let (code, ty) = self.expression(expr)?;
+        Lowered::Expression { code, ty }
+      }
+      Statement::Assign(stmt) => {
+        let (code, ty) = self.expression(&stmt.value)?;
+        let name = match &stmt.variable {
+          Expression::VariableExpression(variable) => variable.name.clone(),
+          _ => return Ok(Lowered::Expression { code, ty }),
+        };
+        self.types.declare(name.clone(), ty);
+        //a variable declared again gets a new local, its type may have changed
+        let count = self.declarations.entry(name.clone()).or_insert(0);
+        let local = format!("$v{}_{}", count, name);
+        *count += 1;
+        self.names.insert(name, local.clone());
+        Lowered::Declare { local, ty, code }
+      }
+    })
+  }
+
+  fn expression(&mut self, expr: &Expression) -> Result<(String, Type), WasmError> {
+    stack::grow(|| {
+      Ok(match expr {
+        Expression::ValueExpression(_) => match Value::from_expression(expr).unwrap() {
+          Value::Int(val) => (format!("(i64.const {})", val), Type::Int),
+          //the f64 debug text is exact for every f32
+          Value::Float(val) => (format!("(f64.const {:?})", val as f64), Type::Float),
+        },
+        Expression::VariableExpression(variable) => {
+          let ty = self.types.expression(expr)?;
+          (format!("(global.get {})", self.names[&variable.name]), ty)
+        }
+        Expression::Binary { op, lhs, rhs } => {
+          let lhs = self.expression(lhs)?;
+          let rhs = self.expression(rhs)?;
+          self.binary(*op, lhs, rhs)?
+        }
+        Expression::Unary { op: UnOp::Neg, operand } => match self.expression(operand)? {
+          (code, Type::Int) => (wrap_int(format!("(i64.sub (i64.const 0) {})", code)), Type::Int),
+          (code, Type::Float) => (format!("(f64.neg {})", code), Type::Float),
+        },
+        //the type checker rejects every call, a module has no host to import functions from
+        Expression::Call(_) => return Err(self.types.expression(expr).unwrap_err().into()),
+      })
+    })
+  }
+
+  fn binary(&mut self, op: BinOp, lhs: (String, Type), rhs: (String, Type)) -> Result<(String, Type), WasmError> {
+    if let ((lhs, Type::Int), (rhs, Type::Int)) = (&lhs, &rhs) {
+      //div_s and rem_s trap on a zero divisor, i32::MIN / -1 fits an i64 and is wrapped back
+      let code = match op {
+        BinOp::Add => wrap_int(format!("(i64.add {} {})", lhs, rhs)),
+        BinOp::Sub => wrap_int(format!("(i64.sub {} {})", lhs, rhs)),
+        BinOp::Mul => wrap_int(format!("(i64.mul {} {})", lhs, rhs)),
+        BinOp::Div => wrap_int(format!("(i64.div_s {} {})", lhs, rhs)),
+        BinOp::Rem => format!("(i64.rem_s {} {})", lhs, rhs),
+      };
+      return Ok((code, Type::Int));
+    }
+
+    let float = |(code, ty): (String, Type)| match ty {
+      Type::Int => round_float(format!("(f64.convert_i64_s {})", code)),
+      Type::Float => code,
+    };
+    let (lhs, rhs) = (float(lhs), float(rhs));
+    let instr = match op {
+      BinOp::Add => "f64.add",
+      BinOp::Sub => "f64.sub",
+      BinOp::Mul => "f64.mul",
+      BinOp::Div => "f64.div",
+      BinOp::Rem => {
+        self.uses_fmod = true;
+        "call $fmod"
+      }
+    };
+    Ok((round_float(format!("({} {} {})", instr, lhs, rhs)), Type::Float))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use cliq_lexer::lexer::Lexer;
+  use cliq_parser::parser::Parser;
+
+  use crate::compile;
+
+  use super::*;
+
+  fn parse(input: &str) -> Vec<Statement> {
+    Parser::new(Lexer::new().lex(input).unwrap()).parse()
+  }
+
+  #[test]
+  fn test_emit() {
+    let module = compile(&parse("var a = 7\na * 2\nvar a = a / 2.5\n(-a)")).unwrap();
+    assert_eq!(
+      module.wat,
+      r#"(module
+  (global $v0_a (mut i64) (i64.const 0))
+  (global $v1_a (mut f64) (f64.const 0))
+  (global $declared (mut i32) (i32.const 0))
+  (func $init (param $upto i32)
+    (if (i32.and (i32.eq (global.get $declared) (i32.const 0)) (i32.gt_u (local.get $upto) (i32.const 0)))
+      (then (global.set $v0_a (i64.const 7)) (global.set $declared (i32.const 1))))
+    (if (i32.and (i32.eq (global.get $declared) (i32.const 1)) (i32.gt_u (local.get $upto) (i32.const 1)))
+      (then (global.set $v1_a (f64.promote_f32 (f32.demote_f64 (f64.div (f64.promote_f32 (f32.demote_f64 (f64.convert_i64_s (global.get $v0_a)))) (f64.const 2.5))))) (global.set $declared (i32.const 2)))))
+  (func (export "expr0") (result i64)
+    (call $init (i32.const 1))
+    (i64.extend32_s (i64.mul (global.get $v0_a) (i64.const 2))))
+  (func (export "expr1") (result f64)
+    (call $init (i32.const 2))
+    (f64.neg (global.get $v1_a)))
+)
+"#
+    );
+    assert_eq!(
+      module.exports,
+      vec![
+        Export {
+          name: "expr0".to_string(),
+          statement: 1,
+          ty: Type::Int
+        },
+        Export {
+          name: "expr1".to_string(),
+          statement: 3,
+          ty: Type::Float
+        },
+      ]
+    );
+    assert_eq!(&module.to_binary()[..4], b"\0asm");
+  }
+
+  #[test]
+  fn test_float_remainder() {
+    let module = compile(&parse("2.5 % 2")).unwrap();
+    assert!(module.wat.contains("(func $fmod"));
+    assert!(module
+      .wat
+      .contains("(call $fmod (f64.const 2.5) (f64.promote_f32 (f32.demote_f64 (f64.convert_i64_s (i64.const 2)))))"));
+    assert!(!compile(&parse("5 % 2")).unwrap().wat.contains("$fmod"));
+  }
+
+  #[test]
+  fn test_errors() {
+    assert!(matches!(compile(&parse("a")), Err(WasmError::Type(_))));
+  }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+
+use cliq_opt::typeck::TypeError;
+use cliq_opt::value::Type;
+use cliq_parser::statement::Statement;
+
+use self::emitter::Emitter;
+
+pub mod emitter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasmError {
+  Type(TypeError),
+}
+
+impl fmt::Display for WasmError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      WasmError::Type(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl From<TypeError> for WasmError {
+  fn from(err: TypeError) -> Self {
+    WasmError::Type(err)
+  }
+}
+
+//exported function returning the value of one top level expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+  pub name: String,
+  //index of the expression statement in the program
+  pub statement: usize,
+  //ints are returned as i64 and floats as f64
+  pub ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmModule {
+  pub wat: String,
+  pub exports: Vec<Export>,
+}
+
+impl WasmModule {
+  pub fn to_binary(&self) -> Vec<u8> {
+    wat::parse_str(&self.wat).expect("emitted text is valid wat")
+  }
+}
+
+pub fn compile(ast: &[Statement]) -> Result<WasmModule, WasmError> {
+  Emitter::new().emit(ast)
+}
@@ -0,0 +1,174 @@
+use cliq_interp::{Interpreter, RuntimeError};
+use cliq_lexer::lexer::Lexer;
+use cliq_opt::value::{ArithError, Type, Value};
+use cliq_parser::{
+  expression::{
+    operator::{BinOp, UnOp},
+    value_expression::ValueExpression,
+    variable_expression::VariableExpression,
+    Expression,
+  },
+  parser::Parser,
+  statement::{assign_stmt::AssignStmt, to_source, Statement},
+};
+use cliq_wasm::{compile, WasmError};
+use proptest::prelude::*;
+use wasmi::core::TrapCode;
+use wasmi::{Engine, Linker, Module, Store};
+
+fn parse(input: &str) -> Vec<Statement> {
+  Parser::new(Lexer::new().lex(input).unwrap()).parse()
+}
+
+//instantiates the module in wasmi and calls every export, values are narrowed back to i32/f32
+fn run_exports(ast: &[Statement]) -> Result<Vec<Result<Value, RuntimeError>>, WasmError> {
+  let module = compile(ast)?;
+  let engine = Engine::default();
+  let wasm = Module::new(&engine, &module.to_binary()[..]).unwrap();
+  let mut store = Store::new(&engine, ());
+  let instance = Linker::<()>::new(&engine)
+    .instantiate(&mut store, &wasm)
+    .unwrap()
+    .start(&mut store)
+    .unwrap();
+
+  let trap = |err: wasmi::Error| match err.as_trap_code() {
+    Some(TrapCode::IntegerDivisionByZero) => RuntimeError::Arith(ArithError::DivisionByZero),
+    _ => panic!("unexpected trap: {}", err),
+  };
+  Ok(
+    module
+      .exports
+      .iter()
+      .map(|export| match export.ty {
+        Type::Int => {
+          let func = instance.get_typed_func::<(), i64>(&store, &export.name).unwrap();
+          func.call(&mut store, ()).map_err(trap).map(|val| {
+            assert_eq!(val, val as i32 as i64, "{} is not wrapped to 32 bits", val);
+            Value::Int(val as i32)
+          })
+        }
+        Type::Float => {
+          let func = instance.get_typed_func::<(), f64>(&store, &export.name).unwrap();
+          func.call(&mut store, ()).map_err(trap).map(|val| {
+            assert!(val.is_nan() || val == val as f32 as f64, "{} is not rounded to f32", val);
+            Value::Float(val as f32)
+          })
+        }
+      })
+      .collect(),
+  )
+}
+
+//export n must return what the interpreter computes for the declarations in front of that expression
+//followed by the expression, a trap in another expression statement does not reach it
+fn assert_matches_interpreter(ast: &[Statement]) {
+  let module = compile(ast).unwrap();
+  let results = run_exports(ast).unwrap();
+  for (export, result) in module.exports.iter().zip(results) {
+    let mut program: Vec<Statement> = ast[..export.statement]
+      .iter()
+      .filter(|stmt| matches!(stmt, Statement::Assign(_)))
+      .cloned()
+      .collect();
+    program.push(ast[export.statement].clone());
+    let expected = Interpreter::new().run(&program).map(Option::unwrap);
+    assert_eq!(
+      format!("{:?}", result),
+      format!("{:?}", expected),
+      "{} of:\n{}",
+      export.name,
+      to_source(ast)
+    );
+  }
+}
+
+#[test]
+fn matches_interpreter() {
+  let programs = [
+    "var a = 7\nvar b = a / 2\nb * 2.5 - -a",
+    "var ab = 1\nvar ac = 2.5\nvar hello = ((ab + 123.3) * (ac - (33 + 22) * 2)) + 2\nhello / ab",
+    "2147483647 + 1\n-2147483647 - 1 - 1\n46341 * 46341",
+    "var m = -2147483647 - 1\nm / -1\nm % -1\n-m",
+    "-7 / 2\n-7 % 2\n16777217 * 1.0\n0.1 + 0.2",
+    "1.0 / 0\n0.0 / 0\n100000000000000000000.0 * 100000000000000000000.0",
+    "var x = 1\nvar x = x * 0.1\nvar x = x * 3\nx",
+    "3\nvar z = 0\n5 % z\n4",
+    "var z = 0\n1\nvar y = 1 / z\n2",
+    "var big = 1000000000000000000000000000000.0\n7.5 % 2\n-7.5 % 2\n-4.0 % 2\n2.5 % 0\n2.5 % (big * big)\n(big * big) % 2.5\n0.0 % 0",
+    "var big = 1000000000000000000000000000000.0\nbig % 0.1\n2147483647 % 0.1\n-big % 3\n0.1 % big",
+  ];
+  for program in programs {
+    assert_matches_interpreter(&parse(program));
+  }
+}
+
+#[test]
+fn traps_only_fail_exports_that_need_them() {
+  let results = run_exports(&parse("3\nvar z = 0\n5 % z\n4\nvar y = 1 / z\ny")).unwrap();
+  assert_eq!(
+    format!("{:?}", results),
+    format!(
+      "{:?}",
+      vec![
+        Ok(Value::Int(3)),
+        Err(RuntimeError::Arith(ArithError::DivisionByZero)),
+        Ok(Value::Int(4)),
+        Err(RuntimeError::Arith(ArithError::DivisionByZero)),
+      ]
+    )
+  );
+}
+
+const NAMES: [&str; 3] = ["a", "b", "c"];
+
+fn expression() -> impl Strategy<Value = Expression> {
+  let leaf = prop_oneof![
+    (0..4).prop_map(ValueExpression::int_value),
+    (0..=i32::MAX).prop_map(ValueExpression::int_value),
+    prop::sample::select(vec![0.0f32, 0.1, 2.5, 1.0e30]).prop_map(ValueExpression::float_value),
+    prop::sample::select(NAMES.to_vec()).prop_map(|name| VariableExpression::expression(name.to_string())),
+  ];
+  leaf.prop_recursive(4, 24, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), inner.clone(), prop::sample::select(BinOp::ALL.to_vec())).prop_map(|(lhs, rhs, op)| Expression::binary(op, lhs, rhs)),
+      inner.prop_map(|operand| Expression::unary(UnOp::Neg, operand)),
+    ]
+  })
+}
+
+//every variable is declared up front so the program always type checks
+fn program() -> impl Strategy<Value = Vec<Statement>> {
+  let statement = prop_oneof![
+    expression().prop_map(Statement::Expression),
+    (prop::sample::select(NAMES.to_vec()), expression()).prop_map(|(name, value)| AssignStmt::statement(
+      VariableExpression::expression(name.to_string()),
+      value,
+      false
+    )),
+  ];
+  (prop::collection::vec(0..100i32, 3), prop::collection::vec(statement, 1..6)).prop_map(|(inits, body)| {
+    let mut ast: Vec<Statement> = NAMES
+      .iter()
+      .zip(inits)
+      .map(|(name, init)| {
+        AssignStmt::statement(
+          VariableExpression::expression(name.to_string()),
+          ValueExpression::int_value(init),
+          false,
+        )
+      })
+      .collect();
+    ast.extend(body);
+    ast
+  })
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(256))]
+
+  #[test]
+  fn random_programs_match_interpreter(ast in program()) {
+    assert_matches_interpreter(&ast);
+  }
+}
@@ -0,0 +1,260 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use cliq::repl::{Repl, Reply};
+use cliq_lexer::source_map::{SourceFile, SourceMap};
+use cliq_lint::config::{Config, CONFIG_FILE};
+use cliq_lint::{Level, Lint, LintError, Linter};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+const USAGE: &str = "usage: cliq <COMMAND>
+
+Commands:
+  repl  evaluate statements interactively, :help inside lists the meta-commands
+  lint  check files for suspicious code, `cliq lint --help` lists the options";
+
+const LINT_USAGE: &str = "usage: cliq lint [--config FILE] [--rules] [--fix] [FILE...]
+
+Checks .cliq files, or stdin when no file is given. Exit code 1 if a lint is an error.
+  --config FILE  rule levels to use, by default cliq-lint.toml in the current directory if there is one
+  --rules        list the rules with their default level
+  --fix          apply the fixes that are certainly right to the files, then check what is left
+
+Levels can be changed for a single statement with a comment on the line above or at its end:
+  // cliq-lint: allow(rule, ...), warn(...) or deny(...)";
+
+fn main() -> ExitCode {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+    ["repl"] => repl(),
+    ["lint", args @ ..] => lint(args),
+    ["-h" | "--help"] => {
+      println!("{}", USAGE);
+      ExitCode::SUCCESS
+    }
+    _ => {
+      eprintln!("{}", USAGE);
+      ExitCode::from(2)
+    }
+  }
+}
+
+fn history_path() -> Option<PathBuf> {
+  std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cliq_history"))
+}
+
+fn repl() -> ExitCode {
+  let mut editor = match DefaultEditor::new() {
+    Ok(editor) => editor,
+    Err(err) => {
+      eprintln!("error: {}", err);
+      return ExitCode::FAILURE;
+    }
+  };
+  let history = history_path();
+  if let Some(path) = &history {
+    //there is no history before the first session
+    let _ = editor.load_history(path);
+  }
+
+  let mut repl = Repl::new();
+  loop {
+    let prompt = if repl.is_pending() { "... " } else { ">>> " };
+    let line = match editor.readline(prompt) {
+      Ok(line) => line,
+      Err(ReadlineError::Interrupted) => {
+        repl.cancel();
+        continue;
+      }
+      Err(ReadlineError::Eof) => break,
+      Err(err) => {
+        eprintln!("error: {}", err);
+        return ExitCode::FAILURE;
+      }
+    };
+    if !line.trim().is_empty() {
+      let _ = editor.add_history_entry(line.as_str());
+    }
+    match repl.feed(&line) {
+      Reply::More => {}
+      Reply::Output(out) if out.is_empty() => {}
+      Reply::Output(out) => println!("{}", out),
+      Reply::Error(msg) => eprintln!("error: {}", msg),
+      Reply::Quit => break,
+    }
+  }
+
+  if let Some(path) = &history {
+    if let Err(err) = editor.save_history(path) {
+      eprintln!("{}: {}", path.display(), err);
+    }
+  }
+  ExitCode::SUCCESS
+}
+
+fn load_config(path: Option<&str>) -> Result<Config, String> {
+  let path = match path {
+    Some(path) => Path::new(path),
+    None if Path::new(CONFIG_FILE).exists() => Path::new(CONFIG_FILE),
+    None => return Ok(Config::default()),
+  };
+  let src = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+  Config::parse(&src).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+//next to the file and renamed over it, so it is never left half written
+fn write_file(path: &Path, src: &str) -> io::Result<()> {
+  let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?;
+  let mut tmp = name.to_os_string();
+  tmp.push(".fix.tmp");
+  let tmp = path.with_file_name(tmp);
+  std::fs::write(&tmp, src)
+    .and_then(|()| {
+      //keep the permissions of the original
+      std::fs::set_permissions(&tmp, std::fs::metadata(path)?.permissions())?;
+      std::fs::rename(&tmp, path)
+    })
+    .inspect_err(|_| {
+      let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+fn print_lint(file: &SourceFile, lint: &Lint) {
+  let (line, col) = file.line_col(lint.range.start);
+  println!("{}:{}:{}: {}[{}]: {}", file.name(), line, col, lint.level, lint.rule, lint.msg);
+  if let Some(fix) = &lint.fix {
+    println!("  help: {}", fix.msg);
+  }
+}
+
+fn lint(args: &[&str]) -> ExitCode {
+  let mut config = None;
+  let mut apply_fixes = false;
+  let mut paths = vec![];
+  let mut iter = args.iter();
+  while let Some(arg) = iter.next() {
+    match *arg {
+      "--config" => match iter.next() {
+        Some(path) => config = Some(*path),
+        None => {
+          eprintln!("--config needs a value");
+          return ExitCode::from(2);
+        }
+      },
+      "--rules" => {
+        for rule in Linter::default().rules() {
+          println!("{:<20} {:<6} {}", rule.name(), rule.default_level().name(), rule.description());
+        }
+        return ExitCode::SUCCESS;
+      }
+      "--fix" => apply_fixes = true,
+      "-h" | "--help" => {
+        println!("{}", LINT_USAGE);
+        return ExitCode::SUCCESS;
+      }
+      _ if arg.starts_with('-') => {
+        eprintln!("unknown option {:?}\n\n{}", arg, LINT_USAGE);
+        return ExitCode::from(2);
+      }
+      path => paths.push(path),
+    }
+  }
+
+  let linter = match load_config(config) {
+    Ok(config) => {
+      let linter = Linter::new(config.clone());
+      for rule in config.unknown_rules(|name| linter.rule(name).is_some()) {
+        eprintln!("warning: unknown lint rule `{}` in the config", rule);
+      }
+      linter
+    }
+    Err(msg) => {
+      eprintln!("{}", msg);
+      return ExitCode::from(2);
+    }
+  };
+
+  if apply_fixes && paths.is_empty() {
+    eprintln!("--fix needs files to write to\n\n{}", LINT_USAGE);
+    return ExitCode::from(2);
+  }
+
+  let mut map = SourceMap::new();
+  let mut files = vec![];
+  let mut failed = false;
+  if apply_fixes {
+    for path in &paths {
+      //files that cannot be read or lexed are reported by the check below
+      let Ok(src) = std::fs::read_to_string(path) else {
+        continue;
+      };
+      let Ok(fixed) = cliq_lint::fix::fix(&src, &linter) else {
+        continue;
+      };
+      if fixed.applied.is_empty() {
+        continue;
+      }
+      match write_file(Path::new(path), &fixed.src) {
+        Ok(()) => eprintln!("{}: applied {} fixes", path, fixed.applied.len()),
+        Err(err) => {
+          eprintln!("{}: {}", path, err);
+          failed = true;
+        }
+      }
+    }
+  }
+  if paths.is_empty() {
+    let mut src = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut src) {
+      eprintln!("<stdin>: {}", err);
+      return ExitCode::from(2);
+    }
+    files.push(map.add_file("<stdin>", &src));
+  }
+  for path in paths {
+    match map.load(path) {
+      Ok(file) => files.push(file),
+      Err(err) => {
+        eprintln!("{}: {}", path, err);
+        failed = true;
+      }
+    }
+  }
+
+  let mut denied = false;
+  for file in files {
+    let file = map.get(file).unwrap();
+    match linter.check(file.src()) {
+      Ok(lints) => {
+        for lint in lints {
+          denied |= lint.level == Level::Deny;
+          print_lint(file, &lint);
+        }
+      }
+      Err(LintError::Syntax(errors)) => {
+        for error in errors {
+          let (line, col) = file.line_col(error.range.start);
+          eprintln!("{}:{}:{}: {}", file.name(), line, col, error.msg);
+          if let Some(fix) = &error.fix {
+            eprintln!("  help: {}", fix.msg);
+          }
+        }
+        failed = true;
+      }
+      Err(LintError::Lex(msg)) => {
+        eprintln!("{}: {}", file.name(), msg);
+        failed = true;
+      }
+    }
+  }
+
+  if failed {
+    ExitCode::from(2)
+  } else if denied {
+    ExitCode::from(1)
+  } else {
+    ExitCode::SUCCESS
+  }
+}
@@ -0,0 +1,238 @@
+use cliq_interp::Interpreter;
+use cliq_lexer::{lexer::Lexer, source_map::SourceMap, token_t::TokenT};
+use cliq_opt::typeck::TypeChecker;
+use cliq_parser::{cst, statement::Statement};
+
+//input goes through the lossless parser and its lowering, the supported front end that cliq-engine, cliq-fmt,
+//cliq-lint and cliq-lsp use too; parser::Parser stays a library api, it accepts input like `1 = 2` that this rejects
+
+pub const HELP: &str = "Statements are evaluated as they are entered, `var` bindings are kept for the whole session.
+Input continues on the next line while a `(` or `{` is left open, ctrl-c drops it.
+  :ast SRC     print the syntax tree of SRC
+  :tokens SRC  print the tokens of SRC
+  :type SRC    print the type of the last expression in SRC without running it
+  :help        show this text
+  :quit        leave the repl, so does ctrl-d";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+  //input is not complete yet, the next line is appended to it
+  More,
+  Output(String),
+  Error(String),
+  Quit,
+}
+
+//everything but the line editing, so a session can be driven from tests
+#[derive(Debug, Default)]
+pub struct Repl {
+  interp: Interpreter,
+  pending: String,
+}
+
+impl Repl {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn interpreter(&self) -> &Interpreter {
+    &self.interp
+  }
+
+  pub fn is_pending(&self) -> bool {
+    !self.pending.is_empty()
+  }
+
+  pub fn cancel(&mut self) {
+    self.pending.clear();
+  }
+
+  pub fn feed(&mut self, line: &str) -> Reply {
+    if self.is_pending() {
+      self.pending.push('\n');
+    }
+    self.pending.push_str(line);
+    let (_, src) = split_command(&self.pending);
+    if is_incomplete(src) {
+      return Reply::More;
+    }
+
+    let input = std::mem::take(&mut self.pending);
+    match split_command(&input) {
+      (None, src) => self.run(src),
+      (Some("ast"), src) => match parse(src) {
+        Ok(ast) => Reply::Output(ast.iter().map(|stmt| format!("{:#?}", stmt)).collect::<Vec<_>>().join("\n")),
+        Err(msg) => Reply::Error(msg),
+      },
+      (Some("tokens"), src) => tokens(src),
+      (Some("type"), src) => self.type_of(src),
+      (Some("help"), _) => Reply::Output(HELP.to_string()),
+      (Some("quit" | "q"), _) => Reply::Quit,
+      (Some(command), _) => Reply::Error(format!("unknown command `:{}`, try :help", command)),
+    }
+  }
+
+  fn run(&mut self, src: &str) -> Reply {
+    let ast = match parse(src) {
+      Ok(ast) => ast,
+      Err(msg) => return Reply::Error(msg),
+    };
+    match self.interp.run(&ast) {
+      Ok(value) => Reply::Output(value.map(|value| value.to_string()).unwrap_or_default()),
+      Err(err) => Reply::Error(err.to_string()),
+    }
+  }
+
+  fn type_of(&self, src: &str) -> Reply {
+    let ast = match parse(src) {
+      Ok(ast) => ast,
+      Err(msg) => return Reply::Error(msg),
+    };
+    let mut checker = TypeChecker::new();
+    for (name, value) in self.interp.globals() {
+      checker.declare(name, value.ty());
+    }
+    match checker.check(&ast) {
+      Ok(Some(ty)) => Reply::Output(ty.to_string()),
+      Ok(None) => Reply::Error("no expression to type".to_string()),
+      Err(err) => Reply::Error(err.to_string()),
+    }
+  }
+}
+
+//`:name rest` is a meta-command applied to rest, anything else is source
+fn split_command(input: &str) -> (Option<&str>, &str) {
+  match input.trim_start().strip_prefix(':') {
+    Some(command) => {
+      let end = command.find(char::is_whitespace).unwrap_or(command.len());
+      (Some(&command[..end]), command[end..].trim_start())
+    }
+    None => (None, input),
+  }
+}
+
+//input that does not lex is complete, running it reports the error
+fn is_incomplete(src: &str) -> bool {
+  let Ok(tokens) = Lexer::new().lex(src) else {
+    return false;
+  };
+  let depth: i32 = tokens
+    .iter()
+    .filter(|token| token.token_t == TokenT::BRACKET)
+    .map(|token| match token.value.as_str() {
+      "(" | "{" => 1,
+      ")" | "}" => -1,
+      _ => 0,
+    })
+    .sum();
+  depth > 0
+}
+
+//syntax errors and out of range literals are reported at their line and column
+fn parse(src: &str) -> Result<Vec<Statement>, String> {
+  cst::parse(src)?.to_ast().map_err(|err| {
+    let (line, col) = line_col(src, err.range.start);
+    format!("{}:{}: {}", line, col, err.msg)
+  })
+}
+
+fn line_col(src: &str, offset: usize) -> (usize, usize) {
+  let mut map = SourceMap::new();
+  let file = map.add_file("<repl>", src);
+  map.get(file).unwrap().line_col(offset)
+}
+
+fn tokens(src: &str) -> Reply {
+  match Lexer::new().lex(src) {
+    Ok(tokens) => Reply::Output(
+      tokens
+        .iter()
+        .filter(|token| token.token_t != TokenT::WHITESPACE)
+        .map(|token| {
+          let (line, col) = line_col(src, token.span.start);
+          format!("{}:{} {:?} {:?}", line, col, token.token_t, token.value)
+        })
+        .collect::<Vec<_>>()
+        .join("\n"),
+    ),
+    Err(msg) => Reply::Error(msg),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn output(text: &str) -> Reply {
+    Reply::Output(text.to_string())
+  }
+
+  #[test]
+  fn test_bindings_persist() {
+    let mut repl = Repl::new();
+    assert_eq!(repl.feed("var a = 6"), output(""));
+    assert_eq!(repl.feed("var b = a / 4.0"), output(""));
+    assert_eq!(repl.feed("a * 7"), output("42"));
+    assert_eq!(repl.feed("b"), output("1.5"));
+    assert_eq!(repl.feed("c"), Reply::Error("undefined variable `c`".to_string()));
+    assert_eq!(repl.feed("a % 0"), Reply::Error("division by zero".to_string()));
+    assert_eq!(repl.feed("a"), output("6"));
+  }
+
+  #[test]
+  fn test_multi_line() {
+    let mut repl = Repl::new();
+    assert_eq!(repl.feed("var a = (1 +"), Reply::More);
+    assert!(repl.is_pending());
+    assert_eq!(repl.feed("  2) * 3"), output(""));
+    assert!(!repl.is_pending());
+    assert_eq!(repl.feed("(a"), Reply::More);
+    assert_eq!(repl.feed(""), Reply::More);
+    assert_eq!(repl.feed(")"), output("9"));
+
+    assert_eq!(repl.feed("{"), Reply::More);
+    repl.cancel();
+    assert_eq!(repl.feed("a"), output("9"));
+  }
+
+  #[test]
+  fn test_errors_do_not_panic() {
+    let mut repl = Repl::new();
+    assert_eq!(
+      repl.feed("(1 + ) )"),
+      Reply::Error("1:6: Expected an expression but got: \")\"".to_string())
+    );
+    assert!(matches!(repl.feed("{ 1 }"), Reply::Error(_)));
+    assert!(matches!(repl.feed("1 $ 2"), Reply::Error(_)));
+    assert_eq!(
+      repl.feed("99999999999"),
+      Reply::Error("1:1: Integer literal out of range".to_string())
+    );
+    assert!(matches!(repl.feed("var x = 99999999999"), Reply::Error(_)));
+    assert!(matches!(repl.feed("1 = 2"), Reply::Error(_)));
+    assert_eq!(repl.feed(":nope"), Reply::Error("unknown command `:nope`, try :help".to_string()));
+  }
+
+  #[test]
+  fn test_meta_commands() {
+    let mut repl = Repl::new();
+    repl.feed("var a = 1");
+    assert_eq!(repl.feed(":type a * 2"), output("int"));
+    assert_eq!(repl.feed(":type a * 2.0"), output("float"));
+    assert_eq!(repl.feed(":type var b = 1"), Reply::Error("no expression to type".to_string()));
+    assert_eq!(repl.feed(":tokens var b = (a"), Reply::More);
+    assert_eq!(
+      repl.feed(")"),
+      output("1:1 VAR \"var\"\n1:5 IDENTIFIER \"b\"\n1:7 OPERATOR \"=\"\n1:9 BRACKET \"(\"\n1:10 IDENTIFIER \"a\"\n2:1 BRACKET \")\"")
+    );
+    //meta-commands never run anything
+    assert_eq!(repl.feed("b"), Reply::Error("undefined variable `b`".to_string()));
+
+    let Reply::Output(ast) = repl.feed(":ast -a") else {
+      panic!("expected a tree");
+    };
+    assert!(ast.starts_with("Expression(\n    Unary {\n        op: Neg,"), "{}", ast);
+    assert_eq!(repl.feed(":help"), output(HELP));
+    assert_eq!(repl.feed(" :quit"), Reply::Quit);
+  }
+}